@@ -0,0 +1,64 @@
+//! A test extension that echoes the query back
+//!
+//! Add it to the rustcast config with
+//!
+//! ```toml
+//! [[extensions]]
+//! name = "echo"
+//! command = "/path/to/target/debug/examples/echo"
+//! ```
+//!
+//! then search for `echo hello` or `echo something`.
+use rustcast_extension::{
+    Extension,
+    protocol::{Command, InitializeResult, Item},
+};
+
+struct Echo {
+    invoked: usize,
+}
+
+impl Extension for Echo {
+    fn initialize(&mut self) -> InitializeResult {
+        InitializeResult {
+            commands: vec![Command {
+                id: "count".to_string(),
+                title: "Echo: show invoke count".to_string(),
+                alias: Some("echo count".to_string()),
+                subtitle: Some("Echo extension".to_string()),
+            }],
+            keywords: vec!["echo".to_string()],
+        }
+    }
+
+    fn search(&mut self, _keyword: &str, query: &str) -> Vec<Item> {
+        vec![
+            Item {
+                id: query.to_string(),
+                title: query.to_string(),
+                subtitle: Some("Echo extension".to_string()),
+            },
+            Item {
+                id: query.to_uppercase(),
+                title: query.to_uppercase(),
+                subtitle: Some("Echo extension, but louder".to_string()),
+            },
+        ]
+    }
+
+    fn action_invoked(&mut self, id: &str, query: &str) {
+        self.invoked += 1;
+        eprintln!(
+            "Invoked {id} with query {query:?} ({} so far)",
+            self.invoked
+        );
+    }
+
+    fn shutdown(&mut self) {
+        eprintln!("Shutting down after {} invocations", self.invoked);
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    rustcast_extension::run(Echo { invoked: 0 })
+}
@@ -0,0 +1,139 @@
+//! A small SDK for writing out-of-process rustcast extensions
+//!
+//! An extension is any executable listed under `[[extensions]]` in the rustcast config. rustcast
+//! spawns it the first time it's needed and talks to it over stdin / stdout (see [`protocol`]).
+//! This crate handles that part, so an extension only has to implement [`Extension`] and call
+//! [`run`]:
+//!
+//! ```no_run
+//! use rustcast_extension::{Extension, protocol::{InitializeResult, Item}};
+//!
+//! struct Hello;
+//!
+//! impl Extension for Hello {
+//!     fn initialize(&mut self) -> InitializeResult {
+//!         InitializeResult {
+//!             keywords: vec!["hello".to_string()],
+//!             ..Default::default()
+//!         }
+//!     }
+//!
+//!     fn search(&mut self, _keyword: &str, query: &str) -> Vec<Item> {
+//!         vec![Item {
+//!             id: query.to_string(),
+//!             title: format!("Hello, {query}!"),
+//!             subtitle: None,
+//!         }]
+//!     }
+//!
+//!     fn action_invoked(&mut self, id: &str, _query: &str) {
+//!         eprintln!("said hello to {id}");
+//!     }
+//! }
+//!
+//! fn main() -> std::io::Result<()> {
+//!     rustcast_extension::run(Hello)
+//! }
+//! ```
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::protocol::{
+    ACTION_INVOKED, ActionInvokedParams, INITIALIZE, INVALID_PARAMS, InitializeResult, Item,
+    METHOD_NOT_FOUND, Request, Response, SEARCH, SHUTDOWN, SearchParams, SearchResult,
+};
+
+pub mod protocol;
+
+/// The callbacks an extension implements
+pub trait Extension {
+    /// Called once after the extension is spawned, returns the commands and keywords to register
+    fn initialize(&mut self) -> InitializeResult;
+
+    /// Called when the query starts with one of the registered keywords
+    ///
+    /// `query` is the rest of the query, after the keyword and whitespace
+    fn search(&mut self, keyword: &str, query: &str) -> Vec<Item> {
+        let _ = (keyword, query);
+        Vec::new()
+    }
+
+    /// Called when the user runs one of the extension's commands or search results
+    fn action_invoked(&mut self, id: &str, query: &str);
+
+    /// Called right before the extension is stopped
+    fn shutdown(&mut self) {}
+}
+
+/// Runs the extension until rustcast shuts it down or closes its stdin
+///
+/// # Errors
+///
+/// Returns an error if reading from stdin or writing to stdout fails
+pub fn run(mut extension: impl Extension) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Ignoring malformed message from rustcast: {e}");
+                continue;
+            }
+        };
+
+        let response = match request.method.as_str() {
+            INITIALIZE => respond(request.id, Ok(extension.initialize())),
+            SEARCH => respond(
+                request.id,
+                parse_params::<SearchParams>(request.params).map(|params| SearchResult {
+                    items: extension.search(&params.keyword, &params.query),
+                }),
+            ),
+            ACTION_INVOKED => {
+                match parse_params::<ActionInvokedParams>(request.params) {
+                    Ok(params) => extension.action_invoked(&params.id, &params.query),
+                    Err((_, message)) => eprintln!("{message}"),
+                }
+                None
+            }
+            SHUTDOWN => {
+                extension.shutdown();
+                return Ok(());
+            }
+            method => request
+                .id
+                .map(|id| Response::err(id, METHOD_NOT_FOUND, format!("Unknown method {method}"))),
+        };
+
+        if let Some(response) = response {
+            serde_json::to_writer(&mut stdout, &response)?;
+            stdout.write_all(b"\n")?;
+            stdout.flush()?;
+        }
+    }
+
+    extension.shutdown();
+    Ok(())
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, format!("Invalid params: {e}")))
+}
+
+/// Builds the response for a request, or nothing if it was sent as a notification
+fn respond<T: Serialize>(id: Option<u64>, result: Result<T, (i64, String)>) -> Option<Response> {
+    let id = id?;
+    Some(match result {
+        Ok(result) => Response::ok(id, serde_json::to_value(result).unwrap_or(Value::Null)),
+        Err((code, message)) => Response::err(id, code, message),
+    })
+}
@@ -0,0 +1,179 @@
+//! The types that rustcast and its extensions send to each other
+//!
+//! Messages are [JSON-RPC 2.0](https://www.jsonrpc.org/specification) objects, one per line, sent
+//! over the extension's stdin (rustcast to extension) and stdout (extension to rustcast). The
+//! extension's stderr is forwarded to rustcast's logs.
+//!
+//! rustcast sends the following:
+//! - [`INITIALIZE`] (request) once after spawning the extension, answered with an
+//!   [`InitializeResult`]
+//! - [`SEARCH`] (request) whenever the query starts with one of the extension's keywords, answered
+//!   with a [`SearchResult`]
+//! - [`ACTION_INVOKED`] (notification) when the user runs one of the extension's commands or search
+//!   results
+//! - [`SHUTDOWN`] (notification) right before rustcast quits or stops the extension
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The JSON-RPC version string
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Sent once after the extension is spawned, with [`InitializeParams`]
+pub const INITIALIZE: &str = "initialize";
+/// Sent when a query uses one of the extension's keywords, with [`SearchParams`]
+pub const SEARCH: &str = "search";
+/// Sent when the user runs a command or search result, with [`ActionInvokedParams`]
+pub const ACTION_INVOKED: &str = "action/invoked";
+/// Sent before the extension is stopped, without any params
+pub const SHUTDOWN: &str = "shutdown";
+
+/// The "method not found" JSON-RPC error code
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The "invalid params" JSON-RPC error code
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC request, or a notification if it has no id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl Request {
+    /// Creates a request that expects a response with the same id
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: Some(id),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Creates a notification, which doesn't get a response
+    pub fn notification(method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: None,
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+/// A JSON-RPC response to a [`Request`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    /// Creates a successful response
+    pub fn ok(id: u64, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Creates an error response
+    pub fn err(id: u64, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+/// A JSON-RPC error object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// The params of [`INITIALIZE`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InitializeParams {
+    /// The version of rustcast that spawned the extension
+    pub rustcast_version: String,
+}
+
+/// The result of [`INITIALIZE`]: everything the extension wants to register
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InitializeResult {
+    /// Commands that show up in the normal search results
+    #[serde(default)]
+    pub commands: Vec<Command>,
+    /// Keywords that send the rest of the query to the extension, e.g. `gh` for `gh rust-lang`
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+/// A command registered by an extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Command {
+    /// The id sent back in [`ActionInvokedParams`] when the command is run
+    pub id: String,
+    /// The name displayed in the search results
+    pub title: String,
+    /// The text used to search for the command, defaults to the lowercased title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// The description displayed under the title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+}
+
+/// The params of [`SEARCH`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchParams {
+    /// The keyword that matched
+    pub keyword: String,
+    /// The rest of the query, after the keyword
+    pub query: String,
+}
+
+/// The result of [`SEARCH`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+/// A single search result returned by an extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    /// The id sent back in [`ActionInvokedParams`] when the result is run
+    pub id: String,
+    /// The name displayed in the search results
+    pub title: String,
+    /// The description displayed under the title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+}
+
+/// The params of [`ACTION_INVOKED`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionInvokedParams {
+    /// The id of the [`Command`] or [`Item`] that was run
+    pub id: String,
+    /// The full query at the time it was run
+    pub query: String,
+}
@@ -0,0 +1,138 @@
+#!/usr/bin/env python3
+
+# Smol MCP server for trying out rustcast's MCP support, without any dependencies
+#
+# Add it to the config with:
+#
+#   [[mcp_servers]]
+#   name = "mock"
+#   command = "python3"
+#   args = ["/path/to/rustcast/scripts/mock-mcp-server.py"]
+
+import base64
+import json
+import sys
+import zlib
+
+TOOLS = [
+    {
+        "name": "echo",
+        "title": "Echo",
+        "description": "Repeats some text",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "text": {"type": "string", "description": "What to repeat"},
+                "times": {"type": "integer", "default": 1},
+                "shout": {"type": "boolean"},
+            },
+            "required": ["text"],
+        },
+    },
+    {
+        "name": "add",
+        "title": "Add numbers",
+        "description": "Adds two numbers",
+        "inputSchema": {
+            "type": "object",
+            "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+            "required": ["a", "b"],
+        },
+    },
+    {
+        "name": "color",
+        "title": "Color swatch",
+        "description": "Returns a small image of a color",
+        "inputSchema": {
+            "type": "object",
+            "properties": {"color": {"type": "string", "enum": ["red", "green", "blue"]}},
+            "required": ["color"],
+        },
+    },
+    {
+        "name": "readme",
+        "title": "Mock readme",
+        "description": "Returns an embedded resource, and takes no arguments",
+        "inputSchema": {"type": "object", "properties": {}},
+    },
+    {
+        "name": "fail",
+        "title": "Always fail",
+        "description": "Reports an error",
+        "inputSchema": {"type": "object", "properties": {}},
+    },
+]
+
+COLORS = {"red": (255, 0, 0), "green": (0, 160, 0), "blue": (0, 0, 255)}
+
+
+def png(rgb, size=32):
+    """Encodes a square of a single color as a PNG"""
+
+    def chunk(kind, data):
+        body = kind + data
+        return len(data).to_bytes(4, "big") + body + zlib.crc32(body).to_bytes(4, "big")
+
+    row = b"\x00" + bytes(rgb) * size
+    header = size.to_bytes(4, "big") * 2 + bytes([8, 2, 0, 0, 0])
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", header)
+        + chunk(b"IDAT", zlib.compress(row * size))
+        + chunk(b"IEND", b"")
+    )
+
+
+def text(value):
+    return {"content": [{"type": "text", "text": value}]}
+
+
+def call_tool(name, args):
+    if name == "echo":
+        value = args["text"].upper() if args.get("shout") else args["text"]
+        return text(" ".join([value] * args.get("times", 1)))
+    if name == "add":
+        return text(str(args["a"] + args["b"]))
+    if name == "color":
+        data = base64.b64encode(png(COLORS[args["color"]])).decode()
+        return {"content": [{"type": "image", "data": data, "mimeType": "image/png"}]}
+    if name == "readme":
+        resource = {"uri": "mock://readme", "mimeType": "text/plain", "text": "Hello from the mock server"}
+        return {"content": [{"type": "resource", "resource": resource}]}
+    if name == "fail":
+        return {"content": [{"type": "text", "text": "Something went wrong"}], "isError": True}
+    raise KeyError(name)
+
+
+def handle(request):
+    method = request["method"]
+    params = request.get("params") or {}
+
+    if method == "initialize":
+        return {
+            "protocolVersion": params.get("protocolVersion", "2025-06-18"),
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "mock", "version": "0.1.0"},
+        }
+    if method == "tools/list":
+        return {"tools": TOOLS}
+    if method == "tools/call":
+        return call_tool(params["name"], params.get("arguments") or {})
+    raise NotImplementedError(method)
+
+
+for line in sys.stdin:
+    request = json.loads(line)
+    if "id" not in request:
+        continue
+
+    response = {"jsonrpc": "2.0", "id": request["id"]}
+    try:
+        response["result"] = handle(request)
+    except NotImplementedError as e:
+        response["error"] = {"code": -32601, "message": f"Unknown method {e}"}
+    except (KeyError, TypeError, ValueError) as e:
+        response["error"] = {"code": -32602, "message": f"Invalid params: {e}"}
+
+    print(json.dumps(response), flush=True)
+    print(f"handled {request['method']}", file=sys.stderr, flush=True)
@@ -3,7 +3,13 @@
 use crate::commands::Function;
 use iced::window::{self, Id, Settings};
 
-use crate::{app::tile::ExtSender, functions::clipboard::ClipBoardContentType};
+use crate::{
+    app::{apps::SimpleApp, tile::ExtSender},
+    bookmarks::Bookmark,
+    functions::clipboard::ClipBoardContentType,
+    mcp::ToolOutput,
+    unit_conversion::currency::Rates,
+};
 
 pub mod apps;
 pub mod menubar;
@@ -24,7 +30,10 @@ pub const RUSTCAST_DESC_NAME: &str = "Utility";
 pub enum Page {
     Main,
     ClipboardHistory,
+    CalculatorHistory,
+    Clocks,
     EmojiSearch,
+    McpTool,
 }
 
 /// The types of arrow keys
@@ -54,6 +63,8 @@ pub enum Message {
     FocusTextInput(Move),
     HideWindow(Id),
     RunFunction(Function),
+    ConfirmPendingFunction,
+    CancelPendingFunction,
     OpenFocused,
     ReturnFocus,
     OpenToPage(Page),
@@ -67,6 +78,17 @@ pub enum Message {
     SwitchToPage(Page),
     ClipboardHistory(ClipBoardContentType),
     ChangeFocus(ArrowKey),
+    ExtensionsStarted(Vec<(String, Vec<SimpleApp>)>),
+    ExtensionResults(String, Vec<SimpleApp>),
+    OpenMcpTool(String, String),
+    McpFieldChanged(usize, String),
+    SubmitMcpTool,
+    McpToolFinished(String, Result<ToolOutput, String>),
+    SwitchMode(String),
+    BookmarksLoaded(Vec<Bookmark>),
+    RatesLoaded(Rates),
+    ReuseCalculation(String),
+    RefreshClocks,
 }
 
 /// The window settings for rustcast
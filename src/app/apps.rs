@@ -63,6 +63,8 @@ pub enum AppData {
     Builtin {
         /// The [`AppCommand`] to run
         command: AppCommand,
+        /// The icon to display in search results, instead of rustcast's
+        icon: Option<iced::widget::image::Handle>,
     },
 }
 
@@ -85,6 +87,9 @@ pub struct SimpleApp {
     /// The information specific to a certain type of app
     pub data: AppData,
 
+    /// Whether the name is shown dimmed, for rows that are hints rather than results
+    pub dimmed: bool,
+
     /// A unique ID generated for each instance of an App.
     #[allow(unused)]
     id: usize,
@@ -113,6 +118,7 @@ impl SimpleApp {
             desc: desc.to_string(),
             id: ID.fetch_add(1, Ordering::Relaxed),
             data,
+            dimmed: false,
         }
     }
 
@@ -120,7 +126,34 @@ impl SimpleApp {
     ///
     /// This is mainly for convenience.
     pub fn new_builtin(name: &str, name_lc: &str, desc: &str, command: AppCommand) -> Self {
-        Self::new(name, name_lc, desc, AppData::Builtin { command })
+        Self::new(
+            name,
+            name_lc,
+            desc,
+            AppData::Builtin {
+                command,
+                icon: None,
+            },
+        )
+    }
+
+    /// Creates a dimmed row that only displays something, like why a calculation has no result
+    pub fn new_hint(name: &str, desc: &str) -> Self {
+        Self {
+            dimmed: true,
+            ..Self::new_builtin(name, "", desc, AppCommand::Display)
+        }
+    }
+
+    /// Creates a new instance of the type [`AppData::Builtin`], with its own icon.
+    pub fn new_builtin_with_icon(
+        name: &str,
+        name_lc: &str,
+        desc: &str,
+        command: AppCommand,
+        icon: Option<widget::image::Handle>,
+    ) -> Self {
+        Self::new(name, name_lc, desc, AppData::Builtin { command, icon })
     }
 
     /// Creates a new instance of the type [`AppData::Executable`].
@@ -189,6 +222,18 @@ impl SimpleApp {
                 RUSTCAST_DESC_NAME,
                 AppCommand::Message(Message::SwitchToPage(Page::ClipboardHistory)),
             ),
+            Self::new_builtin(
+                "Calculator History",
+                "calculator history",
+                RUSTCAST_DESC_NAME,
+                AppCommand::Message(Message::SwitchToPage(Page::CalculatorHistory)),
+            ),
+            Self::new_builtin(
+                "Clocks",
+                "clocks",
+                RUSTCAST_DESC_NAME,
+                AppCommand::Message(Message::SwitchToPage(Page::Clocks)),
+            ),
             Self::new_builtin(
                 "Reload RustCast",
                 "refresh",
@@ -231,7 +276,7 @@ impl SimpleApp {
                     .font(theme.font())
                     .size(16)
                     .wrapping(Wrapping::WordOrGlyph)
-                    .color(theme.text_color(1.0)),
+                    .color(theme.text_color(if self.dimmed { 0.55 } else { 1.0 })),
             )
             .push(
                 Text::new(self.desc)
@@ -255,6 +300,10 @@ impl SimpleApp {
                 | AppData::Executable {
                     icon: Some(ref icon),
                     ..
+                }
+                | AppData::Builtin {
+                    icon: Some(ref icon),
+                    ..
                 } => {
                     row = row.push(
                         container(Viewer::new(icon).height(40).width(40))
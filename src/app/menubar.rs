@@ -8,7 +8,10 @@ use tokio::runtime::Runtime;
 use tray_icon::menu::accelerator::Accelerator;
 use tray_icon::{
     Icon, TrayIcon, TrayIconBuilder,
-    menu::{AboutMetadataBuilder, Icon as Ico, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    menu::{
+        AboutMetadataBuilder, CheckMenuItem, Icon as Ico, Menu, MenuEvent, MenuItem,
+        PredefinedMenuItem, Submenu,
+    },
 };
 
 use crate::{
@@ -16,8 +19,16 @@ use crate::{
     platform::open_settings,
 };
 
+/// The prefix of the ids of the mode menu items, followed by the mode's name
+const MODE_ID_PREFIX: &str = "mode:";
+
 /// This creates a new menubar icon for the app
-pub fn menu_icon(#[cfg(not(target_os = "linux"))] hotkey: HotKey, sender: ExtSender) -> TrayIcon {
+pub fn menu_icon(
+    #[cfg(not(target_os = "linux"))] hotkey: HotKey,
+    sender: ExtSender,
+    modes: &[String],
+    active_mode: &str,
+) -> TrayIcon {
     let builder = TrayIconBuilder::new();
 
     let image = get_image();
@@ -25,11 +36,51 @@ pub fn menu_icon(#[cfg(not(target_os = "linux"))] hotkey: HotKey, sender: ExtSen
 
     init_event_handler(sender);
 
-    let menu = Menu::with_items(&[
+    let menu = menu(
+        #[cfg(not(target_os = "linux"))]
+        hotkey,
+        &image,
+        modes,
+        active_mode,
+    );
+
+    builder
+        .with_icon(icon)
+        .with_menu(Box::new(menu))
+        .build()
+        .unwrap()
+}
+
+/// Rebuilds the menu, so that it shows the modes and which one is active
+pub fn update_modes(
+    tray_icon: &TrayIcon,
+    #[cfg(not(target_os = "linux"))] hotkey: HotKey,
+    modes: &[String],
+    active_mode: &str,
+) {
+    let menu = menu(
+        #[cfg(not(target_os = "linux"))]
+        hotkey,
+        &get_image(),
+        modes,
+        active_mode,
+    );
+    tray_icon.set_menu(Some(Box::new(menu)));
+}
+
+fn menu(
+    #[cfg(not(target_os = "linux"))] hotkey: HotKey,
+    image: &DynamicImage,
+    modes: &[String],
+    active_mode: &str,
+) -> Menu {
+    Menu::with_items(&[
         &version_item(),
-        &about_item(&image),
+        &about_item(image),
         &open_github_item(),
         &PredefinedMenuItem::separator(),
+        &modes_item(modes, active_mode),
+        &PredefinedMenuItem::separator(),
         &refresh_item(),
         &open_item(
             #[cfg(not(target_os = "linux"))]
@@ -44,13 +95,7 @@ pub fn menu_icon(#[cfg(not(target_os = "linux"))] hotkey: HotKey, sender: ExtSen
         &hide_tray_icon(),
         &quit_item(),
     ])
-    .unwrap();
-
-    builder
-        .with_icon(icon)
-        .with_menu(Box::new(menu))
-        .build()
-        .unwrap()
+    .unwrap()
 }
 
 fn get_image() -> DynamicImage {
@@ -85,6 +130,13 @@ fn init_event_handler(sender: ExtSender) {
     MenuEvent::set_event_handler(Some(move |x: MenuEvent| {
         let sender = sender.clone();
         let sender = sender.0.clone();
+        if let Some(mode) = x.id().0.strip_prefix(MODE_ID_PREFIX) {
+            let mode = mode.to_string();
+            runtime.spawn(async move {
+                sender.clone().try_send(Message::SwitchMode(mode)).unwrap();
+            });
+            return;
+        }
         match x.id().0.as_str() {
             "refresh_rustcast" => {
                 runtime.spawn(async move {
@@ -133,6 +185,25 @@ fn version_item() -> MenuItem {
     MenuItem::new(version, false, None)
 }
 
+/// A submenu with a check item for each mode, where only the active one is checked
+///
+/// Check items toggle themselves when clicked, so the menu has to be rebuilt after every click
+/// (see [`update_modes`]) for it to behave like a radio group
+fn modes_item(modes: &[String], active_mode: &str) -> Submenu {
+    let submenu = Submenu::new("Mode", true);
+    for mode in modes {
+        let item = CheckMenuItem::with_id(
+            format!("{MODE_ID_PREFIX}{mode}"),
+            mode,
+            true,
+            mode == active_mode,
+            None,
+        );
+        submenu.append(&item).unwrap();
+    }
+    submenu
+}
+
 fn discord_item() -> MenuItem {
     MenuItem::with_id("open_discord", "RustCast discord", true, None)
 }
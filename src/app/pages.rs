@@ -1,5 +1,9 @@
+pub mod calc_history;
 pub mod clipboard;
+pub mod clocks;
 pub mod common;
 pub mod emoji;
+pub mod mcp;
+pub mod plot;
 pub mod prelude;
 pub mod settings;
@@ -0,0 +1,88 @@
+use iced::widget::scrollable;
+
+use crate::{
+    app::pages::prelude::*,
+    commands::Function,
+    functions::{calc_history::HistoryEntry, clipboard::ClipBoardContentType},
+    styles::result_button_style,
+};
+
+pub fn calc_history_view(
+    entries: &[HistoryEntry],
+    theme: &Theme,
+    focus_id: u32,
+) -> Element<'static, Message> {
+    let theme_clone = theme.clone();
+    let theme_clone_2 = theme.clone();
+
+    let details: Element<'static, Message> = match entries.get(focus_id as usize) {
+        Some(entry) => {
+            let copy_theme = theme.clone();
+            let reuse_theme = theme.clone();
+            Column::new()
+                .push(
+                    Text::new(entry.expression.clone())
+                        .font(theme.font())
+                        .size(16)
+                        .color(theme.text_color(0.7)),
+                )
+                .push(
+                    Text::new(format!("= {}", entry.result))
+                        .font(theme.font())
+                        .size(22)
+                        .color(theme.text_color(1.)),
+                )
+                .push(
+                    Row::new()
+                        .push(
+                            Button::new(Text::new("Copy result").font(theme.font()).size(14))
+                                .on_press(Message::RunFunction(Function::CopyToClipboard(
+                                    ClipBoardContentType::Text(entry.result.clone()),
+                                )))
+                                .style(move |_, _| result_button_style(&copy_theme)),
+                        )
+                        .push(
+                            Button::new(Text::new("Use again").font(theme.font()).size(14))
+                                .on_press(Message::ReuseCalculation(entry.expression.clone()))
+                                .style(move |_, _| result_button_style(&reuse_theme)),
+                        )
+                        .spacing(10),
+                )
+                .spacing(10)
+                .into()
+        }
+        None => Text::new("No calculations yet")
+            .font(theme.font())
+            .size(16)
+            .color(theme.text_color(0.7))
+            .into(),
+    };
+
+    container(Row::from_vec(vec![
+        container(
+            scrollable(
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        #[allow(clippy::cast_possible_truncation)]
+                        entry.to_app().render(theme.clone(), i as u32, focus_id)
+                    })
+                    .collect::<Column<_>>()
+                    .width(WINDOW_WIDTH / 3.),
+            )
+            .id("results"),
+        )
+        .height(385)
+        .style(move |_| result_row_container_style(&theme_clone_2, false))
+        .into(),
+        container(details)
+            .padding(10)
+            .height(385)
+            .style(move |_| result_row_container_style(&theme_clone, false))
+            .width((WINDOW_WIDTH / 3.) * 2.)
+            .into(),
+    ]))
+    .height(280)
+    .into()
+}
@@ -0,0 +1,62 @@
+use chrono::Utc;
+use iced::widget::scrollable;
+
+use crate::{
+    app::{apps::AppCommand, pages::prelude::*},
+    commands::Function,
+    functions::{
+        clipboard::ClipBoardContentType,
+        datetime::{Clock, DateRow, clock_rows},
+    },
+};
+
+/// The current time in each time zone of the world clock
+pub fn world_clock_rows(world_clock: &[String]) -> Vec<DateRow> {
+    clock_rows(Utc::now(), &Clock::system(world_clock))
+}
+
+pub fn clocks_view(
+    world_clock: &[String],
+    theme: &Theme,
+    focus_id: u32,
+) -> Element<'static, Message> {
+    let theme_clone = theme.clone();
+
+    let rows: Vec<SimpleApp> = if world_clock.is_empty() {
+        vec![SimpleApp::new_hint(
+            "No clocks yet",
+            "Add cities or time zones to `world_clock` in your config",
+        )]
+    } else {
+        world_clock_rows(world_clock)
+            .into_iter()
+            .map(|row| {
+                SimpleApp::new_builtin(
+                    &row.text,
+                    "",
+                    &row.desc,
+                    AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                        row.text.clone(),
+                    ))),
+                )
+            })
+            .collect()
+    };
+
+    container(
+        scrollable(
+            rows.into_iter()
+                .enumerate()
+                .map(|(i, app)| {
+                    #[allow(clippy::cast_possible_truncation)]
+                    app.render(theme.clone(), i as u32, focus_id)
+                })
+                .collect::<Column<_>>()
+                .width(WINDOW_WIDTH),
+        )
+        .id("results"),
+    )
+    .height(385)
+    .style(move |_| result_row_container_style(&theme_clone, false))
+    .into()
+}
@@ -0,0 +1,205 @@
+use iced::widget::{checkbox, image, pick_list, text_input};
+
+use crate::{
+    app::pages::prelude::*,
+    commands::Function,
+    functions::clipboard::ClipBoardContentType,
+    mcp::{
+        OutputBlock, Tool, ToolOutput,
+        schema::{FieldKind, FormField},
+    },
+    styles::{result_button_style, rustcast_text_input_style},
+};
+
+/// The MCP tool that is open, and what the user has done with it so far
+#[derive(Debug, Clone)]
+pub struct McpToolView {
+    pub server: String,
+    pub tool: Tool,
+    pub fields: Vec<FormField>,
+    pub state: ToolState,
+}
+
+/// Where running an MCP tool is at
+#[derive(Debug, Clone)]
+pub enum ToolState {
+    /// Filling in the form, with the error of the last submit if the arguments were invalid
+    Editing(Option<String>),
+    Running,
+    Done(ToolOutput),
+    Failed(String),
+}
+
+/// The id of the text input for the nth field
+pub fn field_id(index: usize) -> String {
+    format!("mcp-field-{index}")
+}
+
+pub fn mcp_tool_view(view: &McpToolView, theme: &Theme) -> Element<'static, Message> {
+    let mut column = Column::new().spacing(10).padding(15).width(Length::Fill);
+
+    column = column.push(
+        Text::new(view.tool.display_name().to_string())
+            .font(theme.font())
+            .size(18)
+            .color(theme.text_color(1.)),
+    );
+    if let Some(description) = &view.tool.description {
+        column = column.push(
+            Text::new(description.clone())
+                .font(theme.font())
+                .size(13)
+                .color(theme.text_color(0.55)),
+        );
+    }
+
+    for (i, field) in view.fields.iter().enumerate() {
+        column = column.push(field_view(i, field, theme));
+    }
+
+    let editable = !matches!(view.state, ToolState::Running);
+    let theme_clone = theme.clone();
+    column = column.push(
+        Button::new(Text::new("Run").font(theme.font()).size(14))
+            .on_press_maybe(editable.then_some(Message::SubmitMcpTool))
+            .style(move |_, _| result_button_style(&theme_clone)),
+    );
+
+    match &view.state {
+        ToolState::Editing(None) => {}
+        ToolState::Editing(Some(error)) | ToolState::Failed(error) => {
+            column = column.push(
+                Text::new(error.clone())
+                    .font(theme.font())
+                    .size(14)
+                    .color(theme.text_color(0.7)),
+            );
+        }
+        ToolState::Running => {
+            column = column.push(
+                Text::new("Running…")
+                    .font(theme.font())
+                    .size(14)
+                    .color(theme.text_color(0.55)),
+            );
+        }
+        ToolState::Done(output) => column = column.push(output_view(output, theme)),
+    }
+
+    let theme_clone = theme.clone();
+    container(column)
+        .width(WINDOW_WIDTH)
+        .style(move |_| result_row_container_style(&theme_clone, false))
+        .into()
+}
+
+fn field_view(index: usize, field: &FormField, theme: &Theme) -> Element<'static, Message> {
+    let label = if field.required {
+        format!("{} *", field.name)
+    } else {
+        field.name.clone()
+    };
+
+    let mut column = Column::new().spacing(4).push(
+        Text::new(label)
+            .font(theme.font())
+            .size(14)
+            .color(theme.text_color(1.)),
+    );
+    if let Some(description) = &field.description {
+        column = column.push(
+            Text::new(description.clone())
+                .font(theme.font())
+                .size(12)
+                .color(theme.text_color(0.55)),
+        );
+    }
+
+    let theme_clone = theme.clone();
+    let input: Element<'static, Message> = match &field.kind {
+        FieldKind::Boolean => checkbox(field.value == "true")
+            .on_toggle(move |checked| Message::McpFieldChanged(index, checked.to_string()))
+            .into(),
+        FieldKind::Enum(options) => {
+            let selected = options
+                .iter()
+                .find(|option| **option == field.value)
+                .cloned();
+            pick_list(options.clone(), selected, move |option| {
+                Message::McpFieldChanged(index, option)
+            })
+            .font(theme.font())
+            .width(Length::Fill)
+            .into()
+        }
+        _ => text_input(field.placeholder(), &field.value)
+            .id(field_id(index))
+            .on_input(move |value| Message::McpFieldChanged(index, value))
+            .on_submit(Message::SubmitMcpTool)
+            .font(theme.font())
+            .padding(8)
+            .style(move |_, _| rustcast_text_input_style(&theme_clone, true))
+            .into(),
+    };
+
+    column.push(input).into()
+}
+
+fn output_view(output: &ToolOutput, theme: &Theme) -> Element<'static, Message> {
+    let mut column = Column::new().spacing(10);
+
+    if output.is_error {
+        column = column.push(
+            Text::new("The tool reported an error")
+                .font(theme.font())
+                .size(14)
+                .color(theme.text_color(0.7)),
+        );
+    }
+    if output.blocks.is_empty() {
+        column = column.push(
+            Text::new("The tool didn't return anything")
+                .font(theme.font())
+                .size(14)
+                .color(theme.text_color(0.55)),
+        );
+    }
+
+    for block in &output.blocks {
+        let element: Element<'static, Message> = match block {
+            OutputBlock::Text(text) => copyable_text(text.clone(), theme),
+            OutputBlock::Image(handle) => image(handle.clone()).width(Length::Fill).into(),
+            OutputBlock::Resource { uri, text } => {
+                let mut resource = Column::new().spacing(4).push(
+                    Text::new(uri.clone())
+                        .font(theme.font())
+                        .size(12)
+                        .color(theme.text_color(0.55)),
+                );
+                if let Some(text) = text {
+                    resource = resource.push(copyable_text(text.clone(), theme));
+                }
+                resource.into()
+            }
+        };
+        column = column.push(element);
+    }
+
+    column.into()
+}
+
+/// Text that copies itself when clicked
+fn copyable_text(text: String, theme: &Theme) -> Element<'static, Message> {
+    let theme_clone = theme.clone();
+    Button::new(
+        Text::new(text.clone())
+            .font(theme.font())
+            .size(14)
+            .color(theme.text_color(1.)),
+    )
+    .on_press(Message::RunFunction(Function::CopyToClipboard(
+        ClipBoardContentType::Text(text),
+    )))
+    .style(move |_, _| result_button_style(&theme_clone))
+    .into()
+}
@@ -0,0 +1,170 @@
+//! The chart of a plot (`plot sin(x)/x from -10 to 10`), which is shown above its results
+use iced::{
+    Color, Point, Rectangle, Renderer, mouse,
+    widget::canvas::{self, Action, Frame, Geometry, Path, Stroke, Text},
+};
+
+use crate::{app::pages::prelude::*, functions::plot::PLOT_COLORS, functions::plot::Plot};
+
+/// The height of the chart
+pub const PLOT_HEIGHT: f32 = 220.;
+
+/// Room left around the curves for the labels
+const MARGIN: f32 = 12.;
+
+pub fn plot_view(plot: &Plot, theme: &Theme) -> Element<'static, Message> {
+    container(
+        iced::widget::canvas(Chart {
+            plot: plot.clone(),
+            theme: theme.clone(),
+        })
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .padding([4, 16])
+    .width(WINDOW_WIDTH)
+    .height(PLOT_HEIGHT)
+    .into()
+}
+
+struct Chart {
+    plot: Plot,
+    theme: Theme,
+}
+
+impl canvas::Program<Message> for Chart {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        _bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Option<Action<Message>> {
+        // The value readout follows the cursor
+        match event {
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) => {
+                Some(Action::request_redraw())
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &iced::Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (width, height) = (bounds.width, bounds.height - 2. * MARGIN);
+        let at = |point: (f64, f64)| {
+            let (x, y) = self.plot.to_screen(point, width, height);
+            Point::new(x, y + MARGIN)
+        };
+
+        let axis_color = self.theme.text_color(0.35);
+        let (axis_x, axis_y) = self.plot.axes(width, height);
+        frame.stroke(
+            &Path::line(Point::new(axis_x, 0.), Point::new(axis_x, bounds.height)),
+            Stroke::default().with_color(axis_color).with_width(1.),
+        );
+        frame.stroke(
+            &Path::line(
+                Point::new(0., axis_y + MARGIN),
+                Point::new(width, axis_y + MARGIN),
+            ),
+            Stroke::default().with_color(axis_color).with_width(1.),
+        );
+
+        let (x0, x1) = self.plot.x_range;
+        let (y0, y1) = self.plot.y_range;
+        let labels = [
+            (self.plot.format(y1), Point::new(axis_x + 4., 0.)),
+            (
+                self.plot.format(y0),
+                Point::new(axis_x + 4., bounds.height - MARGIN),
+            ),
+            (self.plot.format(x0), Point::new(0., axis_y + MARGIN + 2.)),
+            (
+                self.plot.format(x1),
+                Point::new(width - 40., axis_y + MARGIN + 2.),
+            ),
+        ];
+        for (content, position) in labels {
+            frame.fill_text(self.label(content, position, 0.55));
+        }
+
+        for (curve, [r, g, b]) in self.plot.curves.iter().zip(PLOT_COLORS) {
+            let path = Path::new(|builder| {
+                for segment in self.plot.segments(curve) {
+                    builder.move_to(at(segment[0]));
+                    for point in &segment[1..] {
+                        builder.line_to(at(*point));
+                    }
+                }
+            });
+            frame.with_clip(Rectangle::with_size(bounds.size()), |frame| {
+                frame.stroke(
+                    &path,
+                    Stroke::default()
+                        .with_color(Color::from_rgb8(r, g, b))
+                        .with_width(2.),
+                );
+            });
+        }
+
+        if let Some(position) = cursor.position_in(bounds) {
+            let x = self.plot.x_at(position.x, width);
+            frame.stroke(
+                &Path::line(
+                    Point::new(position.x, 0.),
+                    Point::new(position.x, bounds.height),
+                ),
+                Stroke::default().with_color(axis_color).with_width(1.),
+            );
+            let mut readout = vec![format!("{} = {}", self.plot.variable, self.plot.format(x))];
+            for curve in &self.plot.curves {
+                let value = self
+                    .plot
+                    .value_at(curve, x)
+                    .map_or("undefined".to_string(), |y| self.plot.format(y));
+                readout.push(format!("{} = {value}", curve.expression));
+            }
+            // The readout is on the side of the cursor that has more room
+            let left = if position.x > width / 2. {
+                8.
+            } else {
+                width / 2.
+            };
+            for (i, line) in readout.into_iter().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let position = Point::new(left, 16. * i as f32);
+                frame.fill_text(self.label(line, position, 0.9));
+            }
+            for (curve, [r, g, b]) in self.plot.curves.iter().zip(PLOT_COLORS) {
+                if let Some(y) = self.plot.value_at(curve, x) {
+                    frame.fill(&Path::circle(at((x, y)), 3.5), Color::from_rgb8(r, g, b));
+                }
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+impl Chart {
+    fn label(&self, content: String, position: Point, opacity: f32) -> Text {
+        Text {
+            content,
+            position,
+            color: self.theme.text_color(opacity),
+            size: 12.into(),
+            font: self.theme.font(),
+            ..Text::default()
+        }
+    }
+}
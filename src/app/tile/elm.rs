@@ -16,12 +16,22 @@ use rayon::slice::ParallelSliceMut;
 #[cfg(target_os = "windows")]
 use crate::app;
 use crate::app::WINDOW_WIDTH;
+use crate::app::pages::calc_history::calc_history_view;
 use crate::app::pages::clipboard::clipboard_view;
+use crate::app::pages::clocks::clocks_view;
 use crate::app::pages::emoji::emoji_page;
+use crate::app::pages::mcp::mcp_tool_view;
+use crate::app::pages::plot::plot_view;
 use crate::app::tile::AppIndex;
 use crate::app_finding::index_installed_apps;
 use crate::config::Theme;
+use crate::extensions::ExtensionHost;
+use crate::functions::calc_history::CalcHistory;
+use crate::mcp::McpHost;
+use crate::modes::{self, DEFAULT_MODE};
 use crate::styles::{contents_style, rustcast_text_input_style, tint, with_alpha};
+use crate::unit_conversion::currency::Rates;
+use crate::wasm_extensions::WasmHost;
 use crate::{
     app::{Message, Page, apps::SimpleApp, default_settings, tile::Tile},
     config::Config,
@@ -123,6 +133,13 @@ pub fn new(
 
     options.extend(config.shells.iter().map(crate::config::Shelly::to_app));
     options.extend(SimpleApp::basic_apps());
+
+    let active_mode = modes::load_active(config);
+    options.extend(modes::mode_apps(config, &active_mode));
+
+    let wasm_extensions = WasmHost::discover();
+    options.extend(wasm_extensions.install_apps());
+
     options.par_sort_by_key(|x| x.name.len());
     let options = AppIndex::from_apps(options);
 
@@ -142,6 +159,17 @@ pub fn new(
             tray_icon: None,
             sender: None,
             page: Page::Main,
+            pending_confirmation: None,
+            extensions: ExtensionHost::new(&config.extensions),
+            extension_apps: vec![],
+            wasm_extensions,
+            mcp: McpHost::new(&config.mcp_servers),
+            mcp_tool: None,
+            active_mode,
+            bookmarks: vec![],
+            calc_history: CalcHistory::load(),
+            rates: Rates::default(),
+            plot: None,
 
             #[cfg(target_os = "macos")]
             frontmost: None,
@@ -171,6 +199,8 @@ pub fn view(tile: &Tile, wid: window::Id) -> Element<'_, Message> {
         let round_bottom_edges = match &tile.page {
             Page::Main | Page::EmojiSearch => tile.results.is_empty(),
             Page::ClipboardHistory => tile.clipboard_content.is_empty(),
+            Page::CalculatorHistory | Page::Clocks => false,
+            Page::McpTool => tile.mcp_tool.is_none(),
         };
         let title_input = text_input(tile.config.placeholder.as_str(), &tile.query)
             .on_input(move |a| Message::SearchQueryChanged(a, wid))
@@ -201,6 +231,19 @@ pub fn view(tile: &Tile, wid: window::Id) -> Element<'_, Message> {
                 &tile.config.theme,
                 tile.focus_id,
             )
+        } else if tile.page == Page::CalculatorHistory {
+            calc_history_view(
+                &tile.calc_history.entries,
+                &tile.config.theme,
+                tile.focus_id,
+            )
+        } else if tile.page == Page::Clocks {
+            clocks_view(&tile.config.world_clock, &tile.config.theme, tile.focus_id)
+        } else if tile.page == Page::McpTool {
+            match &tile.mcp_tool {
+                Some(view) => mcp_tool_view(view, &tile.config.theme),
+                None => space().into(),
+            }
         } else if tile.results.is_empty() {
             space().into()
         } else if tile.page == Page::EmojiSearch {
@@ -228,10 +271,16 @@ pub fn view(tile: &Tile, wid: window::Id) -> Element<'_, Message> {
 
         let results_count = match &tile.page {
             Page::ClipboardHistory => tile.clipboard_content.len(),
+            Page::CalculatorHistory => tile.calc_history.entries.len(),
+            Page::Clocks => tile.config.world_clock.len(),
             Page::Main | Page::EmojiSearch => tile.results.len(),
+            Page::McpTool => 0,
         };
 
-        let height = if tile.page == Page::ClipboardHistory {
+        let height = if matches!(
+            tile.page,
+            Page::ClipboardHistory | Page::CalculatorHistory | Page::Clocks | Page::McpTool
+        ) {
             385
         } else {
             std::cmp::min(tile.results.len() * 60, 290)
@@ -245,8 +294,18 @@ pub fn view(tile: &Tile, wid: window::Id) -> Element<'_, Message> {
         let contents = container(
             Column::new()
                 .push(title_input)
+                .push(
+                    tile.plot
+                        .as_ref()
+                        .filter(|_| tile.page == Page::Main)
+                        .map(|plot| plot_view(plot, &tile.config.theme)),
+                )
                 .push(scrollable)
-                .push(footer(tile.config.theme.clone(), results_count))
+                .push(footer(
+                    tile.config.theme.clone(),
+                    results_count,
+                    &tile.active_mode,
+                ))
                 .spacing(0),
         )
         .width(Length::Fixed(WINDOW_WIDTH))
@@ -276,7 +335,7 @@ pub fn view(tile: &Tile, wid: window::Id) -> Element<'_, Message> {
     }
 }
 
-fn footer(theme: Theme, results_count: usize) -> Element<'static, Message> {
+fn footer(theme: Theme, results_count: usize, active_mode: &str) -> Element<'static, Message> {
     let text = if results_count == 0 {
         return space().into();
     } else if results_count == 1 {
@@ -284,11 +343,16 @@ fn footer(theme: Theme, results_count: usize) -> Element<'static, Message> {
     } else {
         &format!("{results_count} results found")
     };
+    let text = if active_mode == DEFAULT_MODE {
+        text.to_string()
+    } else {
+        format!("{text} · {active_mode} mode")
+    };
 
     container(
         Row::new()
             .push(
-                Text::new(text.to_string())
+                Text::new(text)
                     .size(12)
                     .height(30)
                     .color(theme.text_color(0.7))
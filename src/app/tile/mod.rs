@@ -25,10 +25,19 @@ use iced::{
 use global_hotkey::{GlobalHotKeyEvent, HotKeyState, hotkey::HotKey};
 
 use crate::{
-    app::{ArrowKey, Message, Move, Page, apps::SimpleApp, tile::elm::default_app_paths},
+    app::{
+        ArrowKey, Message, Move, Page, apps::SimpleApp, pages::mcp::McpToolView,
+        tile::elm::default_app_paths,
+    },
+    bookmarks::{self, Bookmark},
+    commands::Function,
     config::Config,
-    functions::clipboard::ClipBoardContentType,
+    extensions::ExtensionHost,
+    functions::{calc_history::CalcHistory, clipboard::ClipBoardContentType, plot::Plot},
+    mcp::McpHost,
     platform::open_settings,
+    unit_conversion::currency::{self, Rates},
+    wasm_extensions::WasmHost,
 };
 
 use arboard::Clipboard;
@@ -90,6 +99,16 @@ impl AppIndex {
 /// - Open Hotkey ID (`u32`) the id of the hotkey that opens the window
 /// - Clipboard Content (`Vec<`[`ClipBoardContentType`]`>`) all of the cliboard contents
 /// - Page ([`Page`]) the current page of the window (main or clipboard history)
+/// - Pending Confirmation ([`Option<Function>`]) the function waiting for the user to confirm it
+/// - Extensions ([`ExtensionHost`]) the out-of-process extensions
+/// - Extension Apps (Vec<[`SimpleApp`]>) the commands registered by extensions
+/// - WASM Extensions ([`WasmHost`]) the sandboxed WASM extensions
+/// - MCP ([`McpHost`]) the MCP servers
+/// - MCP Tool ([`Option<McpToolView>`]) the MCP tool open on the MCP tool page
+/// - Active Mode (String) the name of the mode that is switched on
+/// - Bookmarks (Vec<[`Bookmark`]>) the bookmarks of the local browser profiles
+/// - Calculator History ([`CalcHistory`]) the past calculations and the calculator's variables
+/// - Rates ([`Rates`]) the exchange rates of the rates file, for currency conversions
 #[derive(Clone)]
 pub struct Tile {
     pub theme: iced::Theme,
@@ -115,6 +134,18 @@ pub struct Tile {
     tray_icon: Option<TrayIcon>,
     sender: Option<ExtSender>,
     page: Page,
+    pending_confirmation: Option<Function>,
+    extensions: ExtensionHost,
+    extension_apps: Vec<SimpleApp>,
+    wasm_extensions: WasmHost,
+    mcp: McpHost,
+    mcp_tool: Option<McpToolView>,
+    active_mode: String,
+    bookmarks: Vec<Bookmark>,
+    calc_history: CalcHistory,
+    rates: Rates,
+    /// The plot shown above the results, for a query like `plot sin(x)`
+    plot: Option<Plot>,
 }
 
 impl Tile {
@@ -129,6 +160,9 @@ impl Tile {
     /// - Hotkeys
     /// - Hot reloading
     /// - Clipboard history
+    /// - Bookmark file changes
+    /// - Exchange rates file changes
+    /// - A tick every second while the clocks page is open
     /// - Window close events
     /// - Keypresses (escape to close the window)
     /// - Window focus changes
@@ -159,6 +193,13 @@ impl Tile {
             Subscription::run(handle_recipient),
             Subscription::run(handle_hot_reloading),
             Subscription::run(handle_clipboard_history),
+            Subscription::run_with(self.config.bookmarks.clone(), bookmarks::watch),
+            Subscription::run(currency::watch),
+            if self.page == Page::Clocks {
+                iced::time::every(Duration::from_secs(1)).map(|_| Message::RefreshClocks)
+            } else {
+                Subscription::none()
+            },
             window::close_events().map(Message::HideWindow),
             keyboard::listen().filter_map(|event| {
                 if let keyboard::Event::KeyPressed { key, modifiers, .. } = event {
@@ -8,12 +8,21 @@ use super::Tile;
 use crate::{
     app::{
         ArrowKey, DEFAULT_WINDOW_HEIGHT, Message, Page, WINDOW_WIDTH,
-        apps::{AppCommand, SimpleApp},
+        apps::{AppCommand, AppData, SimpleApp},
+        pages::plot::PLOT_HEIGHT,
     },
+    bookmarks,
     commands::Function,
-    functions::calculator::Expr,
+    functions::calc_quantity::calculate_quantity,
+    functions::calc_value::{Format, Value},
+    functions::calculator::{
+        Base, CalcError, Env, Expr, calculate, looks_like_math, parse_column, split_base_suffix,
+    },
     functions::clipboard::ClipBoardContentType,
-    unit_conversion,
+    functions::datetime::{Clock, date_results},
+    functions::plot::Plot,
+    functions::solver::Equation,
+    unit_conversion::{self, currency::Rates, unit::Dimension},
 };
 
 #[cfg(target_os = "macos")]
@@ -22,6 +31,11 @@ use crate::platform::macos::haptics::{HapticPattern, perform_haptic};
 #[allow(clippy::too_many_lines)]
 pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<Message> {
     tile.focus_id = 0;
+    tile.pending_confirmation = None;
+    if tile.page == Page::McpTool {
+        tile.page = Page::Main;
+        tile.mcp_tool = None;
+    }
     #[cfg(target_os = "macos")]
     if tile.config.haptic_feedback {
         perform_haptic(HapticPattern::Alignment);
@@ -30,7 +44,13 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
     tile.query_lc = input.trim().to_lowercase();
     tile.query = input.to_string();
     let prev_size = tile.results.len();
-    if tile.query_lc.is_empty() && tile.page != Page::ClipboardHistory {
+    let had_plot = tile.plot.take().is_some();
+    if tile.query_lc.is_empty()
+        && !matches!(
+            tile.page,
+            Page::ClipboardHistory | Page::CalculatorHistory | Page::Clocks
+        )
+    {
         tile.results = vec![];
         return window::resize(
             id,
@@ -70,6 +90,20 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
             },
         );
     }
+    if let Some((engine, query)) = tile.config.find_bang(&tile.query)
+        && !query.is_empty()
+    {
+        tile.results = vec![SimpleApp::new_builtin(
+            &format!("Search {} for: {query}", engine.name),
+            "",
+            "Web Search",
+            AppCommand::Function(Function::WebSearch(engine.url.clone(), query.clone())),
+        )];
+        return window::resize(
+            id,
+            iced::Size::new(WINDOW_WIDTH, 55. + DEFAULT_WINDOW_HEIGHT),
+        );
+    }
     if tile.query_lc.ends_with('?') {
         tile.results = vec![SimpleApp::new_builtin(
             &format!("Search for: {}", tile.query),
@@ -88,42 +122,176 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
     }
     tile.handle_search_query_changed();
 
+    let mut extension_tasks = Vec::new();
+    if tile.extensions.needs_start() {
+        extension_tasks.push(Task::perform(
+            tile.extensions.clone().start(),
+            Message::ExtensionsStarted,
+        ));
+    }
+    if tile.mcp.needs_start() {
+        extension_tasks.push(Task::perform(
+            tile.mcp.clone().start(),
+            Message::ExtensionsStarted,
+        ));
+    }
+    if tile.page == Page::Main
+        && let Some(search) = tile.extensions.keyword_search(&tile.query)
+    {
+        let query = tile.query.clone();
+        extension_tasks.push(Task::perform(search, move |apps| {
+            Message::ExtensionResults(query.clone(), apps)
+        }));
+    }
+    if tile.wasm_extensions.needs_load() {
+        extension_tasks.push(Task::future(tile.wasm_extensions.clone().load()).discard());
+    }
+    if tile.page == Page::Main
+        && let Some(search) = tile.wasm_extensions.keyword_search(&tile.query)
+    {
+        let query = tile.query.clone();
+        extension_tasks.push(Task::perform(search, move |apps| {
+            Message::ExtensionResults(query.clone(), apps)
+        }));
+    }
+    let extension_tasks = Task::batch(extension_tasks);
+
+    if tile.page == Page::Main {
+        let quicklinks: Vec<SimpleApp> = tile
+            .config
+            .quicklinks
+            .iter()
+            .filter_map(|quicklink| quicklink.to_app(&tile.query))
+            .collect();
+        tile.results.splice(0..0, quicklinks);
+    }
+
+    // Dates come before calculations, which would read `2026-03-01 - 2025-11-15` as a subtraction
     if tile.results.is_empty()
-        && let Some(res) = Expr::from_str(&tile.query).ok()
+        && let Some(rows) = date_results(&tile.query, &Clock::system(&tile.config.world_clock))
     {
-        let res_string = res.eval().map_or(String::new(), |x| x.to_string());
-        tile.results.push(SimpleApp::new_builtin(
-            &res_string,
+        tile.results = rows
+            .into_iter()
+            .map(|row| {
+                SimpleApp::new_builtin(
+                    &row.text,
+                    "",
+                    &row.desc,
+                    AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                        row.text.clone(),
+                    ))),
+                )
+            })
+            .collect();
+
+        // A plain number read as a Unix timestamp is still a number too
+        if tile.query.trim().chars().all(|c| c.is_ascii_digit())
+            && let Some(calculation) = calculation_results(
+                &tile.query,
+                &tile.calc_history.env(tile.config.calculator.angle_unit),
+                &tile.config.calculator.format(),
+            )
+        {
+            tile.results.splice(0..0, calculation);
+        }
+    } else if tile.results.is_empty()
+        && let Some(calculation) = calculation_results(
+            &tile.query,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+            &tile.config.calculator.format(),
+        )
+    {
+        tile.results = calculation;
+    } else if tile.results.is_empty()
+        && let Some(plot) = Plot::parse(
+            &tile.query,
+            tile.config.calculator.decimal_separator,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+        )
+    {
+        tile.results = vec![SimpleApp::new_builtin(
+            "Copy as PNG",
             "",
-            "Calculation result",
-            AppCommand::Function(Function::Calculate(res.clone())),
-        ));
+            &format!("Plot of {}", plot.describe()),
+            AppCommand::Function(Function::CopyPlot(plot.clone())),
+        )];
+        tile.plot = Some(plot);
     } else if tile.results.is_empty()
-        && let Some(conversions) = unit_conversion::convert_query(&tile.query)
+        && let Some(solutions) = solution_results(
+            &tile.query,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+            &tile.config.calculator.format(),
+        )
+    {
+        tile.results = solutions;
+    } else if tile.results.is_empty()
+        && let Some(numbers) = parse_column(&tile.query, tile.config.calculator.decimal_separator)
+    {
+        tile.results = column_results(
+            &numbers,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+            &tile.config.calculator.format(),
+        );
+    } else if tile.results.is_empty()
+        && let Some(conversions) = unit_conversion::convert_query(
+            &tile.query,
+            &tile.config.units.preferences(),
+            &tile.rates,
+        )
     {
         tile.results = conversions
             .into_iter()
             .map(|conversion| {
+                let is_currency = conversion.target_unit.dimension == Dimension::CURRENCY;
+                let number = |value: f64| {
+                    if is_currency {
+                        format!("{value:.2}")
+                    } else {
+                        unit_conversion::format_number(value)
+                    }
+                };
                 let source = format!(
                     "{} {}",
-                    unit_conversion::format_number(conversion.source_value),
+                    number(conversion.source_value),
                     conversion.source_unit.name
                 );
                 let target = format!(
                     "{} {}",
-                    unit_conversion::format_number(conversion.target_value),
+                    number(conversion.target_value),
                     conversion.target_unit.name
                 );
+                let desc = if is_currency {
+                    format!("Copy to clipboard ({})", tile.rates.describe())
+                } else {
+                    "Copy to clipboard".to_string()
+                };
                 SimpleApp::new_builtin(
                     &source,
                     &target,
-                    "Copy to clipboard",
+                    &desc,
                     AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
                         target.clone(),
                     ))),
                 )
             })
             .collect();
+    } else if tile.results.is_empty()
+        && let Some(quantity) = quantity_results(
+            &tile.query,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+            &tile.config.calculator.format(),
+            &tile.rates,
+        )
+    {
+        tile.results = quantity;
+    } else if tile.results.is_empty()
+        && let Some(error) = calculation_error_results(
+            &tile.query,
+            &tile.calc_history.env(tile.config.calculator.angle_unit),
+            &tile.config.calculator.format(),
+        )
+    {
+        tile.results = error;
     } else if tile.results.is_empty() && url::Url::parse(input).is_ok() {
         tile.results.push(SimpleApp::new_builtin(
             "Web Browsing",
@@ -138,6 +306,25 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
             "Web Search",
             AppCommand::Function(Function::GoogleSearch(tile.query.clone())),
         ));
+        let default_url = tile.config.default_search_url();
+        let alternatives: Vec<SimpleApp> = tile
+            .config
+            .search_engines
+            .iter()
+            .filter(|engine| engine.url != default_url)
+            .map(|engine| {
+                SimpleApp::new_builtin(
+                    &format!("Search {} for: {}", engine.name, tile.query),
+                    "",
+                    &format!("Web Search (!{})", engine.bang()),
+                    AppCommand::Function(Function::WebSearch(
+                        engine.url.clone(),
+                        tile.query.clone(),
+                    )),
+                )
+            })
+            .collect();
+        tile.results.extend(alternatives);
     } else if tile.results.is_empty() && tile.query_lc == "lemon" {
         #[cfg(target_os = "macos")]
         tile.results.push(SimpleApp::new_builtin(
@@ -147,6 +334,26 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
             AppCommand::Display,
         ));
     }
+    if tile.page == Page::Main {
+        // Bookmarks go above the web searches, which are only there when nothing better matched
+        let position = tile
+            .results
+            .iter()
+            .position(|app| {
+                matches!(
+                    &app.data,
+                    AppData::Builtin {
+                        command: AppCommand::Function(
+                            Function::GoogleSearch(_) | Function::WebSearch(..)
+                        ),
+                        ..
+                    }
+                )
+            })
+            .unwrap_or(tile.results.len());
+        let bookmarks = bookmarks::search(&tile.bookmarks, &tile.query_lc);
+        tile.results.splice(position..position, bookmarks);
+    }
     if !tile.query_lc.is_empty() && tile.page == Page::EmojiSearch {
         tile.results = tile
             .emoji_apps
@@ -158,39 +365,264 @@ pub(super) fn handle_change(tile: &mut Tile, input: &str, id: Id) -> iced::Task<
     let new_length = tile.results.len();
     let max_elem = cmp::min(5, new_length);
 
-    if prev_size != new_length && tile.page != Page::ClipboardHistory {
-        #[allow(
-            clippy::cast_precision_loss,
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss
-        )]
-        Task::batch([
-            window::resize(
-                id,
-                iced::Size {
-                    width: WINDOW_WIDTH,
-                    height: ((max_elem * 55) + 35 + DEFAULT_WINDOW_HEIGHT as usize) as f32,
-                },
-            ),
-            Task::done(Message::ChangeFocus(ArrowKey::Left)),
-        ])
-    } else if tile.page == Page::ClipboardHistory {
-        #[allow(
-            clippy::cast_precision_loss,
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss
-        )]
-        Task::batch([
-            window::resize(
-                id,
-                iced::Size {
-                    width: WINDOW_WIDTH,
-                    height: ((7 * 55) + 35 + DEFAULT_WINDOW_HEIGHT as usize) as f32,
-                },
-            ),
-            Task::done(Message::ChangeFocus(ArrowKey::Left)),
-        ])
+    let is_history_page = matches!(
+        tile.page,
+        Page::ClipboardHistory | Page::CalculatorHistory | Page::Clocks
+    );
+    let plot_height = if tile.plot.is_some() { PLOT_HEIGHT } else { 0. };
+    let resize_task =
+        if (prev_size != new_length || had_plot != tile.plot.is_some()) && !is_history_page {
+            #[allow(
+                clippy::cast_precision_loss,
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss
+            )]
+            Task::batch([
+                window::resize(
+                    id,
+                    iced::Size {
+                        width: WINDOW_WIDTH,
+                        height: ((max_elem * 55) + 35 + DEFAULT_WINDOW_HEIGHT as usize) as f32
+                            + plot_height,
+                    },
+                ),
+                Task::done(Message::ChangeFocus(ArrowKey::Left)),
+            ])
+        } else if is_history_page {
+            #[allow(
+                clippy::cast_precision_loss,
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss
+            )]
+            Task::batch([
+                window::resize(
+                    id,
+                    iced::Size {
+                        width: WINDOW_WIDTH,
+                        height: ((7 * 55) + 35 + DEFAULT_WINDOW_HEIGHT as usize) as f32,
+                    },
+                ),
+                Task::done(Message::ChangeFocus(ArrowKey::Left)),
+            ])
+        } else {
+            Task::none()
+        };
+
+    Task::batch([resize_task, extension_tasks])
+}
+
+/// The results for a calculation, if the query is one
+///
+/// Integer results of "programmer" expressions (with hex / binary / octal literals or bitwise
+/// operators, or a suffix like `in hex`) are shown in every base, starting with the one asked for.
+/// Exact results whose decimals had to be cut off are also shown as a fraction, and complex results
+/// in polar form.
+fn calculation_results(query: &str, env: &Env, format: &Format) -> Option<Vec<SimpleApp>> {
+    let (expression, suffix_base) = split_base_suffix(query);
+    let (expr, value) = calculate(expression, format.decimal_separator, env).ok()?;
+
+    let desc = match &expr {
+        Expr::Assign { name, .. } => format!("Assign to {name}"),
+        _ => "Calculation result".to_string(),
+    };
+
+    let (Some(n), Some(base)) = (
+        value.to_i128(),
+        suffix_base.or_else(|| expr.programmer_base()),
+    ) else {
+        let other_form = value
+            .fraction(format)
+            .map(|fraction| (fraction, "Fraction"))
+            .or_else(|| value.polar(format).map(|polar| (polar, "Polar form")));
+        let mut results = vec![SimpleApp::new_builtin(
+            &value.format(format),
+            "",
+            &desc,
+            AppCommand::Function(Function::Calculate(
+                expr,
+                value.clone(),
+                value.format(&format.ungrouped()),
+            )),
+        )];
+        results.extend(other_form.map(|(written, desc)| {
+            SimpleApp::new_builtin(
+                &written,
+                "",
+                desc,
+                AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                    written.clone(),
+                ))),
+            )
+        }));
+        return Some(results);
+    };
+
+    let mut results = vec![SimpleApp::new_builtin(
+        &base.format(n),
+        "",
+        &format!("{desc} ({})", base.name()),
+        AppCommand::Function(Function::Calculate(expr, value, base.format(n))),
+    )];
+    results.extend(
+        Base::ALL
+            .into_iter()
+            .filter(|other| *other != base)
+            .map(|other| {
+                SimpleApp::new_builtin(
+                    &other.format(n),
+                    "",
+                    other.name(),
+                    AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                        other.format(n),
+                    ))),
+                )
+            }),
+    );
+    Some(results)
+}
+
+/// The roots of an equation (`solve x^2 - 5x + 6 = 0`, `2x + 3 = 11`), each in its own row, if
+/// the query is one
+fn solution_results(query: &str, env: &Env, format: &Format) -> Option<Vec<SimpleApp>> {
+    let equation = Equation::parse(query, format.decimal_separator, env)?;
+    let roots = equation.solve(env);
+    if roots.is_empty() {
+        return Some(vec![SimpleApp::new_builtin(
+            "No solutions found",
+            "",
+            &format!("Solve for {}", equation.unknown),
+            AppCommand::Display,
+        )]);
+    }
+
+    let count = roots.len();
+    Some(
+        roots
+            .iter()
+            .enumerate()
+            .map(|(i, root)| {
+                let desc = if count == 1 {
+                    "Solution".to_string()
+                } else {
+                    format!("Solution {} of {count}", i + 1)
+                };
+                SimpleApp::new_builtin(
+                    &format!("{} = {}", equation.unknown, root.format(format)),
+                    "",
+                    &desc,
+                    AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                        root.format(&format.ungrouped()),
+                    ))),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// The result of a calculation with units (`3 ft + 20 cm in m`), or why it has none as a dimmed
+/// row (`Can't add length and time`)
+fn quantity_results(
+    query: &str,
+    env: &Env,
+    format: &Format,
+    rates: &Rates,
+) -> Option<Vec<SimpleApp>> {
+    let (value, unit) = match calculate_quantity(query, format.decimal_separator, env, rates)? {
+        Ok(result) => result,
+        Err(error) => return Some(vec![SimpleApp::new_hint(&error.to_string(), "Unit error")]),
+    };
+    // Amounts of money are shown in cents
+    let is_currency = unit
+        .as_ref()
+        .is_some_and(|unit| unit.dimension == Dimension::CURRENCY);
+    let format = &if is_currency {
+        Format {
+            precision: 2,
+            ..*format
+        }
     } else {
-        Task::none()
+        *format
+    };
+    let desc = if is_currency {
+        rates.describe()
+    } else {
+        "Calculation result".to_string()
+    };
+    let written = |format: &Format| {
+        let number = Value::Float(value).format(format);
+        match &unit {
+            Some(unit) => format!("{number} {}", unit.name),
+            None => number,
+        }
+    };
+    Some(vec![SimpleApp::new_builtin(
+        &written(format),
+        "",
+        &desc,
+        AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+            written(&format.ungrouped()),
+        ))),
+    )])
+}
+
+/// Why a query that is clearly a calculation (`2 +`, `sqrt(-1, 2)`, `sni(30)`) has no result, as a
+/// dimmed row, and the query with the function name fixed if it has a typo in one
+fn calculation_error_results(query: &str, env: &Env, format: &Format) -> Option<Vec<SimpleApp>> {
+    let (expression, _) = split_base_suffix(query);
+    if !looks_like_math(expression, env) {
+        return None;
+    }
+    let error = calculate(expression, format.decimal_separator, env).err()?;
+
+    let desc = match error {
+        CalcError::Incomplete => "Keep typing to see the result",
+        _ => "Calculation error",
+    };
+    let mut results = vec![SimpleApp::new_hint(&error.to_string(), desc)];
+    if let CalcError::UnknownFunction {
+        suggestion: Some(function),
+        span,
+        ..
+    } = error
+    {
+        let fixed = format!(
+            "{}{function}{}",
+            &expression[..span.start],
+            &query[span.end..]
+        );
+        results.push(SimpleApp::new_builtin(
+            &format!("Did you mean {fixed}?"),
+            "",
+            "Fix the function name",
+            AppCommand::Message(Message::ReuseCalculation(fixed)),
+        ));
     }
+    Some(results)
+}
+
+/// The statistics that are shown for a pasted column of numbers, and their names
+const COLUMN_STATISTICS: [(&str, &str); 3] =
+    [("sum", "Sum"), ("mean", "Mean"), ("median", "Median")];
+
+/// The sum, mean and median of a column of numbers
+fn column_results(numbers: &[Expr], env: &Env, format: &Format) -> Vec<SimpleApp> {
+    COLUMN_STATISTICS
+        .into_iter()
+        .filter_map(|(function, name)| {
+            let expr = Expr::Func {
+                name: function.to_string(),
+                args: numbers.to_vec(),
+            };
+            let value = expr.evaluate(env)?;
+            let written = value.format(&format.ungrouped());
+            Some(SimpleApp::new_builtin(
+                &value.format(format),
+                "",
+                &format!("{name} of {} numbers", numbers.len()),
+                AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                    written,
+                ))),
+            ))
+        })
+        .collect()
 }
@@ -8,10 +8,12 @@ use iced::window;
 use rayon::slice::ParallelSliceMut;
 
 use crate::app::apps::AppData;
+use crate::app::pages::clocks::world_clock_rows;
+use crate::app::pages::mcp::{McpToolView, ToolState, field_id};
 use crate::app::{
     ArrowKey, DEFAULT_WINDOW_HEIGHT, Message, Move, Page, WINDOW_WIDTH, apps::AppCommand,
-    apps::SimpleApp, default_settings, menubar::menu_icon, tile::AppIndex, tile::Tile,
-    tile::search_query,
+    apps::SimpleApp, default_settings, menubar::menu_icon, menubar::update_modes, tile::AppIndex,
+    tile::Tile, tile::search_query,
 };
 
 #[cfg(target_os = "macos")]
@@ -20,6 +22,11 @@ use crate::platform::macos;
 use crate::app_finding::index_installed_apps;
 use crate::commands::Function;
 use crate::config::Config;
+use crate::extensions::is_from_extension;
+use crate::functions::clipboard::ClipBoardContentType;
+use crate::mcp::schema;
+use crate::modes;
+use crate::wasm_extensions::WasmHost;
 
 #[allow(clippy::too_many_lines)]
 pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
@@ -56,6 +63,8 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
                         #[cfg(not(target_os = "linux"))]
                         tile.hotkey,
                         sender,
+                        &modes::mode_names(&tile.config),
+                        &tile.active_mode,
                     ));
                 }
             }
@@ -63,6 +72,17 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
         }
 
         Message::EscKeyPressed(id) => {
+            if tile.pending_confirmation.is_some() {
+                return Task::done(Message::CancelPendingFunction);
+            }
+
+            if tile.page == Page::McpTool {
+                tile.page = Page::Main;
+                tile.mcp_tool = None;
+                let query = tile.query.clone();
+                return Task::done(Message::SearchQueryChanged(query, id));
+            }
+
             if tile.page == Page::EmojiSearch && !tile.query_lc.is_empty() {
                 return Task::none();
             }
@@ -100,8 +120,14 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
             // No, there won't be more than 2^32-1 items in a list
             let len = match tile.page {
                 Page::ClipboardHistory => tile.clipboard_content.len() as u32,
+                Page::CalculatorHistory => tile.calc_history.entries.len() as u32,
+                Page::Clocks => tile.config.world_clock.len() as u32,
                 Page::EmojiSearch => tile.emoji_apps.search_prefix(&tile.query_lc).count() as u32, // or tile.results.len()
                 Page::Main => tile.results.len() as u32,
+                Page::McpTool => tile
+                    .mcp_tool
+                    .as_ref()
+                    .map_or(0, |view| view.fields.len() as u32),
             };
 
             let old_focus_id = tile.focus_id;
@@ -116,6 +142,14 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
             };
 
             let task = match (key, &tile.page) {
+                (ArrowKey::Down, Page::McpTool) => {
+                    tile.focus_id = (tile.focus_id + 1) % len;
+                    operation::focus(field_id(tile.focus_id as usize))
+                }
+                (ArrowKey::Up, Page::McpTool) => {
+                    tile.focus_id = (tile.focus_id + len - 1) % len;
+                    operation::focus(field_id(tile.focus_id as usize))
+                }
                 (ArrowKey::Down, _) => {
                     tile.focus_id = (tile.focus_id + change_by) % len;
                     Task::none()
@@ -137,9 +171,10 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
 
             let direction = if tile.focus_id < old_focus_id { -1 } else { 1 };
             let quantity = match tile.page {
-                Page::Main => 66.5,
-                Page::ClipboardHistory => 50.,
+                Page::Main | Page::Clocks => 66.5,
+                Page::ClipboardHistory | Page::CalculatorHistory => 50.,
                 Page::EmojiSearch => 5.,
+                Page::McpTool => 60.,
             };
 
             #[allow(clippy::cast_precision_loss)]
@@ -155,6 +190,26 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
             ])
         }
 
+        Message::OpenFocused if tile.page == Page::McpTool => Task::done(Message::SubmitMcpTool),
+
+        Message::OpenFocused if tile.page == Page::CalculatorHistory => {
+            match tile.calc_history.entries.get(tile.focus_id as usize) {
+                Some(entry) => Task::done(Message::RunFunction(Function::CopyToClipboard(
+                    ClipBoardContentType::Text(entry.result.clone()),
+                ))),
+                None => Task::none(),
+            }
+        }
+
+        Message::OpenFocused if tile.page == Page::Clocks => {
+            match world_clock_rows(&tile.config.world_clock).get(tile.focus_id as usize) {
+                Some(row) => Task::done(Message::RunFunction(Function::CopyToClipboard(
+                    ClipBoardContentType::Text(row.text.clone()),
+                ))),
+                None => Task::none(),
+            }
+        }
+
         Message::OpenFocused => match tile.results.get(tile.focus_id as usize).map(|x| &x.data) {
             Some(AppData::Builtin {
                 command: AppCommand::Function(func),
@@ -168,7 +223,13 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
                 command: AppCommand::Display,
                 ..
             }) => Task::done(Message::ReturnFocus),
-            _ => Task::none(),
+            Some(AppData::Executable { path, .. }) => {
+                Task::done(Message::RunFunction(Function::OpenApp(path.to_owned())))
+            }
+            Some(AppData::Command { command, alias, .. }) => Task::done(Message::RunFunction(
+                Function::RunShellCommand(command.to_owned(), alias.to_owned()),
+            )),
+            None => Task::none(),
         },
 
         Message::ReloadConfig => {
@@ -188,13 +249,35 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
                 Err(e) => tracing::error!("Error indexing apps: {e}"),
             }
 
+            tile.extensions.reconfigure(&new_config.extensions);
+            tile.mcp.reconfigure(&new_config.mcp_servers);
+            tile.extension_apps.retain(|app| {
+                new_config
+                    .extensions
+                    .iter()
+                    .chain(&new_config.mcp_servers)
+                    .any(|extension| is_from_extension(app, &extension.name))
+            });
+
             options.extend(new_config.shells.iter().map(crate::config::Shelly::to_app));
             options.extend(SimpleApp::basic_apps());
+            options.extend(tile.extension_apps.iter().cloned());
+
+            // The scripts aren't run, since the mode doesn't exist anymore
+            if !modes::mode_names(&new_config).contains(&tile.active_mode) {
+                tile.active_mode = modes::DEFAULT_MODE.to_string();
+                modes::save_active(&tile.active_mode);
+            }
+            options.extend(modes::mode_apps(&new_config, &tile.active_mode));
+
+            tile.wasm_extensions = WasmHost::discover();
+            options.extend(tile.wasm_extensions.install_apps());
             options.par_sort_by_key(|x| x.name.len());
 
             tile.theme = new_config.theme.clone().into();
             tile.config = new_config;
             tile.options = AppIndex::from_apps(options);
+            update_tray_modes(tile);
             Task::none()
         }
 
@@ -228,30 +311,31 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
         }
 
         Message::RunFunction(command) => {
-            command.execute(&tile.config, &tile.query);
-
-            let return_focus_task = match &command {
-                Function::OpenApp(_) | Function::OpenPrefPane | Function::GoogleSearch(_) => {
-                    Task::none()
-                }
-                _ => Task::done(Message::ReturnFocus),
-            };
-
-            if tile.config.buffer_rules.clear_on_enter {
-                window::latest()
-                    .map(|x| x.unwrap())
-                    .map(Message::HideWindow)
-                    .chain(Task::done(Message::ClearSearchQuery))
-                    .chain(return_focus_task)
+            if tile.config.requires_confirmation(&command) {
+                ask_confirmation(tile, command)
             } else {
-                Task::none()
+                run_function(tile, &command)
             }
         }
 
+        Message::ConfirmPendingFunction => match tile.pending_confirmation.take() {
+            Some(command) => run_function(tile, &command),
+            None => Task::none(),
+        },
+
+        Message::CancelPendingFunction => {
+            tile.pending_confirmation = None;
+            let query = tile.query.clone();
+            window::latest()
+                .map(|x| x.unwrap())
+                .map(move |id| Message::SearchQueryChanged(query.clone(), id))
+        }
+
         Message::HideWindow(a) => {
             tile.visible = false;
             tile.focused = false;
             tile.page = Page::Main;
+            tile.mcp_tool = None;
             Task::batch([window::close(a), Task::done(Message::ClearSearchResults)])
         }
 
@@ -260,6 +344,9 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
             Task::none()
         }
 
+        // Typing into the form is handled by the fields themselves
+        Message::FocusTextInput(_) if tile.page == Page::McpTool => Task::none(),
+
         Message::FocusTextInput(update_query_char) => {
             match update_query_char {
                 Move::Forwards(query_char) => {
@@ -385,9 +472,287 @@ pub fn handle_update(tile: &mut Tile, message: Message) -> Task<Message> {
         }
 
         Message::SearchQueryChanged(input, id) => search_query::handle_change(tile, &input, id),
+
+        Message::ExtensionsStarted(registered) => {
+            for (extension, apps) in registered {
+                tile.extension_apps
+                    .retain(|app| !is_from_extension(app, &extension));
+                tile.options
+                    .by_name
+                    .retain(|_, app| !is_from_extension(app, &extension));
+
+                for app in apps {
+                    tile.options.by_name.insert(app.alias.clone(), app.clone());
+                    tile.extension_apps.push(app);
+                }
+            }
+            Task::none()
+        }
+
+        Message::OpenMcpTool(server, name) => {
+            let Some(tool) = tile.mcp.tool(&server, &name) else {
+                tracing::warn!(target: "mcp", "[{server}] No tool named {name}");
+                return Task::none();
+            };
+
+            let fields = schema::form_fields(&tool.input_schema);
+            let focus_task = if fields.is_empty() {
+                Task::done(Message::SubmitMcpTool)
+            } else {
+                operation::focus(field_id(0))
+            };
+
+            tile.mcp_tool = Some(McpToolView {
+                server,
+                tool,
+                fields,
+                state: ToolState::Editing(None),
+            });
+            tile.page = Page::McpTool;
+            tile.focus_id = 0;
+
+            let height = (7. * 55.) + 35. + DEFAULT_WINDOW_HEIGHT;
+            Task::batch([
+                window::latest().and_then(move |id| {
+                    window::resize(
+                        id,
+                        iced::Size {
+                            width: WINDOW_WIDTH,
+                            height,
+                        },
+                    )
+                }),
+                focus_task,
+            ])
+        }
+
+        Message::McpFieldChanged(index, value) => {
+            if let Some(field) = tile
+                .mcp_tool
+                .as_mut()
+                .and_then(|view| view.fields.get_mut(index))
+            {
+                field.value = value;
+            }
+            Task::none()
+        }
+
+        Message::SubmitMcpTool => {
+            let Some(view) = tile.mcp_tool.as_mut() else {
+                return Task::none();
+            };
+            if matches!(view.state, ToolState::Running) {
+                return Task::none();
+            }
+
+            match schema::arguments(&view.fields) {
+                Ok(arguments) => {
+                    view.state = ToolState::Running;
+                    let name = view.tool.name.clone();
+                    Task::perform(
+                        tile.mcp.call_tool(&view.server, &view.tool.name, arguments),
+                        move |result| Message::McpToolFinished(name.clone(), result),
+                    )
+                }
+                Err(e) => {
+                    view.state = ToolState::Editing(Some(e));
+                    Task::none()
+                }
+            }
+        }
+
+        Message::McpToolFinished(name, result) => {
+            // The user may have closed the tool, or opened another one, in the meantime
+            if let Some(view) = tile.mcp_tool.as_mut()
+                && view.tool.name == name
+                && matches!(view.state, ToolState::Running)
+            {
+                view.state = match result {
+                    Ok(output) => ToolState::Done(output),
+                    Err(e) => ToolState::Failed(e),
+                };
+            }
+            Task::none()
+        }
+
+        Message::ReuseCalculation(expression) => {
+            tile.page = Page::Main;
+            Task::batch([
+                operation::focus("query"),
+                window::latest()
+                    .map(|x| x.unwrap())
+                    .map(move |id| Message::SearchQueryChanged(expression.clone(), id)),
+            ])
+        }
+
+        Message::BookmarksLoaded(bookmarks) => {
+            tile.bookmarks = bookmarks;
+            Task::none()
+        }
+
+        Message::RatesLoaded(rates) => {
+            tile.rates = rates;
+            Task::none()
+        }
+
+        // The clocks page is drawn from the current time, so redrawing it is all that's needed
+        Message::RefreshClocks => Task::none(),
+
+        Message::SwitchMode(mode) => {
+            if mode == tile.active_mode || !modes::mode_names(&tile.config).contains(&mode) {
+                // Clicking a check item toggles it, so the tray is synced even when nothing
+                // changed, to keep the active mode checked
+                update_tray_modes(tile);
+                return Task::none();
+            }
+
+            modes::run_scripts(&tile.config, &tile.active_mode, &mode);
+            modes::save_active(&mode);
+            tile.active_mode = mode;
+
+            tile.options
+                .by_name
+                .retain(|_, app| !modes::is_mode_app(app));
+            for app in modes::mode_apps(&tile.config, &tile.active_mode) {
+                tile.options.by_name.insert(app.alias.clone(), app);
+            }
+            update_tray_modes(tile);
+
+            // Refresh the results (if the window is open), so the new mode shows as the current one
+            let query = tile.query.clone();
+            window::latest()
+                .and_then(move |id| Task::done(Message::SearchQueryChanged(query.clone(), id)))
+        }
+
+        Message::ExtensionResults(query, apps) => {
+            if query != tile.query || apps.is_empty() || tile.pending_confirmation.is_some() {
+                return Task::none();
+            }
+
+            tile.results.splice(0..0, apps);
+            let max_elem = std::cmp::min(5, tile.results.len());
+
+            #[allow(clippy::cast_precision_loss)]
+            let height = ((max_elem * 55) + 35) as f32 + DEFAULT_WINDOW_HEIGHT;
+            window::latest().and_then(move |id| {
+                window::resize(
+                    id,
+                    iced::Size {
+                        width: WINDOW_WIDTH,
+                        height,
+                    },
+                )
+            })
+        }
     }
 }
 
+/// Shows the current modes in the tray icon's menu, if there is one
+fn update_tray_modes(tile: &Tile) {
+    if let Some(tray_icon) = &tile.tray_icon {
+        update_modes(
+            tray_icon,
+            #[cfg(not(target_os = "linux"))]
+            tile.hotkey,
+            &modes::mode_names(&tile.config),
+            &tile.active_mode,
+        );
+    }
+}
+
+/// Runs a function, and then hides the window if the buffer rules say so
+fn run_function(tile: &mut Tile, command: &Function) -> Task<Message> {
+    let mut extension_task = Task::none();
+    match command {
+        Function::ExtensionAction(extension, id) => {
+            tile.extensions.action_invoked(extension, id, &tile.query);
+        }
+        Function::WasmExtensionAction(extension, id) => {
+            extension_task =
+                Task::future(tile.wasm_extensions.invoke(extension, id, &tile.query)).discard();
+        }
+        Function::InstallWasmExtension(extension) => {
+            match tile.wasm_extensions.install(extension) {
+                Ok(()) => extension_task = Task::done(Message::ReloadConfig),
+                Err(e) => tracing::error!(target: "wasm_extension", "{e:#}"),
+            }
+        }
+        Function::Calculate(expr, value, display) => {
+            tile.calc_history.record(&tile.query, expr, value, display);
+            command.execute(&tile.config, &tile.query);
+        }
+        Function::Quit => {
+            // Quitting exits the process, which would also stop the threads that kill extensions
+            // ignoring the shutdown request, so wait for them first
+            let mut shutdowns = tile.extensions.shutdown();
+            shutdowns.extend(tile.mcp.shutdown());
+            for shutdown in shutdowns {
+                shutdown.join().ok();
+            }
+            command.execute(&tile.config, &tile.query);
+        }
+        _ => command.execute(&tile.config, &tile.query),
+    }
+
+    let return_focus_task = match command {
+        Function::OpenApp(_)
+        | Function::OpenPrefPane
+        | Function::GoogleSearch(_)
+        | Function::WebSearch(..) => Task::none(),
+        _ => Task::done(Message::ReturnFocus),
+    };
+
+    let buffer_task = if tile.config.buffer_rules.clear_on_enter {
+        window::latest()
+            .map(|x| x.unwrap())
+            .map(Message::HideWindow)
+            .chain(Task::done(Message::ClearSearchQuery))
+            .chain(return_focus_task)
+    } else {
+        Task::none()
+    };
+
+    Task::batch([extension_task, buffer_task])
+}
+
+/// Swaps the search results for a confirm / cancel prompt, holding on to the function until the
+/// user picks one of them
+fn ask_confirmation(tile: &mut Tile, command: Function) -> Task<Message> {
+    let action = match &command {
+        Function::InstallWasmExtension(extension) => tile.wasm_extensions.install_prompt(extension),
+        function => function.describe(),
+    };
+
+    tile.results = vec![
+        SimpleApp::new_builtin(
+            &format!("Confirm: {action}"),
+            "",
+            "This can't be undone",
+            AppCommand::Message(Message::ConfirmPendingFunction),
+        ),
+        SimpleApp::new_builtin(
+            "Cancel",
+            "",
+            "Go back to the search results",
+            AppCommand::Message(Message::CancelPendingFunction),
+        ),
+    ];
+    // Default to cancelling, so that hitting enter twice doesn't run it
+    tile.focus_id = 1;
+    tile.pending_confirmation = Some(command);
+
+    let height = (2. * 55.) + 35. + DEFAULT_WINDOW_HEIGHT;
+    window::latest().and_then(move |id| {
+        window::resize(
+            id,
+            iced::Size {
+                width: WINDOW_WIDTH,
+                height,
+            },
+        )
+    })
+}
+
 fn open_window() -> Task<Message> {
     Task::chain(
         window::open(default_settings())
@@ -0,0 +1,66 @@
+//! Reading the bookmarks of a Chromium based browser's profile, from its `Bookmarks` JSON file
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::Bookmark;
+
+/// The name of the file that Chromium based browsers keep the bookmarks in
+pub(super) const BOOKMARKS_FILE: &str = "Bookmarks";
+
+/// The contents of the bookmarks file
+///
+/// `roots` has the top-level folders (the bookmarks bar, other bookmarks and mobile bookmarks), and
+/// sometimes other values that aren't folders.
+#[derive(Debug, Deserialize)]
+struct BookmarksFile {
+    roots: Map<String, Value>,
+}
+
+/// A bookmark or a folder
+#[derive(Debug, Deserialize)]
+struct Node {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    url: Option<String>,
+    #[serde(default)]
+    children: Vec<Node>,
+}
+
+/// Reads the bookmarks of a `Bookmarks` file
+pub(super) fn read(path: &Path) -> Result<Vec<Bookmark>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: BookmarksFile = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    let mut bookmarks = Vec::new();
+    for root in file.roots.into_values() {
+        if let Ok(root) = serde_json::from_value::<Node>(root) {
+            collect(root, &mut Vec::new(), &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Adds the bookmarks in the node (and its subfolders) to `bookmarks`
+fn collect(node: Node, folders: &mut Vec<String>, bookmarks: &mut Vec<Bookmark>) {
+    match node.kind.as_str() {
+        "url" => {
+            if let Some(url) = node.url
+                && let Some(bookmark) = Bookmark::new(node.name, url, folders.join(" / "))
+            {
+                bookmarks.push(bookmark);
+            }
+        }
+        "folder" => {
+            folders.push(node.name);
+            for child in node.children {
+                collect(child, folders, bookmarks);
+            }
+            folders.pop();
+        }
+        _ => {}
+    }
+}
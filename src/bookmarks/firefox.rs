@@ -0,0 +1,134 @@
+//! Reading the bookmarks of a Firefox profile, from its `places.sqlite` database
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rusqlite::{Connection, OpenFlags};
+
+use super::Bookmark;
+
+/// The name of the database that Firefox keeps the bookmarks in
+pub(super) const PLACES_FILE: &str = "places.sqlite";
+
+/// The type of the bookmarks (rather than folders or separators) in `moz_bookmarks`
+const TYPE_BOOKMARK: i64 = 1;
+
+/// Used to give every copy of a database its own name
+static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+/// The path with a suffix added to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// The write-ahead log of a database, which has the changes that aren't in the database yet
+pub(super) fn wal_path(path: &Path) -> PathBuf {
+    with_suffix(path, "-wal")
+}
+
+/// Reads the bookmarks of a `places.sqlite` database
+///
+/// Firefox locks the database while it's running, so a temporary copy of it (and of its
+/// write-ahead log) is opened instead.
+pub(super) fn read(path: &Path) -> Result<Vec<Bookmark>, String> {
+    let copy = std::env::temp_dir().join(format!(
+        "rustcast-places-{}-{}.sqlite",
+        std::process::id(),
+        COPIES.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = copy_database(path, &copy).and_then(|()| read_copy(&copy));
+
+    for file in [copy.clone(), wal_path(&copy), with_suffix(&copy, "-shm")] {
+        let _ = fs::remove_file(file);
+    }
+    result
+}
+
+/// Copies the database and its write-ahead log (if there is one)
+fn copy_database(path: &Path, copy: &Path) -> Result<(), String> {
+    fs::copy(path, copy).map_err(|e| format!("Couldn't copy {}: {e}", path.display()))?;
+    let wal = wal_path(path);
+    if wal.is_file() {
+        fs::copy(&wal, wal_path(copy))
+            .map_err(|e| format!("Couldn't copy {}: {e}", wal.display()))?;
+    }
+    Ok(())
+}
+
+/// Reads the bookmarks from the copy of the database
+fn read_copy(copy: &Path) -> Result<Vec<Bookmark>, String> {
+    let connection = Connection::open_with_flags(copy, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+
+    let mut statement = connection
+        .prepare(
+            "SELECT b.id, b.parent, b.type, IFNULL(b.title, ''), p.url
+             FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // The parent and title of every entry, to build the folder paths from
+    let entries: HashMap<i64, (i64, &str)> = rows
+        .iter()
+        .map(|(id, parent, _, title, _)| (*id, (*parent, title.as_str())))
+        .collect();
+
+    Ok(rows
+        .iter()
+        .filter(|(_, _, kind, _, _)| *kind == TYPE_BOOKMARK)
+        .filter_map(|(_, parent, _, title, url)| {
+            Bookmark::new(title.clone(), url.clone()?, folder_path(&entries, *parent))
+        })
+        .collect())
+}
+
+/// The path of a folder, from the top-level folder (e.g. the bookmarks toolbar) down
+fn folder_path(entries: &HashMap<i64, (i64, &str)>, mut folder: i64) -> String {
+    let mut names = Vec::new();
+    // The root folder's parent is 0, and the length check stops this if the tree has a cycle
+    while let Some((parent, title)) = entries.get(&folder) {
+        if *parent == 0 || names.len() > entries.len() {
+            break;
+        }
+        let is_top_level = entries.get(parent).is_some_and(|(root, _)| *root == 0);
+        names.push(if is_top_level {
+            top_level_folder_name(title)
+        } else {
+            title
+        });
+        folder = *parent;
+    }
+    names.reverse();
+    names.join(" / ")
+}
+
+/// The name shown in Firefox for the top-level folders, which have internal names in the database
+fn top_level_folder_name(title: &str) -> &str {
+    match title {
+        "menu" => "Bookmarks Menu",
+        "toolbar" => "Bookmarks Toolbar",
+        "unfiled" => "Other Bookmarks",
+        "mobile" => "Mobile Bookmarks",
+        title => title,
+    }
+}
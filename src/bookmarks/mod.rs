@@ -0,0 +1,353 @@
+//! Searching the bookmarks of the browsers installed locally
+//!
+//! Firefox keeps its bookmarks in the `places.sqlite` database of each profile, and Chromium based
+//! browsers (Chrome, Chromium and Brave) in the `Bookmarks` JSON file of each profile. The profiles
+//! are found in the default locations of the browsers in the `[bookmarks]` config, on top of the
+//! ones configured explicitly, and their bookmarks are read again once those files have changed.
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use iced::{
+    futures::{SinkExt, Stream},
+    stream,
+};
+
+use crate::{
+    app::{
+        Message,
+        apps::{AppCommand, SimpleApp},
+    },
+    commands::Function,
+    config::{Bookmarks, Browser},
+};
+
+mod chromium;
+mod firefox;
+
+/// How often the bookmark files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a bookmarks file has to stay unchanged before it's read again. Firefox writes to its
+/// database on almost every page visit, so this keeps it from being copied over and over while
+/// browsing
+const QUIET_PERIOD: Duration = Duration::from_secs(10);
+
+/// The longest a change waits to be read, for files that never stay unchanged for long enough
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The most bookmarks that are shown for a query
+const MAX_RESULTS: usize = 10;
+
+/// Queries shorter than this (in characters) don't search the bookmarks
+const MIN_QUERY_LEN: usize = 2;
+
+/// A bookmark, with the path of the folder it's in (e.g. `Bookmarks bar / Rust`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    pub folder: String,
+    /// The title, url and folder in lowercase, which is what queries are matched against
+    haystack: String,
+}
+
+impl Bookmark {
+    /// Creates a bookmark, or returns [`None`] if rustcast can't open its url (e.g. bookmarklets)
+    fn new(title: String, url: String, folder: String) -> Option<Self> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return None;
+        }
+
+        let haystack = format!("{title}\n{url}\n{folder}").to_lowercase();
+        Some(Self {
+            title,
+            url,
+            folder,
+            haystack,
+        })
+    }
+
+    /// The title, or the url if the bookmark doesn't have one
+    fn display_name(&self) -> &str {
+        if self.title.trim().is_empty() {
+            &self.url
+        } else {
+            &self.title
+        }
+    }
+
+    /// Converts the bookmark to a result that opens it
+    pub fn to_app(&self) -> SimpleApp {
+        let desc = if self.folder.is_empty() {
+            "Bookmark".to_string()
+        } else {
+            format!("Bookmark in {}", self.folder)
+        };
+
+        SimpleApp::new_builtin(
+            self.display_name(),
+            &self.display_name().to_lowercase(),
+            &desc,
+            AppCommand::Function(Function::OpenWebsite(self.url.clone())),
+        )
+    }
+}
+
+/// A bookmarks file of a browser profile
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Firefox(PathBuf),
+    Chromium(PathBuf),
+}
+
+impl Source {
+    /// Finds the bookmarks file of a profile directory, or guesses the format of a bookmarks file
+    fn from_path(path: &Path) -> Option<Self> {
+        if path.is_dir() {
+            let places = path.join(firefox::PLACES_FILE);
+            let bookmarks = path.join(chromium::BOOKMARKS_FILE);
+            if places.is_file() {
+                Some(Source::Firefox(places))
+            } else if bookmarks.is_file() {
+                Some(Source::Chromium(bookmarks))
+            } else {
+                None
+            }
+        } else if !path.is_file() {
+            None
+        } else if path
+            .file_name()
+            .is_some_and(|name| name == firefox::PLACES_FILE)
+        {
+            Some(Source::Firefox(path.to_path_buf()))
+        } else {
+            Some(Source::Chromium(path.to_path_buf()))
+        }
+    }
+
+    /// The last time the bookmarks file was changed
+    ///
+    /// Firefox writes recent changes to the write-ahead log first, so it is checked too.
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        match self {
+            Source::Firefox(path) => vec![modified(path), modified(&firefox::wal_path(path))],
+            Source::Chromium(path) => vec![modified(path)],
+        }
+    }
+
+    /// Reads the bookmarks in the file
+    fn read(&self) -> Result<Vec<Bookmark>, String> {
+        match self {
+            Source::Firefox(path) => firefox::read(path),
+            Source::Chromium(path) => chromium::read(path),
+        }
+    }
+}
+
+impl Browser {
+    /// The directories that the browser keeps its profiles in
+    fn profile_dirs(self) -> Vec<PathBuf> {
+        let home = dirs::home_dir().unwrap_or_default();
+        let config = dirs::config_dir().unwrap_or_default();
+
+        // Chromium based browsers keep their profiles in the local app data on windows
+        let (chromium_root, user_data) = if cfg!(target_os = "windows") {
+            (dirs::data_local_dir().unwrap_or_default(), "User Data")
+        } else {
+            (config.clone(), "")
+        };
+
+        match self {
+            Browser::Firefox if cfg!(target_os = "linux") => vec![
+                home.join(".mozilla/firefox"),
+                config.join("mozilla/firefox"),
+                home.join("snap/firefox/common/.mozilla/firefox"),
+                home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"),
+            ],
+            Browser::Firefox if cfg!(target_os = "macos") => vec![config.join("Firefox/Profiles")],
+            Browser::Firefox => vec![config.join("Mozilla/Firefox/Profiles")],
+            Browser::Chrome if cfg!(target_os = "linux") => vec![config.join("google-chrome")],
+            Browser::Chrome => vec![chromium_root.join("Google/Chrome").join(user_data)],
+            Browser::Chromium if cfg!(target_os = "linux") => vec![config.join("chromium")],
+            Browser::Chromium => vec![chromium_root.join("Chromium").join(user_data)],
+            Browser::Brave => vec![
+                chromium_root
+                    .join("BraveSoftware/Brave-Browser")
+                    .join(user_data),
+            ],
+        }
+    }
+}
+
+/// Finds the bookmark files of the configured browsers and profiles
+fn discover(config: &Bookmarks) -> Vec<Source> {
+    let profiles = config
+        .browsers
+        .iter()
+        .flat_map(|browser| browser.profile_dirs())
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()));
+
+    let configured = config
+        .profiles
+        .iter()
+        .map(|path| PathBuf::from(path.replace('~', &std::env::var("HOME").unwrap_or_default())));
+
+    let mut sources: Vec<Source> = Vec::new();
+    for source in profiles
+        .chain(configured)
+        .filter_map(|path| Source::from_path(&path))
+    {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+/// The bookmarks read from a source, and the changes to its files that haven't been read yet
+struct Watched {
+    source: Source,
+    modified: Vec<Option<SystemTime>>,
+    bookmarks: Vec<Bookmark>,
+    /// When the files first and last changed since they were read
+    changed: Option<(Instant, Instant)>,
+}
+
+impl Watched {
+    fn read(source: Source) -> Self {
+        let modified = source.modified();
+        let bookmarks = source.read().unwrap_or_else(|e| {
+            tracing::warn!(target: "bookmarks", "Failed to read {source:?}: {e}");
+            Vec::new()
+        });
+        Self {
+            source,
+            modified,
+            bookmarks,
+            changed: None,
+        }
+    }
+
+    /// Notes whether the files changed, and reads them again once they've been quiet for
+    /// [`QUIET_PERIOD`] (or changing for [`MAX_DELAY`]). Returns whether they were read
+    fn poll(&mut self, now: Instant) -> bool {
+        let modified = self.source.modified();
+        if modified != self.modified {
+            self.modified = modified;
+            let first = self.changed.map_or(now, |(first, _)| first);
+            self.changed = Some((first, now));
+        }
+
+        match self.changed {
+            Some((first, last))
+                if now.duration_since(last) >= QUIET_PERIOD
+                    || now.duration_since(first) >= MAX_DELAY =>
+            {
+                *self = Watched::read(self.source.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Finds the sources again, and reads the ones that are new or whose files changed. Returns
+/// whether any bookmarks were read or removed
+fn refresh(watched: &mut Vec<Watched>, config: &Bookmarks) -> bool {
+    let now = Instant::now();
+    let mut previous = std::mem::take(watched);
+    let mut changed = false;
+    for source in discover(config) {
+        match previous.iter().position(|known| known.source == source) {
+            Some(i) => {
+                let mut known = previous.swap_remove(i);
+                changed |= known.poll(now);
+                watched.push(known);
+            }
+            None => {
+                watched.push(Watched::read(source));
+                changed = true;
+            }
+        }
+    }
+    changed || !previous.is_empty()
+}
+
+/// The bookmarks of all the sources, without the ones that are in several profiles
+fn merge(watched: &[Watched]) -> Vec<Bookmark> {
+    let mut urls = HashSet::new();
+    watched
+        .iter()
+        .flat_map(|known| &known.bookmarks)
+        .filter(|bookmark| urls.insert(bookmark.url.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// The results for the bookmarks whose title, url or folder contain every word of the query,
+/// starting with the ones whose title starts with the query
+pub fn search(bookmarks: &[Bookmark], query_lc: &str) -> Vec<SimpleApp> {
+    if query_lc.chars().count() < MIN_QUERY_LEN {
+        return vec![];
+    }
+
+    let words: Vec<&str> = query_lc.split_whitespace().collect();
+    let mut matches: Vec<&Bookmark> = bookmarks
+        .iter()
+        .filter(|bookmark| words.iter().all(|word| bookmark.haystack.contains(word)))
+        .collect();
+    matches.sort_by_key(|bookmark| {
+        let title = bookmark.display_name().to_lowercase();
+        (!title.starts_with(query_lc), !title.contains(query_lc))
+    });
+
+    matches
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(Bookmark::to_app)
+        .collect()
+}
+
+/// The subscription that loads the bookmarks, and loads them again once their files change
+///
+/// Finding and reading the files is blocking, so it's done off the async executor.
+pub fn watch(config: &Bookmarks) -> impl Stream<Item = Message> + use<> {
+    let config = config.clone();
+    stream::channel(1, async move |mut output| {
+        if !config.enabled {
+            output.send(Message::BookmarksLoaded(vec![])).await.ok();
+            return;
+        }
+
+        let mut watched = Vec::new();
+        let mut loaded = false;
+        loop {
+            let config = config.clone();
+            let Ok((refreshed, changed)) = tokio::task::spawn_blocking(move || {
+                let changed = refresh(&mut watched, &config);
+                (watched, changed)
+            })
+            .await
+            else {
+                tracing::error!(target: "bookmarks", "Stopped watching the bookmarks");
+                return;
+            };
+            watched = refreshed;
+
+            if changed || !loaded {
+                loaded = true;
+                let bookmarks = merge(&watched);
+                tracing::debug!(target: "bookmarks", "Loaded {} bookmarks", bookmarks.len());
+                output.send(Message::BookmarksLoaded(bookmarks)).await.ok();
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+}
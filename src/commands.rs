@@ -11,9 +11,10 @@ use objc2_app_kit::NSWorkspace;
 #[cfg(target_os = "macos")]
 use objc2_foundation::NSURL;
 
-use crate::utils::open_application;
+use crate::utils::{open_application, percent_encode};
 use crate::{
-    config::Config, functions::calculator::Expr, functions::clipboard::ClipBoardContentType,
+    config::Config,
+    functions::{calc_value::Value, calculator::Expr, clipboard::ClipBoardContentType, plot::Plot},
 };
 
 /// The different functions that rustcast can perform
@@ -22,15 +23,69 @@ pub enum Function {
     OpenApp(PathBuf),
     RunShellCommand(String, String),
     OpenWebsite(String),
-    RandomVar(i32), // Easter egg function
+    OpenWith(String, Option<String>), // Url or path, app to open it with
+    RandomVar(i32),                   // Easter egg function
     CopyToClipboard(ClipBoardContentType),
+    CopyPlot(Plot), // Drawn as a PNG only when it's copied
     GoogleSearch(String),
-    Calculate(Expr),
+    WebSearch(String, String),      // Search url, query
+    Calculate(Expr, Value, String), // Expression, its value, the result to copy
     OpenPrefPane,
+    ExtensionAction(String, String), // Extension name, action id
+    InstallWasmExtension(String),
+    WasmExtensionAction(String, String), // Extension name, item id
     Quit,
 }
 
 impl Function {
+    /// The name used to configure this function in the `[builtins]` table, if it is a builtin
+    pub fn builtin_name(&self) -> Option<&'static str> {
+        match self {
+            Function::Quit => Some("quit"),
+            Function::OpenPrefPane => Some("settings"),
+            _ => None,
+        }
+    }
+
+    /// Whether the function asks for confirmation when the config doesn't say otherwise
+    ///
+    /// Only the power-related builtins do this, since running them by accident is annoying, and
+    /// installing WASM extensions, so the user gets to see the permissions they're granting
+    pub fn confirm_by_default(&self) -> bool {
+        matches!(self, Function::Quit | Function::InstallWasmExtension(_))
+    }
+
+    /// What the function does, in words, for prompts like the one asking to confirm it
+    pub fn describe(&self) -> String {
+        match self {
+            Function::OpenApp(path) => format!(
+                "Open {}",
+                path.file_stem()
+                    .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            ),
+            Function::RunShellCommand(command, _) => format!("Run `{command}`"),
+            Function::OpenWebsite(url) => format!("Open {url}"),
+            Function::OpenWith(target, Some(app)) => format!("Open {target} with {app}"),
+            Function::OpenWith(target, None) => format!("Open {target}"),
+            Function::RandomVar(_) => "Copy a random number".to_string(),
+            Function::CopyToClipboard(_) => "Copy to the clipboard".to_string(),
+            Function::CopyPlot(plot) => format!("Copy the plot of {}", plot.describe()),
+            Function::GoogleSearch(query) | Function::WebSearch(_, query) => {
+                format!("Search the web for {query}")
+            }
+            Function::Calculate(_, _, result) => format!("Copy {result}"),
+            Function::OpenPrefPane => "Open RustCast Preferences".to_string(),
+            Function::ExtensionAction(extension, action)
+            | Function::WasmExtensionAction(extension, action) => {
+                format!("Run {action} of the {extension} extension")
+            }
+            Function::InstallWasmExtension(extension) => {
+                format!("Install the {extension} extension")
+            }
+            Function::Quit => "Quit RustCast".to_string(),
+        }
+    }
+
     /// Run the command
     pub fn execute(&self, config: &Config, query: &str) {
         tracing::debug!(target: "event", "Executing command: {:?}", self);
@@ -54,11 +109,12 @@ impl Function {
             }
 
             Function::GoogleSearch(query_string) => {
-                let query_args = query_string.replace(' ', "+");
-                let query = config.search_url.replace("%s", &query_args);
-                let query = query.strip_suffix("?").unwrap_or(&query).to_string();
+                let query_string = query_string.strip_suffix('?').unwrap_or(query_string);
+                open_search(config.default_search_url(), query_string);
+            }
 
-                open::that(query).unwrap();
+            Function::WebSearch(search_url, query_string) => {
+                open_search(search_url, query_string);
             }
 
             Function::OpenWebsite(url) => {
@@ -72,11 +128,18 @@ impl Function {
                 open::that(open_url).unwrap();
             }
 
-            Function::Calculate(expr) => {
-                Clipboard::new()
-                    .unwrap()
-                    .set_text(expr.eval().map_or(String::new(), |x| x.to_string()))
-                    .unwrap_or(());
+            Function::OpenWith(target, app) => {
+                let result = match app {
+                    Some(app) => open::with_detached(target, app),
+                    None => open::that_detached(target),
+                };
+                if let Err(e) = result {
+                    tracing::error!("Error opening {target}: {e}");
+                }
+            }
+
+            Function::Calculate(_, _, display) => {
+                Clipboard::new().unwrap().set_text(display).unwrap_or(());
             }
 
             Function::CopyToClipboard(clipboard_content) => match clipboard_content {
@@ -88,6 +151,10 @@ impl Function {
                 }
             },
 
+            Function::CopyPlot(plot) => {
+                Clipboard::new().unwrap().set_image(plot.to_image()).ok();
+            }
+
             #[cfg(target_os = "macos")]
             Function::OpenPrefPane => {
                 thread::spawn(move || {
@@ -108,3 +175,11 @@ impl Function {
         }
     }
 }
+
+/// Opens a search url (with `%s` where the query goes) for the query
+fn open_search(search_url: &str, query: &str) {
+    let url = search_url.replace("%s", &percent_encode(query.trim()));
+    if let Err(e) = open::that(&url) {
+        tracing::error!("Error opening {url}: {e}");
+    }
+}
@@ -1,5 +1,9 @@
 //! This is the config file type definitions for rustcast
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use iced::{Font, font::Family, theme::Custom};
 use serde::{Deserialize, Serialize};
@@ -9,7 +13,13 @@ use tracing::Level;
 use crate::platform::windows::get_known_paths;
 use crate::{
     app::apps::{AppData, SimpleApp},
+    commands::Function,
+    functions::{
+        calc_value::{DecimalSeparator, Format},
+        calculator::AngleUnit,
+    },
     platform::get_img_handle,
+    unit_conversion::{Preferences, UnitCategory, UnitSystem},
 };
 
 mod include_patterns;
@@ -27,8 +37,18 @@ pub struct Config {
 
     pub placeholder: String,
     pub search_url: String,
+    pub search_engines: Vec<SearchEngine>,
     pub show_trayicon: bool,
     pub shells: Vec<Shelly>,
+    pub quicklinks: Vec<Quicklink>,
+    pub bookmarks: Bookmarks,
+    pub calculator: Calculator,
+    pub units: Units,
+    pub world_clock: Vec<String>,
+    pub builtins: HashMap<String, Builtin>,
+    pub extensions: Vec<ExtensionConfig>,
+    pub mcp_servers: Vec<ExtensionConfig>,
+    pub modes: BTreeMap<String, Mode>,
 
     #[cfg(target_os = "macos")]
     pub haptic_feedback: bool,
@@ -64,12 +84,22 @@ impl Default for Config {
             theme: Theme::default(),
             placeholder: String::from("Time to be productive!"),
             search_url: "https://google.com/search?q=%s".to_string(),
+            search_engines: vec![],
 
             #[cfg(target_os = "macos")]
             haptic_feedback: false,
 
             show_trayicon: true,
             shells: vec![],
+            quicklinks: vec![],
+            bookmarks: Bookmarks::default(),
+            calculator: Calculator::default(),
+            units: Units::default(),
+            world_clock: vec![],
+            builtins: HashMap::new(),
+            extensions: vec![],
+            mcp_servers: vec![],
+            modes: BTreeMap::new(),
             index_dirs,
             index_exclude_patterns: vec![],
             index_include_patterns: vec![],
@@ -85,6 +115,51 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Whether the user should be asked to confirm before the function is run
+    ///
+    /// Shell commands only ask if their `confirm` option is set, while builtins fall back to
+    /// [`Function::confirm_by_default`] unless overridden in the `[builtins]` table.
+    pub fn requires_confirmation(&self, function: &Function) -> bool {
+        match function {
+            Function::RunShellCommand(command, alias) => self.shells.iter().any(|shell| {
+                shell.confirm && shell.alias_lc == *alias && shell.command == *command
+            }),
+            function => function
+                .builtin_name()
+                .and_then(|name| self.builtins.get(name))
+                .and_then(|builtin| builtin.confirm)
+                .unwrap_or_else(|| function.confirm_by_default()),
+        }
+    }
+
+    /// The search url used when no `!bang` is given: the default engine's if there is one, or
+    /// `search_url` otherwise
+    pub fn default_search_url(&self) -> &str {
+        self.search_engines
+            .iter()
+            .find(|engine| engine.default)
+            .map_or(&self.search_url, |engine| &engine.url)
+    }
+
+    /// If the query has a `!bang` of one of the search engines (at its start or end), returns the
+    /// engine and the query without the bang
+    pub fn find_bang(&self, query: &str) -> Option<(&SearchEngine, String)> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let (bang, rest) = match words.as_slice() {
+            [first, rest @ ..] if first.starts_with('!') => (*first, rest),
+            [rest @ .., last] if last.starts_with('!') => (*last, rest),
+            _ => return None,
+        };
+
+        let engine = self
+            .search_engines
+            .iter()
+            .find(|engine| engine.bang().eq_ignore_ascii_case(&bang[1..]))?;
+        Some((engine, rest.join(" ")))
+    }
+}
+
 /// The settings you can set for the theme
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
@@ -206,12 +281,15 @@ impl Default for Buffer {
 /// Command is the command it will run when the button is clicked
 /// `Icon_path` is the path to an icon, but this is optional
 /// Alias is the text that is used to call this command / search for it
+/// Confirm is whether the user has to confirm before the command is run
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Shelly {
     command: String,
     icon_path: Option<String>,
     alias: String,
     alias_lc: String,
+    #[serde(default)]
+    confirm: bool,
 }
 
 impl Shelly {
@@ -235,6 +313,218 @@ impl Shelly {
     }
 }
 
+/// A search engine that can be picked with its `!bang`
+///
+/// - `name` is shown in the search results
+/// - `bang` is what to type to search with it, with or without the `!` (e.g. `w` for `!w rust`)
+/// - `url` is the search url, with `%s` where the query goes
+/// - `default` is whether it's used when no bang is given, instead of `search_url`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchEngine {
+    pub name: String,
+    pub bang: String,
+    pub url: String,
+    #[serde(default)]
+    pub default: bool,
+}
+
+impl SearchEngine {
+    /// The bang, without the `!`
+    pub fn bang(&self) -> &str {
+        self.bang.trim_start_matches('!')
+    }
+}
+
+/// A URL or path that is opened by typing its keyword, followed by what to fill its placeholders
+/// with
+///
+/// - `keyword` is what the query has to start with (e.g. `gh`)
+/// - `url` is the URL or path, with placeholders like `{owner}` or `{1}` (see
+///   [`crate::functions::quicklink::expand`])
+/// - `name` is shown in the search results, and defaults to the keyword
+/// - `open_with` is the browser / app to open it with, instead of the default one
+/// - `icon_path` is the path to an icon, but this is optional
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Quicklink {
+    pub keyword: String,
+    pub url: String,
+    pub name: Option<String>,
+    pub open_with: Option<String>,
+    pub icon_path: Option<String>,
+}
+
+/// The settings of the calculator
+///
+/// - `angle_unit` is whether the trigonometric functions use `radians` (the default) or `degrees`
+/// - `precision` is the most digits shown after the decimal point
+/// - `digit_grouping` is whether results are shown with their digits grouped (`1,234,567`). The
+///   copied result never is
+/// - `decimal_separator` is whether numbers are written with a decimal point (`"."`, the default)
+///   or a decimal comma (`","`), in which case digits are grouped with points (`1.234.567,8`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Calculator {
+    pub angle_unit: AngleUnit,
+    pub precision: usize,
+    pub digit_grouping: bool,
+    pub decimal_separator: DecimalSeparator,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self {
+            angle_unit: AngleUnit::default(),
+            precision: 10,
+            digit_grouping: false,
+            decimal_separator: DecimalSeparator::default(),
+        }
+    }
+}
+
+impl Calculator {
+    /// How results are written
+    pub fn format(&self) -> Format {
+        Format {
+            precision: self.precision,
+            digit_grouping: self.digit_grouping,
+            decimal_separator: self.decimal_separator,
+        }
+    }
+}
+
+/// The settings of unit conversions
+///
+/// - `system` is the system of units conversions without a target are shown in first: `metric`
+///   (the default), `imperial` or `us`, whose volumes differ from imperial ones
+/// - `max_results` is the most conversions shown for a query without a target
+/// - `targets` are the units each category is converted to first, like `mi` for `length`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Units {
+    pub system: UnitSystem,
+    pub max_results: usize,
+    pub targets: HashMap<UnitCategory, String>,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            system: UnitSystem::default(),
+            max_results: 4,
+            targets: HashMap::new(),
+        }
+    }
+}
+
+impl Units {
+    /// Which units conversions without a target are shown in
+    pub fn preferences(&self) -> Preferences<'_> {
+        Preferences {
+            system: self.system,
+            targets: &self.targets,
+            max_results: self.max_results,
+        }
+    }
+}
+
+/// Where to find the browser bookmarks to search through
+///
+/// - `enabled` is whether bookmarks are searched at all
+/// - `browsers` are the browsers whose profiles are found automatically
+/// - `profiles` are extra profile directories (or `places.sqlite` / `Bookmarks` files) to read,
+///   for browsers that are installed somewhere else or aren't in `browsers`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Bookmarks {
+    pub enabled: bool,
+    pub browsers: Vec<Browser>,
+    pub profiles: Vec<String>,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            browsers: vec![
+                Browser::Firefox,
+                Browser::Chrome,
+                Browser::Chromium,
+                Browser::Brave,
+            ],
+            profiles: vec![],
+        }
+    }
+}
+
+/// The browsers whose bookmarks rustcast can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Browser {
+    Firefox,
+    Chrome,
+    Chromium,
+    Brave,
+}
+
+/// Per-builtin overrides, keyed by the builtin's search name (e.g. `quit`)
+///
+/// `confirm` is whether the user has to confirm before the builtin is run. When it is left out,
+/// the builtin's own default is used.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Builtin {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+}
+
+/// An out-of-process extension that rustcast spawns and talks to over stdio
+///
+/// `name` identifies the extension in logs and results, `command` is the executable to run (`~`
+/// is expanded), and `args` and `env` are passed to it.
+///
+/// MCP servers are configured the same way.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExtensionConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// The scripts to run when switching to / away from a mode
+///
+/// A mode is either just the path of the script to run when switching to it, or a table with an
+/// `enter` and / or `exit` script.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Mode {
+    Enter(String),
+    Scripts {
+        enter: Option<String>,
+        exit: Option<String>,
+    },
+}
+
+impl Mode {
+    /// The script to run when switching to the mode
+    pub fn enter_script(&self) -> Option<&str> {
+        match self {
+            Mode::Enter(enter) => Some(enter),
+            Mode::Scripts { enter, .. } => enter.as_deref(),
+        }
+    }
+
+    /// The script to run when switching away from the mode
+    pub fn exit_script(&self) -> Option<&str> {
+        match self {
+            Mode::Enter(_) => None,
+            Mode::Scripts { exit, .. } => exit.as_deref(),
+        }
+    }
+}
+
 // Exists for serde reasons
 const fn true_f() -> bool {
     true
@@ -0,0 +1,231 @@
+//! The bookkeeping shared by extensions and MCP servers: they're only spawned once rustcast is
+//! first searched, are restarted if they crash, and are shut down when rustcast quits or they're
+//! removed from the config
+use std::{
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{app::apps::SimpleApp, config::ExtensionConfig, extensions::process::ExtensionProcess};
+
+/// How long to wait before restarting a process that crashed
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Something that happened to a hosted process, for [`Registration::log`]
+pub enum Event<'a, T> {
+    Restarting,
+    Started(&'a T),
+    Failed(&'a anyhow::Error),
+}
+
+/// What a kind of process (an extension or an MCP server) registers when it starts
+pub trait Registration: Default + Send + 'static {
+    /// Does the handshake with a process that was just spawned
+    fn handshake(process: &ExtensionProcess) -> impl Future<Output = anyhow::Result<Self>> + Send;
+
+    /// The commands to add to the search results for what the process registered
+    fn apps(&self, name: &str) -> Vec<SimpleApp>;
+
+    /// Logs what happened to the process, under this kind's target
+    fn log(name: &str, event: Event<'_, Self>);
+}
+
+/// The state of a single process
+enum State {
+    Stopped,
+    Starting,
+    Running(Arc<ExtensionProcess>),
+}
+
+/// A configured process, and what it registered the last time it started
+pub struct Hosted<T> {
+    config: ExtensionConfig,
+    state: State,
+    registration: T,
+    last_start: Option<Instant>,
+}
+
+impl<T: Registration> Hosted<T> {
+    fn new(config: ExtensionConfig) -> Self {
+        Self {
+            config,
+            state: State::Stopped,
+            registration: T::default(),
+            last_start: None,
+        }
+    }
+
+    /// Whether the process should be (re)started now
+    fn needs_start(&self) -> bool {
+        let stopped = match &self.state {
+            State::Stopped => true,
+            State::Starting => false,
+            State::Running(process) => !process.is_alive(),
+        };
+
+        stopped
+            && self
+                .last_start
+                .is_none_or(|last_start| last_start.elapsed() >= RESTART_DELAY)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn registration(&self) -> &T {
+        &self.registration
+    }
+
+    /// The process, if it's running
+    pub fn process(&self) -> Option<Arc<ExtensionProcess>> {
+        match &self.state {
+            State::Running(process) if process.is_alive() => Some(process.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps track of all the configured processes of one kind
+pub struct ProcessHost<T> {
+    processes: Arc<Mutex<Vec<Hosted<T>>>>,
+}
+
+impl<T> Clone for ProcessHost<T> {
+    fn clone(&self) -> Self {
+        Self {
+            processes: self.processes.clone(),
+        }
+    }
+}
+
+impl<T> Default for ProcessHost<T> {
+    fn default() -> Self {
+        Self {
+            processes: Arc::default(),
+        }
+    }
+}
+
+impl<T: Registration> ProcessHost<T> {
+    /// Creates a host for the configured processes, without starting any of them
+    pub fn new(configs: &[ExtensionConfig]) -> Self {
+        Self {
+            processes: Arc::new(Mutex::new(
+                configs.iter().cloned().map(Hosted::new).collect(),
+            )),
+        }
+    }
+
+    /// Applies a new config, stopping processes that were removed or changed
+    pub fn reconfigure(&self, configs: &[ExtensionConfig]) {
+        let mut processes = self.processes.lock().unwrap();
+
+        let (kept, removed): (Vec<_>, Vec<_>) = processes
+            .drain(..)
+            .partition(|hosted| configs.contains(&hosted.config));
+
+        for hosted in removed {
+            if let Some(process) = hosted.process() {
+                process.shutdown();
+            }
+        }
+
+        *processes = kept;
+        for config in configs {
+            if !processes.iter().any(|hosted| hosted.config == *config) {
+                processes.push(Hosted::new(config.clone()));
+            }
+        }
+    }
+
+    /// Whether any process hasn't been started yet, or crashed and should be restarted
+    pub fn needs_start(&self) -> bool {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .any(Hosted::needs_start)
+    }
+
+    /// Starts every process that needs it, returning the commands each of them registered
+    pub async fn start(self) -> Vec<(String, Vec<SimpleApp>)> {
+        let to_start: Vec<ExtensionConfig> = {
+            let mut processes = self.processes.lock().unwrap();
+            processes
+                .iter_mut()
+                .filter(|hosted| hosted.needs_start())
+                .map(|hosted| {
+                    if matches!(hosted.state, State::Running(_)) {
+                        T::log(&hosted.config.name, Event::Restarting);
+                    }
+                    hosted.state = State::Starting;
+                    hosted.last_start = Some(Instant::now());
+                    hosted.config.clone()
+                })
+                .collect()
+        };
+
+        let mut registered = Vec::new();
+        for config in to_start {
+            let started = match ExtensionProcess::spawn(&config) {
+                Ok(process) => {
+                    let process = Arc::new(process);
+                    T::handshake(&process)
+                        .await
+                        .map(|registration| (process, registration))
+                }
+                Err(e) => Err(e),
+            };
+
+            let mut processes = self.processes.lock().unwrap();
+            let hosted = processes.iter_mut().find(|hosted| hosted.config == config);
+
+            match (started, hosted) {
+                (Ok((process, registration)), Some(hosted)) => {
+                    T::log(&config.name, Event::Started(&registration));
+                    registered.push((config.name.clone(), registration.apps(&config.name)));
+                    hosted.registration = registration;
+                    hosted.state = State::Running(process);
+                }
+                // Removed from the config while it was starting
+                (Ok((process, _)), None) => {
+                    process.shutdown();
+                }
+                (Err(e), hosted) => {
+                    T::log(&config.name, Event::Failed(&e));
+                    if let Some(hosted) = hosted {
+                        hosted.state = State::Stopped;
+                    }
+                }
+            }
+        }
+
+        registered
+    }
+
+    /// Finds the first process for which `f` returns something
+    pub fn find_map<R>(&self, f: impl FnMut(&Hosted<T>) -> Option<R>) -> Option<R> {
+        self.processes.lock().unwrap().iter().find_map(f)
+    }
+
+    /// The process with the given name, if it's running
+    pub fn process(&self, name: &str) -> Option<Arc<ExtensionProcess>> {
+        self.find_map(|hosted| (hosted.name() == name).then(|| hosted.process())?)
+    }
+
+    /// Shuts down every running process, returning the threads that kill the ones that don't exit
+    /// in time
+    pub fn shutdown(&self) -> Vec<JoinHandle<()>> {
+        let mut processes = self.processes.lock().unwrap();
+        processes
+            .iter_mut()
+            .filter_map(|hosted| {
+                let process = hosted.process();
+                hosted.state = State::Stopped;
+                Some(process?.shutdown())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,151 @@
+//! Out-of-process extensions
+//!
+//! Extensions are executables listed under `[[extensions]]` in the config. rustcast talks to them
+//! with JSON-RPC over their stdin / stdout; the protocol (and an SDK for writing extensions) lives
+//! in the `rustcast-extension` crate.
+//!
+//! Extensions are hosted by [`host::ProcessHost`]: they're only spawned once rustcast is first
+//! searched, are restarted if they crash, and are shut down when rustcast quits or they're removed
+//! from the config.
+use rustcast_extension::protocol::{
+    ACTION_INVOKED, ActionInvokedParams, INITIALIZE, InitializeParams, InitializeResult, SEARCH,
+    SearchParams, SearchResult,
+};
+
+use crate::{
+    app::{
+        Message,
+        apps::{AppCommand, AppData, SimpleApp},
+    },
+    commands::Function,
+};
+
+use host::{Event, ProcessHost, Registration};
+use process::ExtensionProcess;
+
+pub mod host;
+pub mod process;
+
+/// Keeps track of all the configured extensions and their processes
+pub type ExtensionHost = ProcessHost<InitializeResult>;
+
+impl Registration for InitializeResult {
+    async fn handshake(process: &ExtensionProcess) -> anyhow::Result<Self> {
+        let params = InitializeParams {
+            rustcast_version: option_env!("APP_VERSION")
+                .unwrap_or("Unknown Version")
+                .to_string(),
+        };
+        process.request(INITIALIZE, params).await
+    }
+
+    fn apps(&self, name: &str) -> Vec<SimpleApp> {
+        command_apps(name, self)
+    }
+
+    fn log(name: &str, event: Event<'_, Self>) {
+        match event {
+            Event::Restarting => {
+                tracing::warn!(target: "extension", "[{name}] Crashed, restarting")
+            }
+            Event::Started(_) => tracing::info!(target: "extension", "[{name}] Started"),
+            Event::Failed(e) => {
+                tracing::error!(target: "extension", "[{name}] Failed to start: {e:#}");
+            }
+        }
+    }
+}
+
+impl ExtensionHost {
+    /// If the query starts with a keyword registered by an extension, returns a future that asks
+    /// that extension for results
+    pub fn keyword_search(
+        &self,
+        query: &str,
+    ) -> Option<impl Future<Output = Vec<SimpleApp>> + use<>> {
+        let (keyword, rest) = query.trim_start().split_once(char::is_whitespace)?;
+
+        let (name, process, keyword) = self.find_map(|ext| {
+            let keyword = ext
+                .registration()
+                .keywords
+                .iter()
+                .find(|registered| registered.eq_ignore_ascii_case(keyword))?;
+            Some((ext.name().to_string(), ext.process()?, keyword.clone()))
+        })?;
+
+        let params = SearchParams {
+            keyword,
+            query: rest.trim().to_string(),
+        };
+
+        Some(async move {
+            match process.request::<SearchResult>(SEARCH, params).await {
+                Ok(result) => result
+                    .items
+                    .into_iter()
+                    .map(|item| {
+                        let desc = item.subtitle.unwrap_or_else(|| name.clone());
+                        extension_app(&name, item.id, &item.title, "", &desc)
+                    })
+                    .collect(),
+                Err(e) => {
+                    tracing::error!(target: "extension", "{e:#}");
+                    Vec::new()
+                }
+            }
+        })
+    }
+
+    /// Tells an extension that one of its commands or results was run
+    pub fn action_invoked(&self, extension: &str, id: &str, query: &str) {
+        let Some(process) = self.process(extension) else {
+            tracing::warn!(target: "extension", "[{extension}] Not running, can't run {id}");
+            return;
+        };
+
+        let params = ActionInvokedParams {
+            id: id.to_string(),
+            query: query.to_string(),
+        };
+        if let Err(e) = process.notify(ACTION_INVOKED, params) {
+            tracing::error!(target: "extension", "[{extension}] Failed to send action: {e:#}");
+        }
+    }
+}
+
+/// Whether the app runs an action of the given extension, or a tool of the given MCP server
+pub fn is_from_extension(app: &SimpleApp, extension: &str) -> bool {
+    matches!(
+        &app.data,
+        AppData::Builtin {
+            command: AppCommand::Function(Function::ExtensionAction(name, _))
+                | AppCommand::Message(Message::OpenMcpTool(name, _)),
+            ..
+        } if name == extension
+    )
+}
+
+fn command_apps(extension: &str, registration: &InitializeResult) -> Vec<SimpleApp> {
+    registration
+        .commands
+        .iter()
+        .map(|command| {
+            let alias = command
+                .alias
+                .clone()
+                .unwrap_or_else(|| command.title.to_lowercase());
+            let desc = command.subtitle.as_deref().unwrap_or(extension);
+            extension_app(extension, command.id.clone(), &command.title, &alias, desc)
+        })
+        .collect()
+}
+
+fn extension_app(extension: &str, id: String, name: &str, alias: &str, desc: &str) -> SimpleApp {
+    SimpleApp::new_builtin(
+        name,
+        alias,
+        desc,
+        AppCommand::Function(Function::ExtensionAction(extension.to_string(), id)),
+    )
+}
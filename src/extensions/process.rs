@@ -0,0 +1,237 @@
+//! A single running extension process and the JSON-RPC plumbing for it
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, anyhow};
+use rustcast_extension::protocol::{
+    JSONRPC_VERSION, METHOD_NOT_FOUND, Request, Response, SHUTDOWN,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+
+use crate::config::ExtensionConfig;
+
+/// How long to wait for an extension to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an extension gets to exit by itself after being told to shut down
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// A spawned extension, with a thread reading its stdout and one forwarding its stderr to the logs
+///
+/// This is also used for MCP servers, which speak the same newline-delimited JSON-RPC over stdio
+pub struct ExtensionProcess {
+    name: String,
+    child: Arc<Mutex<Child>>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: Pending,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+}
+
+impl ExtensionProcess {
+    /// Spawns the extension described by the config
+    pub fn spawn(config: &ExtensionConfig) -> anyhow::Result<Self> {
+        let command = config
+            .command
+            .replace('~', &std::env::var("HOME").unwrap_or_default());
+
+        let mut child = Command::new(&command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to spawn extension {} ({command})", config.name))?;
+
+        let stdin = Arc::new(Mutex::new(
+            child.stdin.take().context("Extension stdin not piped")?,
+        ));
+        let stdout = child.stdout.take().context("Extension stdout not piped")?;
+        let stderr = child.stderr.take().context("Extension stderr not piped")?;
+
+        let pending: Pending = Arc::default();
+        let alive = Arc::new(AtomicBool::new(true));
+
+        {
+            let name = config.name.clone();
+            let pending = pending.clone();
+            let alive = alive.clone();
+            let stdin = stdin.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else { break };
+                    let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                        tracing::warn!(target: "extension", "[{name}] Invalid message: {line}");
+                        continue;
+                    };
+
+                    if let Some(method) = message.get("method").and_then(Value::as_str) {
+                        if let Some(id) = message.get("id") {
+                            answer_request(&stdin, id, method);
+                        }
+                        continue;
+                    }
+
+                    match serde_json::from_value::<Response>(message) {
+                        Ok(response) => {
+                            let sender = pending.lock().unwrap().remove(&response.id);
+                            let result = match (response.result, response.error) {
+                                (_, Some(error)) => Err(error.message),
+                                (result, None) => Ok(result.unwrap_or(Value::Null)),
+                            };
+                            if let Some(sender) = sender {
+                                sender.send(result).ok();
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!(target: "extension", "[{name}] Invalid message: {e}");
+                        }
+                    }
+                }
+
+                alive.store(false, Ordering::SeqCst);
+                // Dropping the senders makes every request still waiting fail
+                pending.lock().unwrap().clear();
+                tracing::info!(target: "extension", "[{name}] Exited");
+            });
+        }
+
+        {
+            let name = config.name.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    tracing::info!(target: "extension", "[{name}] {line}");
+                }
+            });
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            child: Arc::new(Mutex::new(child)),
+            stdin,
+            pending,
+            next_id: AtomicU64::new(1),
+            alive,
+        })
+    }
+
+    /// Whether the process is still running
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Sends a request and waits for the extension to answer it
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: impl Serialize,
+    ) -> anyhow::Result<T> {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+            .await
+    }
+
+    /// Same as [`Self::request`], for requests that may take longer than usual to answer
+    pub async fn request_with_timeout<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: impl Serialize,
+        timeout: Duration,
+    ) -> anyhow::Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+
+        if let Err(e) = self.send(&Request::new(id, method, serde_json::to_value(params)?)) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        let result = match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => return Err(anyhow!("Extension {} exited", self.name)),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(anyhow!("Extension {} timed out on {method}", self.name));
+            }
+        };
+
+        let value = result.map_err(|e| anyhow!("Extension {} failed {method}: {e}", self.name))?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Sends a notification, which the extension doesn't answer
+    pub fn notify(&self, method: &str, params: impl Serialize) -> anyhow::Result<()> {
+        self.send(&Request::notification(
+            method,
+            serde_json::to_value(params)?,
+        ))
+    }
+
+    /// Asks the extension to shut down, killing it if it doesn't exit in time
+    ///
+    /// This is called from the UI thread (when the config is reloaded), so the waiting happens on
+    /// a thread of its own. Join it before exiting, or the extension is left running
+    pub fn shutdown(&self) -> JoinHandle<()> {
+        if self.is_alive() {
+            self.notify(SHUTDOWN, Value::Null).ok();
+        }
+
+        let name = self.name.clone();
+        let child = self.child.clone();
+        thread::spawn(move || {
+            let mut child = child.lock().unwrap();
+            let start = Instant::now();
+            while start.elapsed() < SHUTDOWN_GRACE {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            tracing::warn!(target: "extension", "[{name}] Didn't shut down in time, killing it");
+            child.kill().ok();
+            child.wait().ok();
+        })
+    }
+
+    fn send(&self, request: &Request) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+
+        let mut stdin = self.stdin.lock().unwrap();
+        stdin.write_all(&line)?;
+        stdin.flush()?;
+        Ok(())
+    }
+}
+
+/// Answers a request sent by the extension itself. Only `ping` (used by MCP servers) is supported
+fn answer_request(stdin: &Mutex<ChildStdin>, id: &Value, method: &str) {
+    let response = if method == "ping" {
+        json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "result": {} })
+    } else {
+        json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "id": id,
+            "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method {method}") },
+        })
+    };
+
+    let mut stdin = stdin.lock().unwrap();
+    writeln!(stdin, "{response}").ok();
+    stdin.flush().ok();
+}
@@ -0,0 +1,212 @@
+//! Complex numbers, for calculations that use `i` or have no real result (like `sqrt(-4)`)
+use std::{
+    f64::consts::FRAC_PI_2,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// The biggest whole power that is calculated by repeated multiplication, which keeps results like
+/// `(1+i)^2` exact, rather than through logarithms
+const MAX_EXACT_POWER: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    /// The imaginary unit
+    pub const I: Complex = Complex { re: 0., im: 1. };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn real(re: f64) -> Self {
+        Self { re, im: 0. }
+    }
+
+    pub fn is_real(self) -> bool {
+        self.im == 0.
+    }
+
+    /// The distance from 0
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// The angle from the positive real axis, in radians between -pi and pi
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    pub fn exp(self) -> Self {
+        let (sin, cos) = self.im.sin_cos();
+        Self::new(cos, sin).scale(self.re.exp())
+    }
+
+    /// The principal natural logarithm
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    /// The principal square root
+    pub fn sqrt(self) -> Self {
+        let r = self.abs();
+        let re = ((r + self.re) / 2.).sqrt();
+        let im = ((r - self.re) / 2.).sqrt();
+        Self::new(re, if self.im < 0. { -im } else { im })
+    }
+
+    /// The principal cube root
+    pub fn cbrt(self) -> Self {
+        if self.re == 0. && self.im == 0. {
+            return self;
+        }
+        (self.ln().scale(1. / 3.)).exp()
+    }
+
+    /// The principal value of this number to the power of another one
+    pub fn pow(self, exponent: Self) -> Self {
+        if exponent.is_real()
+            && exponent.re.fract() == 0.
+            && exponent.re.abs() <= f64::from(MAX_EXACT_POWER)
+        {
+            #[allow(clippy::cast_possible_truncation)]
+            let n = exponent.re as i32;
+            let power = (0..n.unsigned_abs()).fold(Self::real(1.), |power, _| power * self);
+            return if n < 0 { Self::real(1.) / power } else { power };
+        }
+        if self.re == 0. && self.im == 0. {
+            return if exponent.re > 0. {
+                self
+            } else {
+                Self::new(f64::NAN, f64::NAN)
+            };
+        }
+        (exponent * self.ln()).exp()
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+
+    pub fn sinh(self) -> Self {
+        Self::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    pub fn cosh(self) -> Self {
+        Self::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    pub fn tanh(self) -> Self {
+        self.sinh() / self.cosh()
+    }
+
+    /// The principal arcsine: -i ln(iz + sqrt(1 - z^2))
+    pub fn asin(self) -> Self {
+        -Self::I * (Self::I * self + (Self::real(1.) - self * self).sqrt()).ln()
+    }
+
+    /// The principal arccosine: pi/2 - asin(z)
+    pub fn acos(self) -> Self {
+        Self::real(FRAC_PI_2) - self.asin()
+    }
+
+    /// The principal arctangent: i/2 (ln(1 - iz) - ln(1 + iz))
+    pub fn atan(self) -> Self {
+        let iz = Self::I * self;
+        Self::I.scale(0.5) * ((Self::real(1.) - iz).ln() - (Self::real(1.) + iz).ln())
+    }
+
+    /// Rounds off parts that are only there because of rounding errors, so that e^(i pi) is -1
+    /// rather than -1 + 1.2246467991473532e-16i
+    ///
+    /// A part is only rounded off when it's tiny next to the other one, so small numbers like
+    /// 1e-13i keep it
+    pub fn snap(self, epsilon: f64) -> Self {
+        let snap = |x: f64, other: f64| {
+            if x.abs() < epsilon * other.abs() {
+                0.
+            } else {
+                x
+            }
+        };
+        Self::new(snap(self.re, self.im), snap(self.im, self.re))
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let denominator = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / denominator,
+            (self.im * rhs.re - self.re * rhs.im) / denominator,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
@@ -0,0 +1,276 @@
+//! The calculator's history and variables, which are kept in a file next to the config so that
+//! they survive restarts
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::apps::{AppCommand, SimpleApp},
+    commands::Function,
+    functions::{
+        calc_value::Value,
+        calculator::{ANS, AngleUnit, Env, Expr},
+        clipboard::ClipBoardContentType,
+    },
+    utils::get_config_file_path,
+};
+
+/// The most calculations that are remembered
+const MAX_ENTRIES: usize = 100;
+
+/// A calculation that was run, with its result as it was displayed
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    pub expression: String,
+    pub result: String,
+}
+
+impl HistoryEntry {
+    /// Returns the result for the history page, which copies the result
+    pub fn to_app(&self) -> SimpleApp {
+        SimpleApp::new_builtin(
+            &self.result,
+            &self.expression,
+            &self.expression,
+            AppCommand::Function(Function::CopyToClipboard(ClipBoardContentType::Text(
+                self.result.clone(),
+            ))),
+        )
+    }
+}
+
+/// The past calculations (newest first) and the variables that were assigned
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CalcHistory {
+    pub entries: Vec<HistoryEntry>,
+    #[serde(with = "variables")]
+    pub variables: HashMap<String, Value>,
+}
+
+impl CalcHistory {
+    /// Where the history is stored
+    fn file() -> PathBuf {
+        get_config_file_path().with_file_name("calculator.json")
+    }
+
+    /// Reads the history saved the last time rustcast ran
+    ///
+    /// A file that can't be read is moved out of the way rather than overwritten, so the history
+    /// in it isn't lost
+    pub fn load() -> Self {
+        let file = Self::file();
+        let Ok(content) = fs::read_to_string(&file) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&content) {
+            Ok(history) => history,
+            Err(e) => {
+                let backup = file.with_extension("json.bak");
+                tracing::error!(
+                    target: "calculator",
+                    "Failed to read the calculator history, moving it to {}: {e}",
+                    backup.display()
+                );
+                if let Err(e) = fs::rename(&file, &backup) {
+                    tracing::error!(target: "calculator", "Failed to move the history: {e}");
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Saves the history for the next time rustcast runs
+    fn save(&self) {
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| fs::write(Self::file(), content).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            tracing::error!(target: "calculator", "Failed to save the calculator history: {e}");
+        }
+    }
+
+    /// The environment to evaluate expressions in
+    pub fn env(&self, angle_unit: AngleUnit) -> Env<'_> {
+        Env {
+            angle_unit,
+            variables: &self.variables,
+        }
+    }
+
+    /// Remembers a calculation, making its value `ans` and assigning its variable if it is an
+    /// assignment
+    ///
+    /// Infinite results are only added to the history, since JSON has no infinity to save them as
+    pub fn record(&mut self, expression: &str, expr: &Expr, value: &Value, display: &str) {
+        self.remember(expression, expr, value, display);
+        self.save();
+    }
+
+    /// Adds a calculation to the history, without saving it
+    fn remember(&mut self, expression: &str, expr: &Expr, value: &Value, display: &str) {
+        let is_variable = match value {
+            Value::Exact(_) => true,
+            Value::Float(x) => x.is_finite(),
+            Value::Complex(z) => z.re.is_finite() && z.im.is_finite(),
+        };
+        if is_variable {
+            if let Expr::Assign { name, .. } = expr {
+                self.variables.insert(name.clone(), value.clone());
+            }
+            self.variables.insert(ANS.to_string(), value.clone());
+        }
+
+        let entry = HistoryEntry {
+            expression: expression.trim().to_string(),
+            result: display.to_string(),
+        };
+        self.entries.retain(|existing| *existing != entry);
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+/// Variables are saved as numbers, as fractions (`"1/3"`) if they're exact so that they're still
+/// exact when they're read back, or as `{"re": 1, "im": 2}` if they're complex
+mod variables {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    use crate::functions::{calc_complex::Complex, calc_value::Value};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Float(f64),
+        Exact(String),
+        Complex { re: f64, im: f64 },
+    }
+
+    pub fn serialize<S>(
+        variables: &HashMap<String, Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(variables.iter().map(|(name, value)| {
+            let saved = match value {
+                Value::Exact(r) => Saved::Exact(r.to_string()),
+                Value::Float(x) => Saved::Float(*x),
+                Value::Complex(z) => Saved::Complex { re: z.re, im: z.im },
+            };
+            (name, saved)
+        }))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<String, Value>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        HashMap::<String, Saved>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, saved)| {
+                let value = match saved {
+                    Saved::Float(x) => Value::Float(x),
+                    Saved::Exact(r) => Value::Exact(
+                        r.parse()
+                            .map_err(|_| D::Error::custom(format!("{r} isn't a fraction")))?,
+                    ),
+                    Saved::Complex { re, im } => Value::Complex(Complex { re, im }),
+                };
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::SQRT_2, str::FromStr};
+
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+
+    use super::*;
+    use crate::functions::calc_complex::Complex;
+
+    #[test]
+    fn saved_history_reads_back() {
+        let third = Value::Exact(BigRational::new(1.into(), 3.into()));
+        let mut history = CalcHistory::default();
+        for (expression, value, display) in [
+            ("x = 1/3", third.clone(), "0.3333333333"),
+            ("y = sqrt(2)", Value::Float(SQRT_2), "1.4142135624"),
+            ("1/0", Value::Float(f64::INFINITY), "inf"),
+            ("sqrt(-1)", Value::Complex(Complex::I), "i"),
+        ] {
+            let expr = Expr::from_str(expression).unwrap();
+            history.remember(expression, &expr, &value, display);
+        }
+
+        let saved = serde_json::to_string(&history).unwrap();
+        let read: CalcHistory = serde_json::from_str(&saved).unwrap();
+        assert_eq!(read.entries, history.entries);
+        assert_eq!(read.entries.len(), 4);
+        assert_eq!(read.entries[1].result, "inf");
+        assert_eq!(
+            read.variables,
+            HashMap::from([
+                ("x".to_string(), third.clone()),
+                ("y".to_string(), Value::Float(SQRT_2)),
+                (ANS.to_string(), Value::Complex(Complex::I)),
+            ])
+        );
+
+        // Histories saved before variables could be exact only have numbers
+        let read: CalcHistory = serde_json::from_str(r#"{"variables": {"x": 2.5}}"#).unwrap();
+        assert_eq!(read.variables["x"], Value::Float(2.5));
+    }
+
+    #[test]
+    fn variables_stay_exact() {
+        let mut history = CalcHistory::default();
+        for expression in ["x = 1/3", "2^100"] {
+            let expr = Expr::from_str(expression).unwrap();
+            let value = expr.evaluate(&history.env(AngleUnit::Radians)).unwrap();
+            history.remember(expression, &expr, &value, "");
+        }
+        let history: CalcHistory =
+            serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+
+        let env = history.env(AngleUnit::Radians);
+        let evaluate = |expression: &str| Expr::from_str(expression).unwrap().evaluate(&env);
+        assert_eq!(
+            evaluate("3x"),
+            Some(Value::Exact(BigRational::from_integer(1.into())))
+        );
+        assert_eq!(
+            evaluate("ans + 1"),
+            Some(Value::Exact(BigRational::from_integer(
+                (BigInt::from(1) << 100) + 1
+            )))
+        );
+    }
+
+    #[test]
+    fn complex_variables() {
+        let mut history = CalcHistory::default();
+        for expression in ["z = 1+2i", "sqrt(-4)"] {
+            let expr = Expr::from_str(expression).unwrap();
+            let value = expr.evaluate(&history.env(AngleUnit::Radians)).unwrap();
+            history.remember(expression, &expr, &value, "");
+        }
+        let history: CalcHistory =
+            serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+
+        let env = history.env(AngleUnit::Radians);
+        let evaluate = |expression: &str| Expr::from_str(expression).unwrap().evaluate(&env);
+        assert_eq!(evaluate("ans^2"), Some(Value::Float(-4.)));
+        assert_eq!(
+            evaluate("z * 2"),
+            Some(Value::Complex(Complex { re: 2., im: 4. }))
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Calculations with quantities that have units, like `3 ft + 20 cm in m`, `60 km / 45 min in
+//! km/h` or `2 * 350 g to lb`. The names in these expressions are units (see [`Unit::parse`]),
+//! unless they're variables, or currencies (`$20 + €15 in gbp`), and values are kept in the base
+//! units of their dimension. Temperatures are differences here, so their offsets are ignored.
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    functions::{
+        calc_value::DecimalSeparator,
+        calculator::{BinOp, CalcError, Env, Expr, UnaryOp},
+    },
+    unit_conversion::{
+        currency::{self, Rates},
+        unit::{Dimension, Unit},
+    },
+};
+
+/// The words that come before the unit a calculation's result is asked in
+const TARGET_KEYWORDS: [&str; 3] = [" in ", " to ", " as "];
+
+/// A value in the base units of its dimension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    /// A number without units
+    fn number(value: f64) -> Quantity {
+        Quantity {
+            value,
+            dimension: Dimension::NONE,
+        }
+    }
+}
+
+impl Expr {
+    /// Evaluates an expression whose names can be units, checking that only quantities of the
+    /// same dimension are added, subtracted or compared
+    pub fn evaluate_quantity(&self, env: &Env, rates: &Rates) -> Result<Quantity, CalcError> {
+        let undefined = || CalcError::Domain("The result is undefined".to_string());
+        let without_units =
+            || CalcError::Domain("Only numbers without units can be used here".to_string());
+
+        if !self.has_units(env) {
+            return self
+                .eval(env)
+                .filter(|value| !value.is_nan())
+                .map(Quantity::number)
+                .ok_or_else(undefined);
+        }
+
+        match self {
+            Expr::Var(name) => {
+                let unit = rates
+                    .parse_unit(name)
+                    .ok_or_else(|| CalcError::UnknownVariable {
+                        name: name.clone(),
+                        span: None,
+                    })?;
+                Ok(Quantity {
+                    value: unit.scale,
+                    dimension: unit.dimension,
+                })
+            }
+
+            Expr::Unary { op, rhs } => {
+                let q = rhs.evaluate_quantity(env, rates)?;
+                let value = match op {
+                    UnaryOp::Plus => q.value,
+                    UnaryOp::Minus => -q.value,
+                    UnaryOp::Percent => q.value / 100.,
+                    UnaryOp::Not | UnaryOp::Factorial => return Err(without_units()),
+                };
+                Ok(Quantity { value, ..q })
+            }
+
+            Expr::Binary { op, lhs, rhs } => {
+                let (a, b) = (
+                    lhs.evaluate_quantity(env, rates)?,
+                    rhs.evaluate_quantity(env, rates)?,
+                );
+                let same_dimension = |operation| {
+                    if a.dimension == b.dimension {
+                        Ok(a.dimension)
+                    } else {
+                        Err(CalcError::IncompatibleUnits {
+                            operation,
+                            lhs: a.dimension,
+                            rhs: b.dimension,
+                        })
+                    }
+                };
+                let division_by_zero = || CalcError::Domain("Division by zero".to_string());
+
+                let (value, dimension) = match op {
+                    BinOp::Add => (a.value + b.value, same_dimension("add")?),
+                    BinOp::Sub => (a.value - b.value, same_dimension("subtract")?),
+                    BinOp::Mod if b.value == 0. => return Err(division_by_zero()),
+                    BinOp::Mod => (a.value % b.value, same_dimension("divide")?),
+                    BinOp::Mul => (a.value * b.value, a.dimension.times(b.dimension, 1)),
+                    BinOp::Div if b.value == 0. => return Err(division_by_zero()),
+                    BinOp::Div => (a.value / b.value, a.dimension.times(b.dimension, -1)),
+                    BinOp::Pow if b.dimension != Dimension::NONE => {
+                        return Err(CalcError::Domain("Exponents can't have units".to_string()));
+                    }
+                    BinOp::Pow if a.dimension == Dimension::NONE => {
+                        (a.value.powf(b.value), Dimension::NONE)
+                    }
+                    BinOp::Pow => {
+                        let exponent = Some(b.value)
+                            .filter(|exponent| exponent.fract() == 0.)
+                            .and_then(|exponent| exponent.to_i8())
+                            .ok_or_else(|| {
+                                CalcError::Domain(
+                                    "Units can only be raised to whole powers".to_string(),
+                                )
+                            })?;
+                        (
+                            a.value.powi(exponent.into()),
+                            Dimension::NONE.times(a.dimension, exponent),
+                        )
+                    }
+                    BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+                        return Err(without_units());
+                    }
+                };
+                Ok(Quantity { value, dimension })
+            }
+
+            Expr::Func { name, .. } => Err(CalcError::Domain(format!(
+                "{name} only works on numbers without units"
+            ))),
+
+            Expr::Assign { .. }
+            | Expr::Number(_)
+            | Expr::Decimal(_)
+            | Expr::Integer(..)
+            | Expr::Imaginary => Err(without_units()),
+        }
+    }
+
+    /// Whether any of the names in the expression aren't variables, so they're units
+    fn has_units(&self, env: &Env) -> bool {
+        self.variables()
+            .iter()
+            .any(|name| !env.variables.contains_key(*name))
+    }
+}
+
+/// Calculates a query with units (`3 ft + 20 cm in m`), and returns the result in the unit that
+/// was asked for, or the first unit in the expression it can be shown in (or the base units of its
+/// dimension, or none if it's just a number). Queries without units, or without an operator or a
+/// function call, aren't calculations with units.
+pub fn calculate_quantity(
+    query: &str,
+    decimal_separator: DecimalSeparator,
+    env: &Env,
+    rates: &Rates,
+) -> Option<Result<(f64, Option<Unit>), CalcError>> {
+    let query = currency::normalize(query);
+    let (expression, target) = split_target(&query, rates);
+    let has_operator = expression
+        .trim_start()
+        .chars()
+        .skip(1)
+        .any(|c| "+-*/^(".contains(c));
+    if !has_operator {
+        return None;
+    }
+
+    let expr = Expr::parse_quantity(expression, decimal_separator).ok()?;
+    let units: Vec<Unit> = expr
+        .variables()
+        .into_iter()
+        .filter(|name| !env.variables.contains_key(*name))
+        .map(|name| rates.parse_unit(name))
+        .collect::<Option<_>>()?;
+    if units.is_empty() {
+        return None;
+    }
+
+    let quantity = match expr.evaluate_quantity(env, rates) {
+        Ok(quantity) => quantity,
+        Err(error) => return Some(Err(error)),
+    };
+    let unit = match target {
+        Some(target) if target.dimension != quantity.dimension => {
+            return Some(Err(CalcError::IncompatibleUnits {
+                operation: "convert",
+                lhs: quantity.dimension,
+                rhs: target.dimension,
+            }));
+        }
+        Some(target) => Some(target),
+        None if quantity.dimension == Dimension::NONE => None,
+        None => Some(
+            units
+                .into_iter()
+                .find(|unit| unit.dimension == quantity.dimension)
+                .unwrap_or_else(|| Unit::base(quantity.dimension)),
+        ),
+    };
+    let value = unit
+        .as_ref()
+        .map_or(quantity.value, |unit| quantity.value / unit.scale);
+    Some(Ok((value, unit)))
+}
+
+/// Splits the unit a query asks for its result in (`in km/h`) off the query
+fn split_target<'a>(query: &'a str, rates: &Rates) -> (&'a str, Option<Unit>) {
+    let lowercase = query.to_ascii_lowercase();
+    let mut keywords: Vec<(usize, &str)> = TARGET_KEYWORDS
+        .iter()
+        .flat_map(|keyword| lowercase.match_indices(keyword))
+        .collect();
+    keywords.sort_unstable_by_key(|(i, _)| std::cmp::Reverse(*i));
+    keywords
+        .into_iter()
+        .find_map(|(i, keyword)| {
+            let target = rates.parse_unit(&query[i + keyword.len()..])?;
+            Some((&query[..i], Some(target)))
+        })
+        .unwrap_or((query, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::functions::{calc_value::Value, calculator::AngleUnit};
+
+    fn calculate(query: &str) -> Option<Result<(f64, Option<String>), String>> {
+        let variables = HashMap::from([("x".to_string(), Value::Float(2.))]);
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &variables,
+        };
+        calculate_quantity(query, DecimalSeparator::Point, &env, &Rates::default()).map(|result| {
+            result
+                .map(|(value, unit)| (value, unit.map(|unit| unit.name)))
+                .map_err(|error| error.to_string())
+        })
+    }
+
+    #[test]
+    fn quantities() {
+        let cases = [
+            ("3 ft + 20 cm in m", 1.1144, Some("m")),
+            ("60 km / 45 min in km/h", 80., Some("km/h")),
+            ("2 * 350 g to lb", 1.543_235_835_294_143_7, Some("lb")),
+            ("3 ft + 20 cm", 3.656_167_979_002_624_6, Some("ft")),
+            ("1 km / 4 min", 4.166_666_666_666_667, Some("m/s")),
+            (
+                "x * 1.5 kg + 1 lb as oz",
+                121.821_885_848_741_23,
+                Some("oz"),
+            ),
+            ("10 m * 20 m", 200., Some("m²")),
+            ("1 kg * 1 m / 1 s^2", 1., Some("N")),
+            ("3 m * 2 s", 6., Some("m*s")),
+            ("6 m / 2 m", 3., None),
+            ("(2 m)^3 in l", 8000., Some("l")),
+        ];
+        for (query, expected, unit) in cases {
+            let (value, name) = calculate(query)
+                .unwrap_or_else(|| panic!("{query} isn't a unit calculation"))
+                .unwrap_or_else(|error| panic!("{query}: {error}"));
+            assert!(
+                (value - expected).abs() <= 1e-9 * expected.abs(),
+                "{query}: expected {expected}, got {value}"
+            );
+            assert_eq!(name.as_deref(), unit, "{query}");
+        }
+    }
+
+    #[test]
+    fn incompatible_units() {
+        let errors = [
+            ("3 m + 2 s", "Can't add length and time"),
+            ("1 kg - 1 l", "Can't subtract mass and volume"),
+            ("60 km / 1 h to m", "Can't convert speed to length"),
+            ("1 m * 1 s to J", "Can't convert length time to energy"),
+            ("2 m ^ 0.5", "Units can only be raised to whole powers"),
+            ("sqrt(4 m)", "sqrt only works on numbers without units"),
+        ];
+        for (query, message) in errors {
+            assert_eq!(calculate(query), Some(Err(message.to_string())), "{query}");
+        }
+
+        for query in ["5 km to mi", "2 + 3", "x + 1", "hello + world", "3 m"] {
+            assert_eq!(calculate(query), None, "{query}");
+        }
+    }
+}
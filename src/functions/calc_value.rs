@@ -0,0 +1,256 @@
+//! The results of calculations, which are exact (big rationals) when the expression only used
+//! exact operations, and how they're written
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::functions::calc_complex::Complex;
+
+/// The most bits (of the numerator and denominator together) an exact result can have. Bigger
+/// results are calculated with floats instead, so that something like `9^9^9` doesn't hang
+pub const MAX_EXACT_BITS: u64 = 2048;
+
+/// The biggest exponent a decimal literal (`1e300`) can have to be kept exact
+const MAX_DECIMAL_EXPONENT: u64 = 600;
+
+/// The result of a calculation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Exact(BigRational),
+    Float(f64),
+    Complex(Complex),
+}
+
+/// Whether numbers are written with a decimal point (`1,234.5`) or a decimal comma (`1.234,5`).
+/// The other one is the separator between groups of digits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DecimalSeparator {
+    #[default]
+    #[serde(rename = ".")]
+    Point,
+    #[serde(rename = ",")]
+    Comma,
+}
+
+impl DecimalSeparator {
+    /// The character between the whole part and the decimals
+    pub fn decimal(self) -> char {
+        match self {
+            DecimalSeparator::Point => '.',
+            DecimalSeparator::Comma => ',',
+        }
+    }
+
+    /// The character between groups of three digits
+    pub fn grouping(self) -> char {
+        match self {
+            DecimalSeparator::Point => ',',
+            DecimalSeparator::Comma => '.',
+        }
+    }
+}
+
+/// How results are written
+///
+/// - `precision` is the most digits shown after the decimal point
+/// - `digit_grouping` is whether the digits before the decimal point are grouped in threes
+/// - `decimal_separator` is which of `.` and `,` is the decimal separator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub precision: usize,
+    pub digit_grouping: bool,
+    pub decimal_separator: DecimalSeparator,
+}
+
+impl Format {
+    /// The same format without digit grouping, for copying the result somewhere else
+    pub fn ungrouped(self) -> Self {
+        Self {
+            digit_grouping: false,
+            ..self
+        }
+    }
+}
+
+impl Value {
+    /// The value as a float, for variables and `ans`, which is NaN for complex numbers
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Value::Float(x) => *x,
+            Value::Complex(_) => f64::NAN,
+        }
+    }
+
+    /// The value as an integer, if it's an exact one that fits in 128 bits
+    pub fn to_i128(&self) -> Option<i128> {
+        match self {
+            Value::Exact(r) if r.is_integer() => r.to_integer().to_i128(),
+            _ => None,
+        }
+    }
+
+    /// Writes the value with at most `format.precision` decimals
+    pub fn format(&self, format: &Format) -> String {
+        match self {
+            Value::Exact(r) => format_exact(r, format),
+            Value::Float(x) => format_float(*x, format),
+            Value::Complex(z) => format_complex(*z, format),
+        }
+    }
+
+    /// A complex value in polar form (`5 e^(0.927295218i)`), which can be calculated with again
+    pub fn polar(&self, format: &Format) -> Option<String> {
+        match self {
+            Value::Complex(z) => Some(format!(
+                "{} e^({}i)",
+                format_float(z.abs(), format),
+                format_float(z.arg(), format)
+            )),
+            _ => None,
+        }
+    }
+
+    /// The value as a fraction (`1/3`), if it's exact but its decimals had to be cut off
+    pub fn fraction(&self, format: &Format) -> Option<String> {
+        match self {
+            Value::Exact(r) if !(r * pow10(format.precision)).is_integer() => {
+                Some(format!("{}/{}", r.numer(), r.denom()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parses a decimal number (`1.5`, `.5`, `2e-3`, `-0.25`) exactly, or returns [`None`] if it isn't
+/// one or its exponent is too big to keep it exact
+pub fn parse_decimal(s: &str) -> Option<BigRational> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (whole, decimals) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{whole}{decimals}");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = digits.parse::<BigInt>().ok()?;
+    let exponent = exponent.checked_sub(i64::try_from(decimals.len()).ok()?)?;
+    if exponent.unsigned_abs() > MAX_DECIMAL_EXPONENT {
+        return None;
+    }
+    let scale = pow10(usize::try_from(exponent.unsigned_abs()).ok()?);
+    let value = if exponent >= 0 {
+        BigRational::from_integer(digits) * scale
+    } else {
+        BigRational::from_integer(digits) / scale
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// 10 to the power of `exponent`, as a rational
+fn pow10(exponent: usize) -> BigRational {
+    BigRational::from_integer(num_traits::pow(BigInt::from(10), exponent))
+}
+
+/// Writes an exact value, rounding it (half away from zero) to the precision
+fn format_exact(r: &BigRational, format: &Format) -> String {
+    if r.is_integer() {
+        return group_digits(&r.to_integer().to_string(), format);
+    }
+
+    let rounded = (r * pow10(format.precision)).round().to_integer();
+    if rounded.is_zero() {
+        // Too small for the precision, so it's written in scientific notation instead of as 0
+        return scientific(r.to_f64().unwrap_or(0.), format);
+    }
+
+    let digits = format!(
+        "{:0>width$}",
+        rounded.abs().to_string(),
+        width = format.precision + 1
+    );
+    let (whole, decimals) = digits.split_at(digits.len() - format.precision);
+    let sign = if r.is_negative() { "-" } else { "" };
+    join_decimals(&format!("{sign}{whole}"), decimals, format)
+}
+
+/// Writes a float rounded to the precision, in scientific notation if it's too big or too small
+/// for that
+fn format_float(x: f64, format: &Format) -> String {
+    let tiny =
+        x != 0. && x.abs() < 10f64.powi(-i32::try_from(format.precision).unwrap_or(i32::MAX));
+    if !x.is_finite() || tiny || x.abs() >= 1e21 {
+        return if x.is_finite() {
+            scientific(x, format)
+        } else {
+            x.to_string()
+        };
+    }
+
+    let written = format!("{x:.precision$}", precision = format.precision);
+    let (whole, decimals) = written.split_once('.').unwrap_or((&written, ""));
+    let whole = if whole == "-0" && decimals.trim_end_matches('0').is_empty() {
+        "0"
+    } else {
+        whole
+    };
+    join_decimals(whole, decimals, format)
+}
+
+/// Writes a complex number in rectangular form (`3 + 4i`)
+fn format_complex(z: Complex, format: &Format) -> String {
+    let im = match format_float(z.im.abs(), format).as_str() {
+        "1" => "i".to_string(),
+        im => format!("{im}i"),
+    };
+    let sign = if z.im < 0. { "-" } else { "+" };
+    if z.re == 0. {
+        format!("{}{im}", if z.im < 0. { "-" } else { "" })
+    } else {
+        format!("{} {sign} {im}", format_float(z.re, format))
+    }
+}
+
+/// Writes a float in scientific notation (`1.5e-12`), with at most the precision's digits after
+/// the decimal point
+fn scientific(x: f64, format: &Format) -> String {
+    let written = format!("{x:.precision$e}", precision = format.precision);
+    let (mantissa, exponent) = written.split_once('e').unwrap_or((&written, "0"));
+    let (whole, decimals) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    format!("{}e{exponent}", join_decimals(whole, decimals, format))
+}
+
+/// Joins the whole part of a number (grouping its digits if asked to) with its decimals, without
+/// their trailing zeros
+fn join_decimals(whole: &str, decimals: &str, format: &Format) -> String {
+    let whole = group_digits(whole, format);
+    match decimals.trim_end_matches('0') {
+        "" => whole,
+        decimals => format!("{whole}{}{decimals}", format.decimal_separator.decimal()),
+    }
+}
+
+/// Separates the digits of a whole number in groups of three (`1,234,567` or `1.234.567`), if the
+/// format asks for it
+fn group_digits(whole: &str, format: &Format) -> String {
+    if !format.digit_grouping {
+        return whole.to_string();
+    }
+    let (sign, digits) = whole.split_at(usize::from(whole.starts_with('-')));
+    let mut grouped = String::with_capacity(whole.len() + digits.len() / 3);
+    grouped.push_str(sign);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(format.decimal_separator.grouping());
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
@@ -1,10 +1,26 @@
 /// A small expression parser/evaluator supporting:
-/// - + - * / ^ with precedence
+/// - + - * / % ^ with precedence (% is the remainder when a number follows it)
 /// - parentheses
-/// - unary +/-
-/// - ln(x)
-/// - log(x) (base 10)
-/// - log(base, x)
+/// - unary +/- and postfix ! (factorial)
+/// - percentages: 15% of 80, 20% off 80, and 200 + 10% (which adds 10% of 200)
+/// - grouping separators (1,250) and magnitude suffixes (2.5k, 3M, 4G, 3 million, 2 billion)
+/// - a decimal comma instead of a decimal point, see [`DecimalSeparator`]
+/// - exact arithmetic with big integers and fractions, as long as only exact operations are used
+///   (0.1 + 0.2 is exactly 0.3), and floats otherwise (see [`Value`])
+/// - complex numbers, written with i or j (3 + 4i), which are used when an expression has no real
+///   result (sqrt(-4) is 2i)
+/// - integers in hex (0xff), binary (0b101) and octal (0o17)
+/// - the bitwise operators & | xor ~ << >>, which bind more loosely than arithmetic (like in C)
+/// - implicit multiplication before constants, variables, functions and parentheses (2pi, 3(4+5))
+/// - the constants pi, e and tau
+/// - variables, assigned with `name = expression`, and `ans` (the previous result)
+/// - ln(x), log(x) (base 10), log(base, x) and exp(x)
+/// - sin, cos, tan, asin, acos, atan, sinh, cosh, tanh (in degrees or radians, see [`AngleUnit`])
+/// - sqrt, cbrt, abs, floor, ceil, round(x), round(x, digits), min(...) and max(...)
+/// - re, im, arg (the angle) and conj (the conjugate) of complex numbers
+/// - sum, avg / mean, median, mode, stdev and var (of samples), gcd and lcm of any number of
+///   arguments, and ncr(n, r) / npr(n, r) (combinations / permutations)
+/// - errors that say why an expression has no result, and where, see [`CalcError`]
 ///
 /// Examples:
 ///   "2 + 3*4"        => 14
@@ -13,10 +29,370 @@
 ///   "ln(2.7182818)"  => ~1
 ///   "log(100)"       => 2
 ///   "log(2, 8)"      => 3
+///   "2pi"            => ~6.283
+///   "3(4+5)"         => 27
+///   "10 % 4"         => 2
+///   "5!"             => 120
+///   "x = 3.5"        => 3.5, and x is 3.5 from then on
+///   "0xff & 0b1010"  => 10
+///   "1 << 4 | 1"     => 17
+///   "1/3"            => 1/3, shown as 0.3333333333
+///   "2^100"          => 1267650600228229401496703205376
+///   "(1+2i)*(3-i)"   => 5 + 5i
+///   "e^(i*pi)"       => -1
+///   "15% of 80"      => 12
+///   "200 + 10%"      => 220
+///   "1,250 * 3"      => 3750
+///   "2.5k * 4"       => 10000
+///   "median(3, 1, 2)" => 2
+use std::{
+    collections::HashMap,
+    f64::consts::{E, PI, TAU},
+    fmt,
+    ops::Range,
+    str::FromStr,
+};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    functions::{
+        calc_complex::Complex,
+        calc_value::{DecimalSeparator, MAX_EXACT_BITS, Value, parse_decimal},
+    },
+    unit_conversion::unit::Dimension,
+};
+
+/// Whether the trigonometric functions take (and the inverse ones return) degrees or radians
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AngleUnit {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl AngleUnit {
+    /// Converts an angle in this unit to radians
+    fn to_radians(self, angle: f64) -> f64 {
+        match self {
+            AngleUnit::Radians => angle,
+            AngleUnit::Degrees => angle.to_radians(),
+        }
+    }
+
+    /// Converts an angle in radians to this unit
+    fn radians_to(self, angle: f64) -> f64 {
+        match self {
+            AngleUnit::Radians => angle,
+            AngleUnit::Degrees => angle.to_degrees(),
+        }
+    }
+
+    /// How many radians one of this unit is, to convert complex angles
+    fn radians(self) -> f64 {
+        self.to_radians(1.)
+    }
+}
+
+/// The variable that holds the result of the previous calculation
+pub const ANS: &str = "ans";
+
+/// What an expression is evaluated in: the angle unit, and the values of the variables (including
+/// [`ANS`]), which stay exact when they were calculated exactly
+#[derive(Debug, Clone, Copy)]
+pub struct Env<'a> {
+    pub angle_unit: AngleUnit,
+    pub variables: &'a HashMap<String, Value>,
+}
+
+/// Why an expression can't be calculated. Spans are byte ranges of the expression, which is ASCII
+/// up to any error
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcError {
+    /// The expression ends before it's complete (`2 +`, `(1 + 2`)
+    Incomplete,
+    /// A character or token that can't be where it is (`1 $ 2`, `2 3`)
+    Unexpected { found: String, span: Range<usize> },
+    /// A number that can't be read (`0xfg`)
+    InvalidNumber { number: String, span: Range<usize> },
+    /// Assigning to something that isn't a variable (`pi = 3`)
+    InvalidAssignment(String),
+    /// A call to a function that doesn't exist, and the builtin function it's probably a typo of
+    UnknownFunction {
+        name: String,
+        span: Range<usize>,
+        suggestion: Option<&'static str>,
+    },
+    /// A variable that isn't set
+    UnknownVariable {
+        name: String,
+        span: Option<Range<usize>>,
+    },
+    /// A function called with the wrong number of arguments (`sqrt(1, 2)`)
+    WrongArity {
+        name: String,
+        expected: String,
+        found: usize,
+        span: Option<Range<usize>>,
+    },
+    /// An operation that isn't defined for its operands (`0/0`, `(-1)!`)
+    Domain(String),
+    /// Adding, subtracting or converting quantities of different dimensions (`3 m + 2 s`)
+    IncompatibleUnits {
+        operation: &'static str,
+        lhs: Dimension,
+        rhs: Dimension,
+    },
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Incomplete => write!(f, "Incomplete expression"),
+            CalcError::Unexpected { found, span } => {
+                write!(f, "Unexpected {found} at column {}", span.start + 1)
+            }
+            CalcError::InvalidNumber { number, span } => {
+                write!(f, "Invalid number {number} at column {}", span.start + 1)
+            }
+            CalcError::InvalidAssignment(name) => write!(f, "Can't assign to {name}"),
+            CalcError::UnknownFunction { name, .. } => write!(f, "Unknown function {name}"),
+            CalcError::UnknownVariable { name, .. } => write!(f, "Unknown variable {name}"),
+            CalcError::WrongArity {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(f, "{name} takes {expected}, not {found}"),
+            CalcError::Domain(message) => write!(f, "{message}"),
+            CalcError::IncompatibleUnits {
+                operation: "convert",
+                lhs,
+                rhs,
+            } => write!(f, "Can't convert {lhs} to {rhs}"),
+            CalcError::IncompatibleUnits {
+                operation,
+                lhs,
+                rhs,
+            } => write!(f, "Can't {operation} {lhs} and {rhs}"),
+        }
+    }
+}
+
+/// A name in an expression (of a variable or function), and where it is
+#[derive(Debug, Clone)]
+struct Name {
+    name: String,
+    span: Range<usize>,
+    /// Whether it's followed by `(`, like a function call
+    called: bool,
+}
+
+/// Parses and evaluates an expression, or says why it can't be calculated. A NaN result counts as
+/// an error, like `0/0`
+pub fn calculate(
+    s: &str,
+    decimal_separator: DecimalSeparator,
+    env: &Env,
+) -> Result<(Expr, Value), CalcError> {
+    let (expr, names) = Expr::parse_from(s, decimal_separator, 0)
+        .map_err(|error| unknown_call(s, env).unwrap_or(error))?;
+    match expr.evaluate(env) {
+        Some(value) if is_finite(&value) => Ok((expr, value)),
+        _ => Err(expr.diagnose(env, &names)),
+    }
+}
+
+/// Whether a value is a number, rather than NaN or infinite (like the float result of `1/0`)
+fn is_finite(value: &Value) -> bool {
+    match value {
+        Value::Exact(_) => true,
+        Value::Float(x) => x.is_finite(),
+        Value::Complex(z) => z.re.is_finite() && z.im.is_finite(),
+    }
+}
+
+/// Whether a query is clearly meant as a calculation, so that why it doesn't calculate is worth
+/// showing: it's only numbers, operators and names the calculator knows (or calls like
+/// functions), with a number or a call, and an operator or parenthesis
+pub fn looks_like_math(s: &str, env: &Env) -> bool {
+    let (names, has_number) = scan(s);
+    let known = |n: &Name| {
+        n.called
+            || s[n.span.end..].trim_start().starts_with('=')
+            || constant(&n.name).is_some()
+            || is_imaginary_unit(&n.name)
+            || is_function(&n.name)
+            || KEYWORDS.contains(&n.name.as_str())
+            || MAGNITUDE_WORDS.iter().any(|(word, _)| *word == n.name)
+            || env.variables.contains_key(&n.name)
+    };
+    let has_operator = s
+        .chars()
+        .any(|c| !c.is_alphanumeric() && !c.is_whitespace() && c != '_' && c != '.');
+    names.iter().all(known) && (has_number || names.iter().any(|n| n.called)) && has_operator
+}
+
+/// The names in a query, and whether it has a number. A name is a run of letters, digits and `_`
+/// that starts with a letter or `_`, and letters right after digits are part of the number, like
+/// in `0xff`, `2e5` or `3k`
+fn scan(s: &str) -> (Vec<Name>, bool) {
+    let mut names = Vec::new();
+    let mut has_number = false;
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        if c.is_ascii_digit() || c == '.' {
+            has_number = true;
+            i += s[i..]
+                .find(|c: char| !is_word(c) && c != '.')
+                .unwrap_or(s.len() - i);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = i + s[i..].find(|c: char| !is_word(c)).unwrap_or(s.len() - i);
+            names.push(Name {
+                name: s[i..end].to_string(),
+                span: i..end,
+                called: s[end..].trim_start().starts_with('('),
+            });
+            i = end;
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    (names, has_number)
+}
+
+/// The first call to a function that doesn't exist in a query, which is likely why it doesn't
+/// parse (`meidan(1, 2)` is `meidan * (1, 2)` otherwise)
+fn unknown_call(s: &str, env: &Env) -> Option<CalcError> {
+    let (names, _) = scan(s);
+    let n = names.into_iter().find(|n| {
+        n.called
+            && !is_function(&n.name)
+            && constant(&n.name).is_none()
+            && !is_imaginary_unit(&n.name)
+            && !env.variables.contains_key(&n.name)
+    })?;
+    Some(CalcError::UnknownFunction {
+        suggestion: suggest_function(&n.name),
+        name: n.name,
+        span: n.span,
+    })
+}
+
+/// The base that integers are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    Dec,
+    Hex,
+    Bin,
+    Oct,
+}
+
+impl Base {
+    /// All the bases, in the order they're shown in
+    pub const ALL: [Base; 4] = [Base::Dec, Base::Hex, Base::Bin, Base::Oct];
+
+    /// The name of the base, as shown in the results
+    pub fn name(self) -> &'static str {
+        match self {
+            Base::Dec => "Decimal",
+            Base::Hex => "Hexadecimal",
+            Base::Bin => "Binary",
+            Base::Oct => "Octal",
+        }
+    }
+
+    /// Writes an integer in this base, with its prefix
+    ///
+    /// Negative numbers are written in two's complement, with 64 bits if they fit in them and 128
+    /// bits otherwise.
+    pub fn format(self, n: i128) -> String {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let bits = if n >= 0 {
+            n as u128
+        } else if let Ok(n) = i64::try_from(n) {
+            u128::from(n as u64)
+        } else {
+            n as u128
+        };
+        match self {
+            Base::Dec => n.to_string(),
+            Base::Hex => format!("0x{bits:X}"),
+            Base::Bin => format!("0b{bits:b}"),
+            Base::Oct => format!("0o{bits:o}"),
+        }
+    }
+
+    /// The base for a suffix like `hex` in `255 in hex`
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "dec" | "decimal" => Some(Base::Dec),
+            "hex" | "hexadecimal" => Some(Base::Hex),
+            "bin" | "binary" => Some(Base::Bin),
+            "oct" | "octal" => Some(Base::Oct),
+            _ => None,
+        }
+    }
+
+    /// The base for the prefix of an integer literal, like the `x` of `0xff`
+    fn from_prefix(prefix: char) -> Option<Self> {
+        match prefix.to_ascii_lowercase() {
+            'x' => Some(Base::Hex),
+            'b' => Some(Base::Bin),
+            'o' => Some(Base::Oct),
+            _ => None,
+        }
+    }
+
+    fn radix(self) -> u32 {
+        match self {
+            Base::Dec => 10,
+            Base::Hex => 16,
+            Base::Bin => 2,
+            Base::Oct => 8,
+        }
+    }
+}
+
+/// Splits a suffix that asks for the result in a base (`in hex`, `to bin`, `as oct`...) off the
+/// query
+pub fn split_base_suffix(query: &str) -> (&str, Option<Base>) {
+    let trimmed = query.trim_end();
+    if let Some((rest, suffix)) = trimmed.rsplit_once(char::is_whitespace)
+        && let Some(base) = Base::from_suffix(&suffix.to_lowercase())
+        && let Some((expression, keyword)) = rest.trim_end().rsplit_once(char::is_whitespace)
+        && matches!(keyword.to_lowercase().as_str(), "in" | "to" | "as")
+    {
+        return (expression, Some(base));
+    }
+    (query, None)
+}
+
+/// The largest number whose factorial fits in an f64
+const MAX_FACTORIAL: f64 = 170.;
+
+/// Results of trigonometric functions this close to a whole number are rounded to it, so that
+/// sin(pi) is 0 rather than 1.2246467991473532e-16. The tolerance shrinks along with the angle
+/// (see [`snap`]), so that sin(1e-13) is still 1e-13.
+const TRIG_EPSILON: f64 = 1e-12;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Number(f64),
+    Decimal(BigRational),
+    Integer(i128, Base),
+    Imaginary,
+    Var(String),
+    Assign {
+        name: String,
+        value: Box<Expr>,
+    },
     Unary {
         op: UnaryOp,
         rhs: Box<Expr>,
@@ -36,6 +412,9 @@ pub enum Expr {
 pub enum UnaryOp {
     Plus,
     Minus,
+    Not,
+    Factorial,
+    Percent,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -44,65 +423,840 @@ pub enum BinOp {
     Sub,
     Mul,
     Div,
+    Mod,
     Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 impl Expr {
-    pub fn eval(&self) -> Option<f64> {
-        use BinOp::{Add, Div, Mul, Pow, Sub};
-        use UnaryOp::{Minus, Plus};
+    /// Evaluates the expression in the environment
+    ///
+    /// An assignment evaluates to the assigned value, without changing the environment.
+    pub fn eval(&self, env: &Env) -> Option<f64> {
         match self {
             Expr::Number(x) => Some(*x),
 
+            Expr::Decimal(r) => r.to_f64(),
+
+            #[allow(clippy::cast_precision_loss)]
+            Expr::Integer(n, _) => Some(*n as f64),
+
+            Expr::Imaginary => None,
+
+            Expr::Var(name) => env.variables.get(name).map(Value::to_f64),
+
+            Expr::Assign { value, .. } => value.eval(env),
+
+            Expr::Unary { op, rhs } => real_unary(*op, rhs.eval(env)?),
+
+            Expr::Binary { op, lhs, rhs } => real_binary(*op, lhs.eval(env)?, rhs.eval(env)?),
+
+            Expr::Func { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(env))
+                    .collect::<Option<Vec<f64>>>()?;
+                eval_func(name, &args, env.angle_unit)
+            }
+        }
+    }
+
+    /// Evaluates the expression exactly if it only uses exact operations, with floats otherwise,
+    /// and with complex numbers if it uses i or has no real result
+    pub fn evaluate(&self, env: &Env) -> Option<Value> {
+        if let Some(exact) = self.eval_exact(env) {
+            return Some(Value::Exact(exact));
+        }
+        match self.eval(env) {
+            Some(x) if !x.is_nan() => Some(Value::Float(x)),
+            real => match self.eval_complex(env).map(|z| z.snap(TRIG_EPSILON)) {
+                Some(z) if z.is_real() => Some(Value::Float(z.re)),
+                Some(z) if !z.re.is_nan() && !z.im.is_nan() => Some(Value::Complex(z)),
+                _ => real.map(Value::Float),
+            },
+        }
+    }
+
+    /// Evaluates the expression with complex numbers. Operations that only exist for real
+    /// numbers (like `%` and `!`) are only done when their operands are real
+    fn eval_complex(&self, env: &Env) -> Option<Complex> {
+        match self {
+            Expr::Imaginary => Some(Complex::I),
+
+            Expr::Number(_) | Expr::Decimal(_) | Expr::Integer(..) => {
+                self.eval(env).map(Complex::real)
+            }
+
+            Expr::Var(name) => match env.variables.get(name)? {
+                Value::Complex(z) => Some(*z),
+                value => Some(Complex::real(value.to_f64())),
+            },
+
+            Expr::Assign { value, .. } => value.eval_complex(env),
+
             Expr::Unary { op, rhs } => {
-                let v = rhs.eval()?;
-                Some(match op {
-                    Plus => v,
-                    Minus => -v,
-                })
+                let z = rhs.eval_complex(env)?;
+                if z.is_real() {
+                    return real_unary(*op, z.re).map(Complex::real);
+                }
+                match op {
+                    UnaryOp::Plus => Some(z),
+                    UnaryOp::Minus => Some(-z),
+                    UnaryOp::Percent => Some(z.scale(0.01)),
+                    UnaryOp::Not | UnaryOp::Factorial => None,
+                }
             }
 
             Expr::Binary { op, lhs, rhs } => {
-                let a = lhs.eval()?;
-                let b = rhs.eval()?;
+                let a = lhs.eval_complex(env)?;
+                let b = rhs.eval_complex(env)?;
+                if a.is_real()
+                    && b.is_real()
+                    && let Some(x) = real_binary(*op, a.re, b.re).filter(|x| !x.is_nan())
+                {
+                    return Some(Complex::real(x));
+                }
                 match op {
-                    Add => Some(a + b),
-                    Sub => Some(a - b),
-                    Mul => Some(a * b),
-                    Div => Some(a / b),
-                    Pow => Some(a.powf(b)),
+                    BinOp::Add => Some(a + b),
+                    BinOp::Sub => Some(a - b),
+                    BinOp::Mul => Some(a * b),
+                    BinOp::Div => Some(a / b),
+                    BinOp::Pow => Some(a.pow(b)),
+                    _ => None,
                 }
             }
 
             Expr::Func { name, args } => {
-                let name = name.as_str();
-                match name {
-                    "ln" => {
-                        if args.len() != 1 {
-                            return None;
-                        }
-                        Some(args[0].eval()?.ln())
-                    }
-                    "log" => match args.len() {
-                        1 => Some(args[0].eval()?.log10()),
-                        2 => {
-                            let base = args[0].eval()?;
-                            let x = args[1].eval()?;
-                            Some(x.log(base))
-                        }
-                        _ => None,
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval_complex(env))
+                    .collect::<Option<Vec<_>>>()?;
+                if args.iter().all(|z| z.is_real())
+                    && let Some(x) = eval_func(
+                        name,
+                        &args.iter().map(|z| z.re).collect::<Vec<_>>(),
+                        env.angle_unit,
+                    )
+                    .filter(|x| !x.is_nan())
+                {
+                    return Some(Complex::real(x));
+                }
+                eval_func_complex(name, &args, env.angle_unit)
+            }
+        }
+    }
+
+    /// Evaluates the expression exactly, or returns [`None`] if it uses something whose result
+    /// isn't always rational (constants, roots, logarithms, trigonometry...) or the result gets too
+    /// big to be kept exact (see [`MAX_EXACT_BITS`])
+    fn eval_exact(&self, env: &Env) -> Option<BigRational> {
+        let result = match self {
+            Expr::Number(_) | Expr::Imaginary => None,
+
+            Expr::Decimal(r) => Some(r.clone()),
+
+            Expr::Integer(n, _) => Some(BigRational::from_integer(BigInt::from(*n))),
+
+            // Float variables are read back as the decimal they're shown as
+            Expr::Var(name) => match env.variables.get(name)? {
+                Value::Exact(r) => Some(r.clone()),
+                Value::Float(x) => parse_decimal(&x.to_string()),
+                Value::Complex(_) => None,
+            },
+
+            Expr::Assign { value, .. } => value.eval_exact(env),
+
+            Expr::Unary { op, rhs } => {
+                let v = rhs.eval_exact(env)?;
+                match op {
+                    UnaryOp::Plus => Some(v),
+                    UnaryOp::Minus => Some(-v),
+                    UnaryOp::Not => Some(BigRational::from_integer(!whole(&v)?)),
+                    UnaryOp::Factorial => exact_factorial(&v),
+                    UnaryOp::Percent => Some(v / BigRational::from_integer(BigInt::from(100))),
+                }
+            }
+
+            Expr::Binary { op, lhs, rhs } => {
+                exact_binary(*op, lhs.eval_exact(env)?, rhs.eval_exact(env)?)
+            }
+
+            Expr::Func { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval_exact(env))
+                    .collect::<Option<Vec<_>>>()?;
+                eval_func_exact(name, &args)
+            }
+        }?;
+        (result.numer().bits() + result.denom().bits() <= MAX_EXACT_BITS).then_some(result)
+    }
+
+    /// The names of the variables the expression uses (including [`ANS`]), in the order they first
+    /// appear in, which are the unknowns of an equation when they aren't set
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) | Expr::Decimal(_) | Expr::Integer(..) | Expr::Imaginary => {}
+            Expr::Var(name) => {
+                if !variables.contains(&name.as_str()) {
+                    variables.push(name);
+                }
+            }
+            Expr::Assign { value, .. } => value.collect_variables(variables),
+            Expr::Unary { rhs, .. } => rhs.collect_variables(variables),
+            Expr::Binary { lhs, rhs, .. } => {
+                lhs.collect_variables(variables);
+                rhs.collect_variables(variables);
+            }
+            Expr::Func { args, .. } => {
+                for arg in args {
+                    arg.collect_variables(variables);
+                }
+            }
+        }
+    }
+
+    /// The base the result should be shown in when the expression is a "programmer" one: the base
+    /// of its first hex / binary / octal literal, or decimal if it only uses bitwise operators
+    pub fn programmer_base(&self) -> Option<Base> {
+        self.literal_base()
+            .or_else(|| self.is_bitwise().then_some(Base::Dec))
+    }
+
+    /// The base of the first integer literal that isn't decimal
+    fn literal_base(&self) -> Option<Base> {
+        match self {
+            Expr::Integer(_, Base::Dec)
+            | Expr::Number(_)
+            | Expr::Decimal(_)
+            | Expr::Imaginary
+            | Expr::Var(_) => None,
+            Expr::Integer(_, base) => Some(*base),
+            Expr::Assign { value, .. } => value.literal_base(),
+            Expr::Unary { rhs, .. } => rhs.literal_base(),
+            Expr::Binary { lhs, rhs, .. } => lhs.literal_base().or_else(|| rhs.literal_base()),
+            Expr::Func { args, .. } => args.iter().find_map(Expr::literal_base),
+        }
+    }
+
+    /// Whether the expression is a percentage (`10%`)
+    fn is_percentage(&self) -> bool {
+        matches!(
+            self,
+            Expr::Unary {
+                op: UnaryOp::Percent,
+                ..
+            }
+        )
+    }
+
+    /// Whether the expression is a number, possibly with a sign
+    fn is_number(&self) -> bool {
+        match self {
+            Expr::Integer(..) | Expr::Number(_) | Expr::Decimal(_) => true,
+            Expr::Unary {
+                op: UnaryOp::Plus | UnaryOp::Minus,
+                rhs,
+            } => rhs.is_number(),
+            _ => false,
+        }
+    }
+
+    /// Whether the expression uses a bitwise operator
+    fn is_bitwise(&self) -> bool {
+        match self {
+            Expr::Integer(..)
+            | Expr::Number(_)
+            | Expr::Decimal(_)
+            | Expr::Imaginary
+            | Expr::Var(_) => false,
+            Expr::Assign { value, .. } => value.is_bitwise(),
+            Expr::Unary { op, rhs } => *op == UnaryOp::Not || rhs.is_bitwise(),
+            Expr::Binary { op, lhs, rhs } => {
+                matches!(
+                    op,
+                    BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr
+                ) || lhs.is_bitwise()
+                    || rhs.is_bitwise()
+            }
+            Expr::Func { args, .. } => args.iter().any(Expr::is_bitwise),
+        }
+    }
+
+    /// Parses an expression, or an assignment to a variable (`name = expression`), with numbers
+    /// written with the given decimal separator
+    pub fn parse(s: &str, decimal_separator: DecimalSeparator) -> Result<Expr, CalcError> {
+        Expr::parse_from(s, decimal_separator, 0).map(|(expr, _)| expr)
+    }
+
+    /// Parses the expression that starts at `start`, and returns where the names in it are
+    fn parse_from(
+        s: &str,
+        decimal_separator: DecimalSeparator,
+        start: usize,
+    ) -> Result<(Expr, Vec<Name>), CalcError> {
+        if let Some((name, _)) = s[start..].split_once('=') {
+            let value_start = start + name.len() + 1;
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(CalcError::Incomplete);
+            } else if !is_variable_name(name) {
+                return Err(CalcError::InvalidAssignment(name.to_string()));
+            }
+            let (value, names) = Expr::parse_from(s, decimal_separator, value_start)?;
+            let assign = Expr::Assign {
+                name: name.to_string(),
+                value: Box::new(value),
+            };
+            return Ok((assign, names));
+        }
+
+        let mut p = Parser::new(s, decimal_separator, start)?;
+        let expr = p.parse_expr()?;
+        p.expect(&Token::End)?;
+        Ok((expr, p.names))
+    }
+
+    /// Parses an expression whose names can be units (see [`Expr::evaluate_quantity`]), where a
+    /// divisor includes the units right after it, so that `60 km / 45 min` is a speed
+    pub fn parse_quantity(s: &str, decimal_separator: DecimalSeparator) -> Result<Expr, CalcError> {
+        let mut p = Parser::new(s, decimal_separator, 0)?;
+        p.quantities = true;
+        let expr = p.parse_expr()?;
+        p.expect(&Token::End)?;
+        Ok(expr)
+    }
+
+    /// Why the expression can't be evaluated, when [`Expr::evaluate`] has no result (or NaN):
+    /// the first part of it that has none, and why
+    fn diagnose(&self, env: &Env, names: &[Name]) -> CalcError {
+        let has_value = |expr: &Expr| expr.evaluate(env).as_ref().is_some_and(is_finite);
+        let is_infinite = || matches!(self.evaluate(env), Some(Value::Float(x)) if x.is_infinite());
+        let is_zero = |expr: &Expr| match expr.evaluate(env) {
+            Some(Value::Exact(r)) => r.is_zero(),
+            Some(Value::Float(x)) => x == 0.,
+            _ => false,
+        };
+        let domain = |message: &str| CalcError::Domain(message.to_string());
+
+        match self {
+            Expr::Var(name) if !env.variables.contains_key(name) => {
+                match names.iter().find(|n| n.name == *name) {
+                    Some(n) if n.called => CalcError::UnknownFunction {
+                        name: name.clone(),
+                        span: n.span.clone(),
+                        suggestion: suggest_function(name),
                     },
-                    _ => None,
+                    n => CalcError::UnknownVariable {
+                        name: name.clone(),
+                        span: n.map(|n| n.span.clone()),
+                    },
+                }
+            }
+
+            Expr::Assign { value, .. } => value.diagnose(env, names),
+
+            Expr::Unary { rhs, .. } if !has_value(rhs) => rhs.diagnose(env, names),
+            Expr::Unary { .. } if is_infinite() => domain("The result is too big"),
+            Expr::Unary { op, .. } => match op {
+                UnaryOp::Factorial => {
+                    domain("Factorials are only defined for whole numbers that aren't negative")
+                }
+                UnaryOp::Not => domain("~ only works on integers"),
+                _ => domain("The result is undefined"),
+            },
+
+            Expr::Binary { lhs, .. } if !has_value(lhs) => lhs.diagnose(env, names),
+            Expr::Binary { rhs, .. } if !has_value(rhs) => rhs.diagnose(env, names),
+            Expr::Binary { op, rhs, .. } => match op {
+                BinOp::Div | BinOp::Mod if is_zero(rhs) => domain("Division by zero"),
+                _ if is_infinite() => domain("The result is too big"),
+                BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+                    domain("Bitwise operators only work on integers")
+                }
+                _ => domain("The result is undefined"),
+            },
+
+            Expr::Func { args, .. } if args.iter().any(|arg| !has_value(arg)) => args
+                .iter()
+                .find(|arg| !has_value(arg))
+                .map_or(CalcError::Incomplete, |arg| arg.diagnose(env, names)),
+            Expr::Func { name, args } => {
+                let (min, max) = arity(name);
+                if args.len() < min || max.is_some_and(|max| args.len() > max) {
+                    CalcError::WrongArity {
+                        name: name.clone(),
+                        expected: describe_arity(min, max),
+                        found: args.len(),
+                        span: names
+                            .iter()
+                            .find(|n| n.name == *name)
+                            .map(|n| n.span.clone()),
+                    }
+                } else {
+                    CalcError::Domain(format!("{name} is undefined for these arguments"))
                 }
             }
+
+            _ => domain("The result is undefined"),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = CalcError;
+
+    /// Parses an expression written with a decimal point
+    fn from_str(s: &str) -> Result<Expr, CalcError> {
+        Expr::parse(s, DecimalSeparator::Point)
+    }
+}
+
+/// Parses a column of numbers (separated by spaces or newlines, like one pasted from a
+/// spreadsheet), or returns [`None`] if there are fewer than two or something else is in there
+pub fn parse_column(s: &str, decimal_separator: DecimalSeparator) -> Option<Vec<Expr>> {
+    let numbers = s
+        .split_whitespace()
+        .map(|number| {
+            Expr::parse(number, decimal_separator)
+                .ok()
+                .filter(Expr::is_number)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (numbers.len() >= 2).then_some(numbers)
+}
+
+/// The names of the builtin functions
+const FUNCTIONS: &[&str] = &[
+    "ln", "log", "exp", "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh",
+    "sqrt", "cbrt", "abs", "floor", "ceil", "round", "min", "max", "re", "im", "arg", "conj",
+    "sum", "avg", "mean", "median", "mode", "stdev", "var", "gcd", "lcm", "ncr", "npr",
+];
+
+/// The functions that are calculated by [`eval_list_func`]
+const LIST_FUNCTIONS: &[&str] = &[
+    "sum", "avg", "mean", "median", "mode", "var", "gcd", "lcm", "ncr", "npr",
+];
+
+/// The most items ncr and npr pick, so that they don't take forever
+const MAX_PICKED: u32 = 1000;
+
+/// Words that are operators rather than variables
+const KEYWORDS: &[&str] = &["xor", "of", "off"];
+
+/// The words that can follow a number to multiply it, with the power of 10 they multiply it by
+const MAGNITUDE_WORDS: &[(&str, u32)] = &[
+    ("thousand", 3),
+    ("million", 6),
+    ("billion", 9),
+    ("trillion", 12),
+];
+
+/// Whether there is a builtin function with this name
+fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+/// The least and most arguments a builtin function takes
+fn arity(name: &str) -> (usize, Option<usize>) {
+    match name {
+        "log" | "round" => (1, Some(2)),
+        "ncr" | "npr" => (2, Some(2)),
+        "min" | "max" | "sum" | "avg" | "mean" | "median" | "mode" | "gcd" | "lcm" => (1, None),
+        "stdev" | "var" => (2, None),
+        _ => (1, Some(1)),
+    }
+}
+
+/// How many arguments a function takes, in words
+fn describe_arity(min: usize, max: Option<usize>) -> String {
+    let arguments = |n: usize| if n == 1 { "argument" } else { "arguments" };
+    match max {
+        Some(max) if max == min => format!("{min} {}", arguments(min)),
+        Some(max) => format!("{min} or {max} {}", arguments(max)),
+        None => format!("at least {min} {}", arguments(min)),
+    }
+}
+
+/// The builtin function an unknown function name is probably a typo of (`sin` for `sni` or `Sin`):
+/// the closest one, if it's at most one edit away (two for longer names)
+fn suggest_function(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let allowed = if name.len() > 4 { 2 } else { 1 };
+    FUNCTIONS
+        .iter()
+        .map(|function| (edit_distance(&name, function), *function))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, function)| function)
+}
+
+/// The number of insertions, deletions, substitutions and swaps of neighbouring characters that
+/// turn one string into another (the optimal string alignment distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution = rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            row[j] = substitution.min(rows[i - 1][j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Calls a builtin function with already evaluated arguments, or returns [`None`] if there is no
+/// such function or it has the wrong number of arguments
+fn eval_func(name: &str, args: &[f64], angle_unit: AngleUnit) -> Option<f64> {
+    let trig = |f: fn(f64) -> f64, x: f64| {
+        let radians = angle_unit.to_radians(x);
+        snap(f(radians), radians)
+    };
+    let inverse_trig = |f: fn(f64) -> f64, x: f64| {
+        let angle = angle_unit.radians_to(f(x));
+        snap(angle, angle)
+    };
+
+    match (name, args) {
+        ("ln", [x]) => Some(x.ln()),
+        ("log", [x]) => Some(x.log10()),
+        ("log", [base, x]) => Some(x.log(*base)),
+        ("exp", [x]) => Some(x.exp()),
+
+        ("sin", [x]) => Some(trig(f64::sin, *x)),
+        ("cos", [x]) => Some(trig(f64::cos, *x)),
+        ("tan", [x]) => Some(trig(f64::tan, *x)),
+        ("asin", [x]) => Some(inverse_trig(f64::asin, *x)),
+        ("acos", [x]) => Some(inverse_trig(f64::acos, *x)),
+        ("atan", [x]) => Some(inverse_trig(f64::atan, *x)),
+        ("sinh", [x]) => Some(x.sinh()),
+        ("cosh", [x]) => Some(x.cosh()),
+        ("tanh", [x]) => Some(x.tanh()),
+
+        ("sqrt", [x]) => Some(x.sqrt()),
+        ("cbrt", [x]) => Some(x.cbrt()),
+        ("abs", [x]) => Some(x.abs()),
+        ("floor", [x]) => Some(x.floor()),
+        ("ceil", [x]) => Some(x.ceil()),
+        ("round", [x]) => Some(x.round()),
+        ("round", [x, digits]) => {
+            let scale = 10f64.powf(digits.trunc());
+            Some((x * scale).round() / scale)
+        }
+        ("min", [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, b| a.min(*b))),
+        ("max", [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, b| a.max(*b))),
+
+        ("re" | "conj", [x]) => Some(*x),
+        ("im", [_]) => Some(0.),
+        ("arg", [x]) => Some(angle_unit.radians_to(if *x < 0. { PI } else { 0. })),
+
+        // These are calculated exactly with the floats' values, and rounded back to floats
+        ("stdev", _) => eval_list_func("var", &exact_args(args)?)?
+            .to_f64()
+            .map(f64::sqrt),
+        _ if LIST_FUNCTIONS.contains(&name) => eval_list_func(name, &exact_args(args)?)?.to_f64(),
+
+        _ => None,
+    }
+}
+
+/// The exact values of float arguments, or [`None`] if one is infinite or NaN
+fn exact_args(args: &[f64]) -> Option<Vec<BigRational>> {
+    args.iter().map(|x| BigRational::from_float(*x)).collect()
+}
+
+/// Calls a statistics or number theory function exactly, or returns [`None`] if there is no such
+/// function or its arguments don't fit it (like the gcd of fractions)
+fn eval_list_func(name: &str, args: &[BigRational]) -> Option<BigRational> {
+    let count = || BigRational::from_integer(BigInt::from(args.len()));
+    match (name, args) {
+        ("sum", [_, ..]) => Some(args.iter().sum()),
+        ("avg" | "mean", [_, ..]) => Some(args.iter().sum::<BigRational>() / count()),
+        ("median", [_, ..]) => {
+            let mut sorted = args.to_vec();
+            sorted.sort();
+            let middle = sorted.len() / 2;
+            Some(if sorted.len().is_multiple_of(2) {
+                (&sorted[middle - 1] + &sorted[middle]) / BigRational::from_integer(BigInt::from(2))
+            } else {
+                sorted.swap_remove(middle)
+            })
+        }
+        ("mode", [_, ..]) => mode(args),
+        ("var", [_, _, ..]) => {
+            let mean = args.iter().sum::<BigRational>() / count();
+            let squares = args
+                .iter()
+                .map(|x| (x - &mean) * (x - &mean))
+                .sum::<BigRational>();
+            Some(squares / (count() - BigRational::one()))
+        }
+        ("gcd", [_, ..]) => args
+            .iter()
+            .try_fold(BigInt::zero(), |gcd, x| Some(gcd.gcd(&whole(x)?)))
+            .map(BigRational::from_integer),
+        ("lcm", [_, ..]) => args
+            .iter()
+            .try_fold(BigInt::one(), |lcm, x| Some(lcm.lcm(&whole(x)?)))
+            .map(BigRational::from_integer),
+        ("npr", [n, r]) => {
+            let (n, r) = picked(n, r)?;
+            Some(BigRational::from_integer((0..r).map(|k| &n - k).product()))
+        }
+        ("ncr", [n, r]) => {
+            let (n, r) = picked(n, r)?;
+            // C(n, r) = C(n, n - r), and each partial product is itself a binomial coefficient
+            let r = (&n - r).to_u32().map_or(r, |other| other.min(r));
+            let combinations = (1..=r).fold(BigInt::one(), |c, k| c * (&n - r + k) / k);
+            Some(BigRational::from_integer(combinations))
+        }
+        _ => None,
+    }
+}
+
+/// The most common value, the smallest one if several are as common, or [`None`] if no value is
+/// repeated
+fn mode(values: &[BigRational]) -> Option<BigRational> {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let runs = sorted.chunk_by(|a, b| a == b);
+    let longest = runs.clone().map(<[_]>::len).max()?;
+    if longest == 1 && values.len() > 1 {
+        return None;
+    }
+    runs.into_iter()
+        .find(|run| run.len() == longest)
+        .map(|run| run[0].clone())
+}
+
+/// The n and r of ncr and npr, if they're whole numbers with 0 <= r <= n
+fn picked(n: &BigRational, r: &BigRational) -> Option<(BigInt, u32)> {
+    let n = whole(n)?;
+    let r = whole(r)?.to_u32().filter(|r| *r <= MAX_PICKED)?;
+    (n >= BigInt::from(r)).then_some((n, r))
+}
+
+/// Calls a builtin function with complex arguments, or returns [`None`] if there is no such
+/// function, it has the wrong number of arguments or it only exists for real numbers
+fn eval_func_complex(name: &str, args: &[Complex], angle_unit: AngleUnit) -> Option<Complex> {
+    let trig = |f: fn(Complex) -> Complex, z: Complex| f(z.scale(angle_unit.radians()));
+    let inverse_trig =
+        |f: fn(Complex) -> Complex, z: Complex| f(z).scale(1. / angle_unit.radians());
+
+    match (name, args) {
+        ("ln", [z]) => Some(z.ln()),
+        ("log", [z]) => Some(z.ln().scale(1. / 10f64.ln())),
+        ("log", [base, z]) => Some(z.ln() / base.ln()),
+        ("exp", [z]) => Some(z.exp()),
+
+        ("sin", [z]) => Some(trig(Complex::sin, *z)),
+        ("cos", [z]) => Some(trig(Complex::cos, *z)),
+        ("tan", [z]) => Some(trig(Complex::tan, *z)),
+        ("asin", [z]) => Some(inverse_trig(Complex::asin, *z)),
+        ("acos", [z]) => Some(inverse_trig(Complex::acos, *z)),
+        ("atan", [z]) => Some(inverse_trig(Complex::atan, *z)),
+        ("sinh", [z]) => Some(z.sinh()),
+        ("cosh", [z]) => Some(z.cosh()),
+        ("tanh", [z]) => Some(z.tanh()),
+
+        ("sqrt", [z]) => Some(z.sqrt()),
+        ("cbrt", [z]) => Some(z.cbrt()),
+        ("abs", [z]) => Some(Complex::real(z.abs())),
+
+        ("re", [z]) => Some(Complex::real(z.re)),
+        ("im", [z]) => Some(Complex::real(z.im)),
+        ("arg", [z]) => Some(Complex::real(angle_unit.radians_to(z.arg()))),
+        ("conj", [z]) => Some(z.conj()),
+
+        _ => None,
+    }
+}
+
+/// Calls a builtin function exactly, or returns [`None`] if it doesn't have an exact result
+fn eval_func_exact(name: &str, args: &[BigRational]) -> Option<BigRational> {
+    match (name, args) {
+        ("abs", [x]) => Some(x.abs()),
+        ("floor", [x]) => Some(x.floor()),
+        ("ceil", [x]) => Some(x.ceil()),
+        ("round", [x]) => Some(x.round()),
+        ("round", [x, digits]) => {
+            let scale = exact_pow(BigRational::from_integer(BigInt::from(10)), digits)?;
+            Some((x * &scale).round() / scale)
+        }
+        ("min", [first, rest @ ..]) => Some(rest.iter().fold(first, Ord::min).clone()),
+        ("max", [first, rest @ ..]) => Some(rest.iter().fold(first, Ord::max).clone()),
+        _ => eval_list_func(name, args),
+    }
+}
+
+/// Whether a variable can be given this name, which has to be an identifier that isn't a constant
+/// or [`ANS`]
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && constant(name).is_none()
+        && !is_imaginary_unit(name)
+        && !KEYWORDS.contains(&name)
+        && name != ANS
+}
+
+/// The value of a constant, like pi
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => Some(PI),
+        "e" => Some(E),
+        "tau" => Some(TAU),
+        _ => None,
+    }
+}
+
+/// Whether the name is the imaginary unit, which can be written as i (like in maths) or j (like in
+/// engineering)
+fn is_imaginary_unit(name: &str) -> bool {
+    matches!(name, "i" | "j")
+}
+
+/// Applies a unary operator to a float
+fn real_unary(op: UnaryOp, v: f64) -> Option<f64> {
+    match op {
+        UnaryOp::Plus => Some(v),
+        UnaryOp::Minus => Some(-v),
+        UnaryOp::Not => to_int(v).map(|n| int_to_float(!n)),
+        UnaryOp::Factorial => factorial(v),
+        UnaryOp::Percent => Some(v / 100.),
+    }
+}
+
+/// Applies a binary operator to floats
+fn real_binary(op: BinOp, a: f64, b: f64) -> Option<f64> {
+    match op {
+        BinOp::Add => Some(a + b),
+        BinOp::Sub => Some(a - b),
+        BinOp::Mul => Some(a * b),
+        BinOp::Div => Some(a / b),
+        BinOp::Mod => Some(a % b),
+        BinOp::Pow => Some(a.powf(b)),
+        BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+            int_bitwise(op, to_int(a)?, to_int(b)?).map(int_to_float)
+        }
+    }
+}
+
+/// The factorial of a whole number, or [`None`] for negative and fractional numbers
+fn factorial(n: f64) -> Option<f64> {
+    if n < 0. || n.fract() != 0. {
+        return None;
+    }
+    if n > MAX_FACTORIAL {
+        return Some(f64::INFINITY);
+    }
+    Some((2..=n as u32).map(f64::from).product())
+}
+
+/// The factorial of a whole number, calculated exactly
+fn exact_factorial(n: &BigRational) -> Option<BigRational> {
+    let n = whole(n)?
+        .to_u32()
+        .filter(|n| f64::from(*n) <= MAX_FACTORIAL)?;
+    Some(BigRational::from_integer(
+        (2..=n).map(BigInt::from).product(),
+    ))
+}
+
+/// Applies a binary operator exactly, or returns [`None`] if the result isn't rational or would
+/// be too big
+fn exact_binary(op: BinOp, a: BigRational, b: BigRational) -> Option<BigRational> {
+    let shift = |b: &BigRational| whole(b)?.to_u64().filter(|shift| *shift <= MAX_EXACT_BITS);
+    match op {
+        BinOp::Add => Some(a + b),
+        BinOp::Sub => Some(a - b),
+        BinOp::Mul => Some(a * b),
+        BinOp::Div => (!b.is_zero()).then(|| a / b),
+        // Like the float remainder, this has the sign of the dividend
+        BinOp::Mod => (!b.is_zero()).then(|| {
+            let quotient = (&a / &b).trunc();
+            a - b * quotient
+        }),
+        BinOp::Pow => exact_pow(a, &b),
+        BinOp::BitAnd => Some(BigRational::from_integer(whole(&a)? & whole(&b)?)),
+        BinOp::BitOr => Some(BigRational::from_integer(whole(&a)? | whole(&b)?)),
+        BinOp::BitXor => Some(BigRational::from_integer(whole(&a)? ^ whole(&b)?)),
+        BinOp::Shl => Some(BigRational::from_integer(whole(&a)? << shift(&b)?)),
+        BinOp::Shr => Some(BigRational::from_integer(whole(&a)? >> shift(&b)?)),
+    }
+}
+
+/// Raises a number to a whole power exactly, or returns [`None`] if the power isn't whole or the
+/// result would be too big
+fn exact_pow(base: BigRational, exponent: &BigRational) -> Option<BigRational> {
+    let exponent = whole(exponent)?.to_i32()?;
+    if base.is_zero() && exponent < 0 {
+        return None;
+    }
+    if base.abs() != BigRational::one() {
+        let bits = base.numer().bits().max(base.denom().bits());
+        if bits * u64::from(exponent.unsigned_abs()) > MAX_EXACT_BITS {
+            return None;
         }
     }
+    Some(base.pow(exponent))
+}
 
-    pub fn from_str(s: &str) -> Result<Expr, String> {
-        let mut p = Parser::new(s);
-        let expr = p.parse_expr()?;
-        p.expect(&Token::End)?;
-        Ok(expr)
+/// The number as an integer, if it's a whole number
+fn whole(n: &BigRational) -> Option<BigInt> {
+    n.is_integer().then(|| n.to_integer())
+}
+
+/// Applies a bitwise operator to integers, or returns [`None`] if a shift overflows
+fn int_bitwise(op: BinOp, a: i128, b: i128) -> Option<i128> {
+    match op {
+        BinOp::BitAnd => Some(a & b),
+        BinOp::BitOr => Some(a | b),
+        BinOp::BitXor => Some(a ^ b),
+        BinOp::Shl => a.checked_shl(u32::try_from(b).ok()?),
+        BinOp::Shr => a.checked_shr(u32::try_from(b).ok()?),
+        _ => None,
+    }
+}
+
+/// Converts a whole number to an integer, for the bitwise operators
+fn to_int(x: f64) -> Option<i128> {
+    #[allow(clippy::cast_possible_truncation)]
+    (x.fract() == 0. && x.abs() < 2f64.powi(127)).then_some(x as i128)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn int_to_float(n: i128) -> f64 {
+    n as f64
+}
+
+/// Rounds a result to the nearest whole number if it's only off by a rounding error
+///
+/// The errors come from the angle, so they're relative to it for angles smaller than 1: sin(pi)
+/// is off by 1e-16, but sin(1e-13) is a genuine 1e-13
+fn snap(x: f64, angle: f64) -> f64 {
+    if (x - x.round()).abs() < TRIG_EPSILON * angle.abs().min(1.) {
+        x.round()
+    } else {
+        x
     }
 }
 
@@ -111,26 +1265,58 @@ impl Expr {
 #[derive(Debug, Clone, PartialEq)]
 enum Token {
     Number(f64),
+    Decimal(BigRational),
+    Integer(i128, Base),
     Ident(String),
     Plus,
     Minus,
     Star,
     Slash,
     Caret,
+    Percent,
+    Bang,
+    Amp,
+    Pipe,
+    Tilde,
+    Xor,
+    Of,
+    Off,
+    Shl,
+    Shr,
     LParen,
     RParen,
     Comma,
     End,
 }
 
+#[derive(Clone)]
 struct Lexer<'a> {
     input: &'a str,
     i: usize,
+    /// Where the last token starts
+    start: usize,
+    decimal_separator: DecimalSeparator,
+    /// Whether each open parenthesis is a function call's, where `,` only separates arguments
+    parens: Vec<bool>,
+    /// Whether the last token was the name of a function
+    after_function: bool,
 }
 
 impl<'a> Lexer<'a> {
-    fn new(input: &'a str) -> Self {
-        Self { input, i: 0 }
+    fn new(input: &'a str, decimal_separator: DecimalSeparator, start: usize) -> Self {
+        Self {
+            input,
+            i: start,
+            start,
+            decimal_separator,
+            parens: Vec::new(),
+            after_function: false,
+        }
+    }
+
+    /// Whether the lexer is in the arguments of a function call
+    fn in_call(&self) -> bool {
+        self.parens.last() == Some(&true)
     }
 
     fn peek_char(&self) -> Option<char> {
@@ -149,8 +1335,22 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn next_token(&mut self) -> Result<Token, String> {
+    fn next_token(&mut self) -> Result<Token, CalcError> {
+        let token = self.lex_token()?;
+        match &token {
+            Token::LParen => self.parens.push(self.after_function),
+            Token::RParen => {
+                self.parens.pop();
+            }
+            _ => {}
+        }
+        self.after_function = matches!(&token, Token::Ident(name) if is_function(name));
+        Ok(token)
+    }
+
+    fn lex_token(&mut self) -> Result<Token, CalcError> {
         self.skip_ws();
+        self.start = self.i;
         let Some(c) = self.peek_char() else {
             return Ok(Token::End);
         };
@@ -177,6 +1377,33 @@ impl<'a> Lexer<'a> {
                 self.bump_char();
                 Token::Caret
             }
+            '%' => {
+                self.bump_char();
+                Token::Percent
+            }
+            '!' => {
+                self.bump_char();
+                Token::Bang
+            }
+            '&' => {
+                self.bump_char();
+                Token::Amp
+            }
+            '|' => {
+                self.bump_char();
+                Token::Pipe
+            }
+            '~' => {
+                self.bump_char();
+                Token::Tilde
+            }
+            '<' | '>' => {
+                self.bump_char();
+                if self.bump_char() != Some(c) {
+                    return Err(self.unexpected_char(c));
+                }
+                if c == '<' { Token::Shl } else { Token::Shr }
+            }
             '(' => {
                 self.bump_char();
                 Token::LParen
@@ -196,39 +1423,166 @@ impl<'a> Lexer<'a> {
                 } else if c.is_ascii_alphabetic() || c == '_' {
                     return Ok(self.lex_ident());
                 }
-                return Err(format!("Unexpected character: {c}"));
+                return Err(self.unexpected_char(c));
             }
         };
         Ok(tok)
     }
 
-    fn lex_number(&mut self) -> Result<Token, String> {
-        // Simple float lexer: digits/./e/E/+/- in exponent
+    /// The error for a character that can't start a token, at the start of the current one
+    fn unexpected_char(&self, c: char) -> CalcError {
+        CalcError::Unexpected {
+            found: c.to_string(),
+            span: self.start..self.start + c.len_utf8(),
+        }
+    }
+
+    fn lex_number(&mut self) -> Result<Token, CalcError> {
+        if let Some(token) = self.lex_radix_integer() {
+            return token;
+        }
+
+        // Simple float lexer: digits/./e/E/+/- in exponent, skipping grouping separators. The
+        // number is rewritten with a decimal point, whatever the decimal separator is
         let start = self.i;
+        let mut number = String::new();
+        let mut seen_point = false;
         let mut seen_e = false;
+        let mut grouped = false;
 
         while let Some(c) = self.peek_char() {
-            if c.is_ascii_digit() || c == '.' {
-                self.bump_char();
-                continue;
-            }
-            if (c == 'e' || c == 'E') && !seen_e {
+            if c.is_ascii_digit() {
+                number.push(c);
+            } else if c == self.decimal_separator.grouping()
+                && !seen_point
+                && !seen_e
+                && (grouped || (1..=3).contains(&number.len()))
+                && self.grouping_follows()
+            {
+                grouped = true;
+            } else if self.is_decimal_point(c) && !seen_point && !seen_e {
+                seen_point = true;
+                number.push('.');
+            } else if (c == 'e' || c == 'E') && !seen_e && self.exponent_follows() {
                 seen_e = true;
+                number.push(c);
                 self.bump_char();
                 // optional sign after exponent
-                if matches!(self.peek_char(), Some('+' | '-')) {
+                if let Some(sign @ ('+' | '-')) = self.peek_char() {
+                    number.push(sign);
                     self.bump_char();
                 }
                 continue;
+            } else {
+                break;
             }
-            break;
+            self.bump_char();
+        }
+
+        // numbers are kept exact when they can be, so that they can be calculated with exactly
+        let token = if let Ok(n) = number.parse::<i128>() {
+            Token::Integer(n, Base::Dec)
+        } else if let Some(r) = parse_decimal(&number) {
+            Token::Decimal(r)
+        } else {
+            let n = number
+                .parse::<f64>()
+                .map_err(|_| CalcError::InvalidNumber {
+                    number: self.input[start..self.i].to_string(),
+                    span: start..self.i,
+                })?;
+            Token::Number(n)
+        };
+
+        Ok(match self.lex_magnitude() {
+            Some(exponent) => scale_number(token, exponent),
+            None => token,
+        })
+    }
+
+    /// Whether the grouping separator at the current position separates groups of digits, which
+    /// it does when it's followed by exactly three digits. In function calls, `,` only separates
+    /// arguments
+    fn grouping_follows(&self) -> bool {
+        let mut rest = self.input[self.i..].chars().skip(1);
+        (self.decimal_separator.grouping() != ',' || !self.in_call())
+            && rest.by_ref().take(3).filter(char::is_ascii_digit).count() == 3
+            && !rest.next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    /// Whether the character is a decimal separator: the configured one (except a `,` in function
+    /// calls, where it separates arguments), or a point that doesn't group digits
+    fn is_decimal_point(&self, c: char) -> bool {
+        c == '.'
+            || (c == ',' && self.decimal_separator == DecimalSeparator::Comma && !self.in_call())
+    }
+
+    /// Lexes a magnitude suffix right after a number (`k`, `M`, `G`) or a word after it
+    /// (`million`), and returns the power of 10 it multiplies the number by
+    fn lex_magnitude(&mut self) -> Option<u32> {
+        let rest = &self.input[self.i..];
+        let ends_word =
+            |rest: &str| !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+
+        let mut chars = rest.chars();
+        if let Some(suffix @ ('k' | 'K' | 'M' | 'G')) = chars.next()
+            && ends_word(chars.as_str())
+        {
+            self.i += 1;
+            return Some(match suffix {
+                'k' | 'K' => 3,
+                'M' => 6,
+                _ => 9,
+            });
+        }
+
+        let trimmed = rest.trim_start();
+        MAGNITUDE_WORDS.iter().find_map(|(word, exponent)| {
+            let after = trimmed
+                .strip_prefix(word)
+                .filter(|after| ends_word(after))?;
+            self.i = self.input.len() - after.len();
+            Some(*exponent)
+        })
+    }
+
+    /// Lexes an integer with a base prefix (`0x`, `0b` or `0o`), if there is one here
+    fn lex_radix_integer(&mut self) -> Option<Result<Token, CalcError>> {
+        let mut chars = self.input[self.i..].chars();
+        let base = match (chars.next(), chars.next().and_then(Base::from_prefix)) {
+            (Some('0'), Some(base)) => base,
+            _ => return None,
+        };
+        // `0b` followed by something that isn't a binary digit is 0 times b
+        if !chars.next().is_some_and(|c| c.is_digit(base.radix())) {
+            return None;
+        }
+
+        self.i += 2;
+        let start = self.i;
+        while matches!(self.peek_char(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            self.bump_char();
         }
+        let digits = self.input[start..self.i].replace('_', "");
+        Some(
+            i128::from_str_radix(&digits, base.radix())
+                .map(|n| Token::Integer(n, base))
+                .map_err(|_| CalcError::InvalidNumber {
+                    number: self.input[start - 2..self.i].to_string(),
+                    span: start - 2..self.i,
+                }),
+        )
+    }
 
-        let s = &self.input[start..self.i];
-        let n = s
-            .parse::<f64>()
-            .map_err(|_| format!("Invalid number: {s}"))?;
-        Ok(Token::Number(n))
+    /// Whether the `e` at the current position starts an exponent (`2e3`, `2e-3`), rather than
+    /// being the constant e (`2e`)
+    fn exponent_follows(&self) -> bool {
+        let mut rest = self.input[self.i..].chars().skip(1);
+        match rest.next() {
+            Some('+' | '-') => rest.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        }
     }
 
     fn lex_ident(&mut self) -> Token {
@@ -240,7 +1594,27 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
-        Token::Ident(self.input[start..self.i].to_string())
+        match &self.input[start..self.i] {
+            "xor" => Token::Xor,
+            "of" => Token::Of,
+            "off" => Token::Off,
+            ident => Token::Ident(ident.to_string()),
+        }
+    }
+}
+
+/// Multiplies a number token by a power of 10, keeping it an integer if it fits
+fn scale_number(token: Token, exponent: u32) -> Token {
+    let scale = 10i128.pow(exponent);
+    match token {
+        Token::Integer(n, base) => match n.checked_mul(scale) {
+            Some(n) => Token::Integer(n, base),
+            None => Token::Decimal(BigRational::from_integer(BigInt::from(n) * scale)),
+        },
+        Token::Decimal(r) => Token::Decimal(r * BigRational::from_integer(BigInt::from(scale))),
+        #[allow(clippy::cast_precision_loss)]
+        Token::Number(n) => Token::Number(n * scale as f64),
+        token => token,
     }
 }
 
@@ -249,30 +1623,125 @@ impl<'a> Lexer<'a> {
 struct Parser<'a> {
     lex: Lexer<'a>,
     cur: Token,
+    /// Where the current token is
+    span: Range<usize>,
+    /// The names of the variables and functions parsed so far
+    names: Vec<Name>,
+    /// Whether names can be units, so that a divisor includes the units after it: `60 km / 45 min`
+    /// is `60 km / (45 min)`
+    quantities: bool,
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
-        let mut lex = Lexer::new(input);
-        let cur = lex.next_token().unwrap_or(Token::End);
-        Self { lex, cur }
+    /// A parser for the input from `start` on
+    fn new(
+        input: &'a str,
+        decimal_separator: DecimalSeparator,
+        start: usize,
+    ) -> Result<Self, CalcError> {
+        let mut lex = Lexer::new(input, decimal_separator, start);
+        let cur = lex.next_token()?;
+        let span = lex.start..lex.i;
+        Ok(Self {
+            lex,
+            cur,
+            span,
+            names: Vec::new(),
+            quantities: false,
+        })
     }
 
-    fn bump(&mut self) -> Result<(), String> {
+    fn bump(&mut self) -> Result<(), CalcError> {
         self.cur = self.lex.next_token()?;
+        self.span = self.lex.start..self.lex.i;
         Ok(())
     }
 
-    fn expect(&mut self, t: &Token) -> Result<(), String> {
+    /// The error for the current token, which can't be where it is
+    fn unexpected(&self) -> CalcError {
+        if self.cur == Token::End {
+            CalcError::Incomplete
+        } else {
+            CalcError::Unexpected {
+                found: self.lex.input[self.span.clone()].to_string(),
+                span: self.span.clone(),
+            }
+        }
+    }
+
+    /// Whether the current '%' is the remainder operator: it is when what follows can be its right
+    /// side, or when it's spaced out like an operator and followed by a signed operand
+    /// (`10 % -3`). Right after its number and before a sign, it's a percentage: `200 + 10% - 5`
+    fn is_remainder(&self) -> bool {
+        let mut lex = self.lex.clone();
+        let next = lex.next_token().unwrap_or(Token::End);
+        if starts_operand(&next) {
+            return true;
+        }
+
+        let input = self.lex.input;
+        let spaced = input[..self.span.start].ends_with(char::is_whitespace)
+            && input[self.span.end..].starts_with(char::is_whitespace);
+        spaced
+            && matches!(next, Token::Plus | Token::Minus)
+            && starts_operand(&lex.next_token().unwrap_or(Token::End))
+    }
+
+    fn expect(&mut self, t: &Token) -> Result<(), CalcError> {
         if self.cur == *t {
             self.bump()
         } else {
-            Err(format!("Expected {:?}, found {:?}", t, self.cur))
+            Err(self.unexpected())
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, CalcError> {
+        // expr = xor ('|' xor)*
+        self.parse_binary_level(&[(Token::Pipe, BinOp::BitOr)], Self::parse_xor)
+    }
+
+    fn parse_xor(&mut self) -> Result<Expr, CalcError> {
+        // xor = and ('xor' and)*
+        self.parse_binary_level(&[(Token::Xor, BinOp::BitXor)], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, CalcError> {
+        // and = shift ('&' shift)*
+        self.parse_binary_level(&[(Token::Amp, BinOp::BitAnd)], Self::parse_shift)
+    }
+
+    fn parse_shift(&mut self) -> Result<Expr, CalcError> {
+        // shift = sum (('<<'|'>>') sum)*
+        self.parse_binary_level(
+            &[(Token::Shl, BinOp::Shl), (Token::Shr, BinOp::Shr)],
+            Self::parse_sum,
+        )
+    }
+
+    /// Parses a left associative chain of operands separated by the given operators
+    fn parse_binary_level(
+        &mut self,
+        ops: &[(Token, BinOp)],
+        operand: fn(&mut Self) -> Result<Expr, CalcError>,
+    ) -> Result<Expr, CalcError> {
+        let mut node = operand(self)?;
+        while let Some((_, op)) = ops.iter().find(|(token, _)| *token == self.cur) {
+            let op = *op;
+            self.bump()?;
+            let rhs = operand(self)?;
+            node = Expr::Binary {
+                op,
+                lhs: Box::new(node),
+                rhs: Box::new(rhs),
+            };
         }
+        Ok(node)
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, String> {
-        // expr = term (('+'|'-') term)*
+    fn parse_sum(&mut self) -> Result<Expr, CalcError> {
+        // sum = term (('+'|'-') term)*
+        // where adding or subtracting a percentage (200 + 10%) changes the left side by that
+        // percentage of it, unless it's a percentage too
         let mut node = self.parse_term()?;
         loop {
             let op = match self.cur {
@@ -282,26 +1751,54 @@ impl<'a> Parser<'a> {
             };
             self.bump()?;
             let rhs = self.parse_term()?;
-            node = Expr::Binary {
-                op,
-                lhs: Box::new(node),
-                rhs: Box::new(rhs),
+            node = if rhs.is_percentage() && !node.is_percentage() {
+                // a ± p% = a * (1 ± p%)
+                Expr::Binary {
+                    op: BinOp::Mul,
+                    lhs: Box::new(node),
+                    rhs: Box::new(Expr::Binary {
+                        op,
+                        lhs: Box::new(Expr::Integer(1, Base::Dec)),
+                        rhs: Box::new(rhs),
+                    }),
+                }
+            } else {
+                Expr::Binary {
+                    op,
+                    lhs: Box::new(node),
+                    rhs: Box::new(rhs),
+                }
             };
         }
         Ok(node)
     }
 
-    fn parse_term(&mut self) -> Result<Expr, String> {
-        // term = power (('*'|'/') power)*
+    fn parse_term(&mut self) -> Result<Expr, CalcError> {
+        // term = power (('*'|'/'|'%') power | implicit-mul power)*
+        // where implicit multiplication is a power starting with an identifier or '('
         let mut node = self.parse_power()?;
         loop {
             let op = match self.cur {
-                Token::Star => BinOp::Mul,
-                Token::Slash => BinOp::Div,
+                Token::Star | Token::Slash | Token::Percent => {
+                    let op = match self.cur {
+                        Token::Star => BinOp::Mul,
+                        Token::Slash => BinOp::Div,
+                        _ => BinOp::Mod,
+                    };
+                    self.bump()?;
+                    op
+                }
+                Token::Ident(_) | Token::LParen => BinOp::Mul,
                 _ => break,
             };
-            self.bump()?;
-            let rhs = self.parse_power()?;
+            let mut rhs = self.parse_power()?;
+            while self.quantities && op == BinOp::Div && matches!(self.cur, Token::Ident(_)) {
+                rhs = Expr::Binary {
+                    op: BinOp::Mul,
+                    lhs: Box::new(rhs),
+                    rhs: Box::new(self.parse_power()?),
+                };
+            }
             node = Expr::Binary {
                 op,
                 lhs: Box::new(node),
@@ -311,7 +1808,7 @@ impl<'a> Parser<'a> {
         Ok(node)
     }
 
-    fn parse_power(&mut self) -> Result<Expr, String> {
+    fn parse_power(&mut self) -> Result<Expr, CalcError> {
         // power = unary ('^' power)?  (right associative)
         let lhs = self.parse_unary()?;
         if self.cur == Token::Caret {
@@ -327,9 +1824,16 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_unary(&mut self) -> Result<Expr, String> {
-        // unary = ('+'|'-')* primary
+    fn parse_unary(&mut self) -> Result<Expr, CalcError> {
+        // unary = ('+'|'-'|'~')* postfix
         match self.cur {
+            Token::Tilde => {
+                self.bump()?;
+                Ok(Expr::Unary {
+                    op: UnaryOp::Not,
+                    rhs: Box::new(self.parse_unary()?),
+                })
+            }
             Token::Plus => {
                 self.bump()?;
                 Ok(Expr::Unary {
@@ -344,17 +1848,70 @@ impl<'a> Parser<'a> {
                     rhs: Box::new(self.parse_unary()?),
                 })
             }
-            _ => self.parse_primary(),
+            _ => self.parse_postfix(),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, CalcError> {
+        // postfix = primary ('!' | '%' (('of'|'off') power)?)*
+        // where '%' is only a percentage when it isn't the remainder operator
+        let mut node = self.parse_primary()?;
+        loop {
+            let op = match self.cur {
+                Token::Bang => UnaryOp::Factorial,
+                Token::Percent if !self.is_remainder() => UnaryOp::Percent,
+                _ => break,
+            };
+            self.bump()?;
+            node = Expr::Unary {
+                op,
+                rhs: Box::new(node),
+            };
+
+            if op == UnaryOp::Percent && matches!(self.cur, Token::Of | Token::Off) {
+                let off = self.cur == Token::Off;
+                self.bump()?;
+                let whole = self.parse_power()?;
+                // p% of x = p% * x, and p% off x = x * (1 - p%)
+                node = if off {
+                    Expr::Binary {
+                        op: BinOp::Mul,
+                        lhs: Box::new(whole),
+                        rhs: Box::new(Expr::Binary {
+                            op: BinOp::Sub,
+                            lhs: Box::new(Expr::Integer(1, Base::Dec)),
+                            rhs: Box::new(node),
+                        }),
+                    }
+                } else {
+                    Expr::Binary {
+                        op: BinOp::Mul,
+                        lhs: Box::new(node),
+                        rhs: Box::new(whole),
+                    }
+                };
+            }
         }
+        Ok(node)
     }
 
-    fn parse_primary(&mut self) -> Result<Expr, String> {
+    fn parse_primary(&mut self) -> Result<Expr, CalcError> {
         match &self.cur {
             Token::Number(n) => {
                 let v = *n;
                 self.bump()?;
                 Ok(Expr::Number(v))
             }
+            Token::Decimal(r) => {
+                let r = r.clone();
+                self.bump()?;
+                Ok(Expr::Decimal(r))
+            }
+            Token::Integer(n, base) => {
+                let (n, base) = (*n, *base);
+                self.bump()?;
+                Ok(Expr::Integer(n, base))
+            }
             Token::LParen => {
                 self.bump()?;
                 let e = self.parse_expr()?;
@@ -363,9 +1920,24 @@ impl<'a> Parser<'a> {
             }
             Token::Ident(name) => {
                 let name = name.clone();
+                let span = self.span.clone();
+                self.bump()?;
+                // an identifier is a constant, the imaginary unit, a variable (all of which can be
+                // followed by '(' as an implicit multiplication) or a function call: ident '(' ...
+                if let Some(value) = constant(&name) {
+                    return Ok(Expr::Number(value));
+                } else if is_imaginary_unit(&name) {
+                    return Ok(Expr::Imaginary);
+                }
+                self.names.push(Name {
+                    name: name.clone(),
+                    span,
+                    called: self.cur == Token::LParen,
+                });
+                if self.cur != Token::LParen || !is_function(&name) {
+                    return Ok(Expr::Var(name));
+                }
                 self.bump()?;
-                // function call must be ident '(' ...
-                self.expect(&Token::LParen)?;
                 let mut args = Vec::new();
                 if self.cur != Token::RParen {
                     loop {
@@ -380,7 +1952,850 @@ impl<'a> Parser<'a> {
                 self.expect(&Token::RParen)?;
                 Ok(Expr::Func { name, args })
             }
-            _ => Err(format!("Unexpected token: {:?}", self.cur)),
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Whether the token can start an operand, which makes a '%' before it the remainder operator.
+/// Signs don't count, so that `200 + 10% - 5` is a percentage (see [`Parser::is_remainder`])
+fn starts_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Number(_)
+            | Token::Decimal(_)
+            | Token::Integer(..)
+            | Token::Ident(_)
+            | Token::LParen
+            | Token::Tilde
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::calc_value::Format;
+
+    /// Checks that each expression evaluates to the expected value (up to rounding errors)
+    fn assert_evals(cases: &[(&str, f64)], angle_unit: AngleUnit) {
+        assert_evals_with(cases, angle_unit, &HashMap::new());
+    }
+
+    /// Like [`assert_evals`], with some variables set
+    fn assert_evals_with(
+        cases: &[(&str, f64)],
+        angle_unit: AngleUnit,
+        variables: &HashMap<String, Value>,
+    ) {
+        let env = Env {
+            angle_unit,
+            variables,
+        };
+        for (input, expected) in cases {
+            let expr =
+                Expr::from_str(input).unwrap_or_else(|e| panic!("{input} didn't parse: {e}"));
+            let result = expr
+                .eval(&env)
+                .unwrap_or_else(|| panic!("{input} didn't evaluate"));
+            assert!(
+                (result - expected).abs() <= 1e-9 * expected.abs().max(1.),
+                "{input}: expected {expected}, got {result}"
+            );
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_evals(
+            &[
+                ("2 + 3*4", 14.),
+                ("2^(1+2)", 8.),
+                ("2^3^2", 512.),
+                ("-(3 + 4)", -7.),
+                ("7 / 2", 3.5),
+                ("10 % 4", 2.),
+                ("-7 % 3", -1.),
+                ("5.5 % 2", 1.5),
+                ("2 + 10 % 4 * 3", 8.),
+                ("1.5e3", 1500.),
+                ("2e-3", 0.002),
+                ("log(100)", 2.),
+                ("log(2, 8)", 3.),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn constants() {
+        assert_evals(
+            &[
+                ("pi", PI),
+                ("e", E),
+                ("tau", TAU),
+                ("tau / 2", PI),
+                ("ln(e)", 1.),
+                ("e^2", E * E),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        assert_evals(
+            &[
+                ("2pi", 2. * PI),
+                ("2 pi", 2. * PI),
+                ("3(4+5)", 27.),
+                ("(1+1)(2+2)", 8.),
+                ("2e", 2. * E),
+                ("pi(2)", 2. * PI),
+                ("2sqrt(9)", 6.),
+                ("2pi^2", 2. * PI * PI),
+                ("1 + 2(3)", 7.),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn factorial() {
+        assert_evals(
+            &[
+                ("0!", 1.),
+                ("5!", 120.),
+                ("3!!", 720.),
+                ("(2+1)!", 6.),
+                ("-3!", -6.),
+                ("2^3!", 64.),
+                ("170!", 7.257_415_615_307_999e306),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn functions() {
+        assert_evals(
+            &[
+                ("sqrt(16)", 4.),
+                ("cbrt(-27)", -3.),
+                ("abs(-2.5)", 2.5),
+                ("floor(2.7)", 2.),
+                ("floor(-2.5)", -3.),
+                ("ceil(2.1)", 3.),
+                ("round(2.5)", 3.),
+                ("round(1.23456, 2)", 1.23),
+                ("min(3, 1, 2)", 1.),
+                ("max(3, 1, 2)", 3.),
+                ("max(4)", 4.),
+                ("exp(0)", 1.),
+                ("sinh(0)", 0.),
+                ("cosh(0)", 1.),
+                ("tanh(0)", 0.),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn trigonometry_in_radians() {
+        assert_evals(
+            &[
+                ("sin(pi)", 0.),
+                ("sin(pi / 2)", 1.),
+                ("cos(pi)", -1.),
+                ("tan(pi / 4)", 1.),
+                ("asin(1)", PI / 2.),
+                ("acos(-1)", PI),
+                ("atan(1)", PI / 4.),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn small_trigonometric_results() {
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        for input in ["sin(1e-13)", "tan(1e-13)", "asin(1e-13)", "atan(1e-13)"] {
+            let result = Expr::from_str(input).unwrap().eval(&env).unwrap();
+            assert!(
+                (result - 1e-13).abs() < 1e-25,
+                "{input}: expected 1e-13, got {result}"
+            );
+        }
+
+        let env = Env {
+            angle_unit: AngleUnit::Degrees,
+            ..env
+        };
+        let result = Expr::from_str("sin(1e-10)").unwrap().eval(&env).unwrap();
+        assert!(
+            (result - 1e-10_f64.to_radians()).abs() < 1e-25,
+            "got {result}"
+        );
+    }
+
+    #[test]
+    fn trigonometry_in_degrees() {
+        assert_evals(
+            &[
+                ("sin(180)", 0.),
+                ("sin(30)", 0.5),
+                ("cos(60)", 0.5),
+                ("tan(45)", 1.),
+                ("asin(1)", 90.),
+                ("acos(0.5)", 60.),
+                ("atan(1)", 45.),
+                ("sin(pi)", PI.to_radians().sin()),
+            ],
+            AngleUnit::Degrees,
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let variables = HashMap::from([
+            ("x".to_string(), Value::Float(3.5)),
+            ("rate_2".to_string(), Value::Float(0.2)),
+            (ANS.to_string(), Value::Float(10.)),
+        ]);
+        assert_evals_with(
+            &[
+                ("x", 3.5),
+                ("2x", 7.),
+                ("x(2)", 7.),
+                ("x^2", 12.25),
+                ("ans * 2", 20.),
+                ("ans x", 35.),
+                ("100 rate_2", 20.),
+                ("y = x + 1", 4.5),
+                ("total = ans", 10.),
+            ],
+            AngleUnit::Radians,
+            &variables,
+        );
+
+        assert_eq!(
+            Expr::from_str("y = 2 + 3"),
+            Ok(Expr::Assign {
+                name: "y".to_string(),
+                value: Box::new(Expr::Binary {
+                    op: BinOp::Add,
+                    lhs: Box::new(Expr::Integer(2, Base::Dec)),
+                    rhs: Box::new(Expr::Integer(3, Base::Dec)),
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn percentages() {
+        assert_evals(
+            &[
+                ("15% of 80", 12.),
+                ("200 + 10%", 220.),
+                ("200 - 10%", 180.),
+                ("200 * 10%", 20.),
+                ("50%", 0.5),
+                ("20% off 80", 64.),
+                ("(5 + 5)% of 80", 8.),
+                ("15% of 80 + 5", 17.),
+                ("10% of 2^3", 0.8),
+                ("200 + 10% + 10%", 242.),
+                // a percentage plus a percentage is just their sum
+                ("10% + 5%", 0.15),
+                // only a bare percentage changes the left side, not one in a product
+                ("200 + 10% * 2", 200.2),
+                // % followed by something that can be its right side is the remainder
+                ("10 % 4", 2.),
+                ("10%4", 2.),
+                ("10 % (3)", 1.),
+                ("10 % abs(-4)", 2.),
+                // but not a sign, so that percentages can be followed by a subtraction
+                ("200 + 10% - 5", 215.),
+                ("200 + 10%-5", 215.),
+                // unless the % is spaced out like an operator
+                ("10 % -3", 1.),
+                ("10 % +3", 1.),
+                ("-7 % -(2)", -1.),
+            ],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn grouping_separators_and_magnitudes() {
+        assert_evals_with(
+            &[
+                ("1,250 * 3", 3750.),
+                ("1,234,567.5", 1_234_567.5),
+                ("-1,000", -1000.),
+                ("2.5k * 4", 10_000.),
+                ("1K", 1000.),
+                ("3M", 3e6),
+                ("4G", 4e9),
+                ("3 million / 12", 250_000.),
+                ("1.5 billion", 1.5e9),
+                ("2 thousand", 2000.),
+                ("1,500k", 1.5e6),
+                // in function calls, commas separate arguments
+                ("max(1,250)", 250.),
+                ("max((1,250), 2)", 1250.),
+                // k after a space is a variable, and after letters it isn't a suffix
+                ("2 k", 6.),
+                ("2kk", 12.),
+            ],
+            AngleUnit::Radians,
+            &HashMap::from([
+                ("k".to_string(), Value::Float(3.)),
+                ("kk".to_string(), Value::Float(6.)),
+            ]),
+        );
+
+        let invalid = ["1,25", "1,2345", "12345,678", "1,250.5,5", "2,5"];
+        for input in invalid {
+            assert!(Expr::from_str(input).is_err(), "{input} shouldn't parse");
+        }
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        let cases = [
+            ("2,5 + 1", 3.5),
+            ("1.250 * 2", 2500.),
+            ("1.250,5", 1250.5),
+            ("1.234.567", 1_234_567.),
+            // a point that doesn't group digits is still a decimal point
+            ("1.5", 1.5),
+            // in function calls, commas separate arguments
+            ("max(2,5)", 5.),
+            ("2,5k", 2500.),
+        ];
+        for (input, expected) in cases {
+            let value = Expr::parse(input, DecimalSeparator::Comma)
+                .unwrap_or_else(|e| panic!("{input} didn't parse: {e}"))
+                .evaluate(&env)
+                .unwrap_or_else(|| panic!("{input} didn't evaluate"));
+            assert!(
+                (value.to_f64() - expected).abs() < 1e-9,
+                "{input}: {value:?}"
+            );
+        }
+
+        let format = Format {
+            precision: 10,
+            digit_grouping: true,
+            decimal_separator: DecimalSeparator::Comma,
+        };
+        let written = [
+            ("1234567.5", "1.234.567,5"),
+            ("1/4", "0,25"),
+            ("1e-20", "1e-20"),
+            ("1/3 * 1e-12", "3,3333333333e-13"),
+        ];
+        for (input, expected) in written {
+            let value = Expr::from_str(input).unwrap().evaluate(&env).unwrap();
+            assert_eq!(value.format(&format), expected, "{input}");
+        }
+    }
+
+    /// Checks that each expression's result is written as expected
+    fn assert_formats(cases: &[(&str, &str)], format: Format) {
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        for (input, expected) in cases {
+            let value = Expr::from_str(input)
+                .ok()
+                .and_then(|expr| expr.evaluate(&env))
+                .unwrap_or_else(|| panic!("{input} didn't evaluate"));
+            assert_eq!(value.format(&format), *expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn exact_arithmetic() {
+        let format = Format {
+            precision: 10,
+            digit_grouping: false,
+            decimal_separator: DecimalSeparator::Point,
+        };
+        assert_formats(
+            &[
+                ("0.1 + 0.2", "0.3"),
+                ("1/3", "0.3333333333"),
+                ("-2/3", "-0.6666666667"),
+                ("(1/3) * 3", "1"),
+                ("2^100", "1267650600228229401496703205376"),
+                ("2^-2", "0.25"),
+                ("1.5e3 * 2", "3000"),
+                ("-7.5 % 2", "-1.5"),
+                ("round(2.675, 2)", "2.68"),
+                ("max(1/3, 0.3)", "0.3333333333"),
+                ("30!", "265252859812191058636308480000000"),
+                ("~(2^100)", "-1267650600228229401496703205377"),
+                ("1e-20", "1e-20"),
+                ("sqrt(2)", "1.4142135624"),
+                ("1/3 + sqrt(4)", "2.3333333333"),
+                ("0.1 * 3 - 0.3", "0"),
+            ],
+            format,
+        );
+        assert_formats(
+            &[
+                ("1234567.891", "1,234,567.891"),
+                ("-1000000", "-1,000,000"),
+                ("2^20 + 0.5", "1,048,576.5"),
+                ("999", "999"),
+                ("1e6 + sqrt(2)", "1,000,001.4142135624"),
+            ],
+            Format {
+                digit_grouping: true,
+                ..format
+            },
+        );
+        assert_formats(
+            &[
+                ("2/3", "0.67"),
+                ("1/200", "0.01"),
+                ("1/300", "3.33e-3"),
+                ("1e-5 * pi", "3.14e-5"),
+            ],
+            Format {
+                precision: 2,
+                ..format
+            },
+        );
+
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        let fractions = [
+            ("1/3", Some("1/3")),
+            ("2/6 + 1", Some("4/3")),
+            ("0.1 + 0.2", None),
+            ("7", None),
+            ("sqrt(2)", None),
+        ];
+        for (input, expected) in fractions {
+            let value = Expr::from_str(input).unwrap().evaluate(&env).unwrap();
+            assert_eq!(value.fraction(&format).as_deref(), expected, "{input}");
+        }
+        assert!(matches!(
+            Expr::from_str("9^9^9").unwrap().evaluate(&env),
+            Some(Value::Float(_))
+        ));
+    }
+
+    #[test]
+    fn complex_numbers() {
+        let format = Format {
+            precision: 10,
+            digit_grouping: false,
+            decimal_separator: DecimalSeparator::Point,
+        };
+        assert_formats(
+            &[
+                ("sqrt(-4)", "2i"),
+                ("(1+2i)*(3-i)", "5 + 5i"),
+                ("(1+2j)*(3-j)", "5 + 5i"),
+                ("abs(3+4i)", "5"),
+                ("e^(i*pi)", "-1"),
+                ("i^2", "-1"),
+                ("(1+i)^2", "2i"),
+                ("1/i", "-i"),
+                ("(3+4i)/(1-2i)", "-1 + 2i"),
+                ("conj(3+4i)", "3 - 4i"),
+                ("re(3+4i) + im(3+4i)", "7"),
+                ("ln(-1)", "3.1415926536i"),
+                ("sqrt(-2) * sqrt(-2)", "-2"),
+                ("2i + 0.5", "0.5 + 2i"),
+                ("asin(2)", "1.5707963268 - 1.3169578969i"),
+            ],
+            format,
+        );
+        let env = Env {
+            angle_unit: AngleUnit::Degrees,
+            variables: &HashMap::new(),
+        };
+        let value = Expr::from_str("arg(1+i)").unwrap().evaluate(&env).unwrap();
+        assert_eq!(value.format(&format), "45");
+
+        // Small results keep their imaginary part
+        for (input, im) in [
+            ("1e-13i", 1e-13),
+            ("sqrt(-1e-30)", 1e-15),
+            ("2e-20 * i", 2e-20),
+        ] {
+            let value = Expr::from_str(input).unwrap().evaluate(&env);
+            let Some(Value::Complex(z)) = value else {
+                panic!("{input}: expected a complex number, got {value:?}");
+            };
+            assert_eq!(z.re, 0., "{input}");
+            assert!(
+                (z.im - im).abs() < im * 1e-12,
+                "{input}: expected {im}i, got {z:?}"
+            );
+        }
+
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        let value = Expr::from_str("3+4i").unwrap().evaluate(&env).unwrap();
+        assert_eq!(
+            value.polar(&Format {
+                precision: 4,
+                ..format
+            }),
+            Some("5 e^(0.9273i)".to_string())
+        );
+
+        // Real expressions aren't affected, and i can't be assigned to
+        assert!(matches!(
+            Expr::from_str("sqrt(4)").unwrap().evaluate(&env),
+            Some(Value::Float(_))
+        ));
+        assert!(Expr::from_str("i = 2").is_err());
+        assert!(Expr::from_str("5 % i").unwrap().evaluate(&env).is_none());
+    }
+
+    /// Checks that each expression evaluates to the expected exact integer
+    fn assert_evals_int(cases: &[(&str, i128)]) {
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        for (input, expected) in cases {
+            let expr =
+                Expr::from_str(input).unwrap_or_else(|e| panic!("{input} didn't parse: {e}"));
+            let value = expr
+                .evaluate(&env)
+                .unwrap_or_else(|| panic!("{input} didn't evaluate"));
+            assert_eq!(value.to_i128(), Some(*expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn programmer_literals_and_operators() {
+        assert_evals_int(&[
+            ("0xff", 255),
+            ("0XFF", 255),
+            ("0b1010", 10),
+            ("0o17", 15),
+            ("0xff_ff", 65535),
+            ("0b1111_0000", 240),
+            ("0xff & 0b1010", 10),
+            ("0xf0 | 0x0f", 255),
+            ("5 xor 3", 6),
+            ("~0", -1),
+            ("~0xff & 0xfff", 0xf00),
+            ("1 << 4 | 1", 17),
+            ("1 << 2 + 1", 8),
+            ("256 >> 4", 16),
+            ("1 | 2 xor 3 & 1", 3),
+            ("2^100", 1 << 100),
+            ("0xFFFFFFFFFFFFFFFF + 1", 1 << 64),
+            ("6 / 3", 2),
+            ("20!", 2_432_902_008_176_640_000),
+        ]);
+
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        for input in ["7 / 2", "0.5 + 1", "2^-1", "1 << 200", "2^200", "sqrt(4)"] {
+            let expr = Expr::from_str(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+            let value = expr.evaluate(&env).and_then(|value| value.to_i128());
+            assert_eq!(value, None, "{input} isn't an exact integer");
+        }
+        assert_evals(
+            &[("7 / 2", 3.5), ("0xff + 0.5", 255.5), ("0xff >> 0.0", 255.)],
+            AngleUnit::Radians,
+        );
+    }
+
+    #[test]
+    fn base_output() {
+        let cases = [
+            (Base::Dec, 255, "255"),
+            (Base::Hex, 255, "0xFF"),
+            (Base::Bin, 10, "0b1010"),
+            (Base::Oct, 8, "0o10"),
+            (Base::Dec, -1, "-1"),
+            (Base::Hex, -1, "0xFFFFFFFFFFFFFFFF"),
+            (Base::Hex, -(1 << 64), "0xFFFFFFFFFFFFFFFF0000000000000000"),
+            (Base::Bin, 0, "0b0"),
+        ];
+        for (base, n, expected) in cases {
+            assert_eq!(base.format(n), expected, "{n} in {}", base.name());
+        }
+
+        let suffixes = [
+            ("255 in hex", ("255", Some(Base::Hex))),
+            ("0xff to dec", ("0xff", Some(Base::Dec))),
+            ("10 as Binary ", ("10", Some(Base::Bin))),
+            ("8 in octal", ("8", Some(Base::Oct))),
+            ("in hex", ("in hex", None)),
+            ("255 in km", ("255 in km", None)),
+            ("255 hex", ("255 hex", None)),
+        ];
+        for (input, expected) in suffixes {
+            assert_eq!(split_base_suffix(input), expected, "{input}");
+        }
+
+        let bases = [
+            ("0xff + 1", Some(Base::Hex)),
+            ("0b11 * 2", Some(Base::Bin)),
+            ("0o7", Some(Base::Oct)),
+            ("1 << 3", Some(Base::Dec)),
+            ("~5", Some(Base::Dec)),
+            ("1 + 2", None),
+            ("x = 0xff", Some(Base::Hex)),
+        ];
+        for (input, expected) in bases {
+            let expr = Expr::from_str(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+            assert_eq!(expr.programmer_base(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn statistics() {
+        let format = Format {
+            precision: 10,
+            digit_grouping: false,
+            decimal_separator: DecimalSeparator::Point,
+        };
+        assert_formats(
+            &[
+                ("sum(1, 2, 3.5)", "6.5"),
+                ("avg(1, 2, 4)", "2.3333333333"),
+                ("mean(0.1, 0.2)", "0.15"),
+                ("median(3, 1, 2)", "2"),
+                ("median(4, 1, 3, 2)", "2.5"),
+                ("mode(1, 2, 2, 3, 3)", "2"),
+                ("mode(7)", "7"),
+                ("var(2, 4, 4, 4, 5, 5, 7, 9)", "4.5714285714"),
+                ("gcd(12, 18, 27)", "3"),
+                ("lcm(4, 6, 10)", "60"),
+                ("ncr(5, 2)", "10"),
+                ("ncr(52, 5)", "2598960"),
+                ("ncr(100, 98)", "4950"),
+                ("npr(5, 2)", "20"),
+                ("npr(5, 0)", "1"),
+                ("ncr(60, 30)", "118264581564861424"),
+            ],
+            format,
+        );
+        assert_evals(
+            &[
+                (
+                    "stdev(2, 4, 4, 4, 5, 5, 7, 9)",
+                    4.571_428_571_428_571_f64.sqrt(),
+                ),
+                ("sum(sqrt(2), sqrt(2))", 2. * 2f64.sqrt()),
+                ("max(1, 5, 3)", 5.),
+                ("min(1, 5, 3)", 1.),
+            ],
+            AngleUnit::Radians,
+        );
+
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        let undefined = [
+            "mode(1, 2, 3)",
+            "var(1)",
+            "stdev(1)",
+            "sum()",
+            "gcd(1.5, 3)",
+            "ncr(2, 3)",
+            "ncr(5, -1)",
+            "npr(5.5, 2)",
+        ];
+        for input in undefined {
+            let expr = Expr::from_str(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+            assert_eq!(expr.evaluate(&env), None, "{input} shouldn't evaluate");
+        }
+    }
+
+    #[test]
+    fn columns() {
+        let columns = [
+            ("1 2 3", Some(3)),
+            ("1.5\n-2\n3e2\n", Some(3)),
+            ("  4   5 ", Some(2)),
+            ("0xff 10", Some(2)),
+            ("42", None),
+            ("1 2 x", None),
+            ("1 + 2", None),
+            ("", None),
+        ];
+        for (input, expected) in columns {
+            let column = parse_column(input, DecimalSeparator::Point);
+            assert_eq!(column.map(|numbers| numbers.len()), expected, "{input}");
+        }
+        assert_eq!(
+            parse_column("1,5 2,5", DecimalSeparator::Comma),
+            Some(vec![
+                Expr::from_str("1.5").unwrap(),
+                Expr::from_str("2.5").unwrap()
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let invalid = [
+            "2 +",
+            "(1 + 2",
+            "2 3",
+            "1 $ 2",
+            "%3",
+            "pi = 3",
+            "ans = 2",
+            "2x = 1",
+            "x = ",
+            "x = 1 = 2",
+        ];
+        for input in invalid {
+            assert!(Expr::from_str(input).is_err(), "{input} shouldn't parse");
+        }
+
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &HashMap::new(),
+        };
+        let undefined = [
+            "foo",
+            "5 km",
+            "ans",
+            "foo(1)",
+            "sqrt(1, 2)",
+            "round()",
+            "min()",
+            "(-1)!",
+            "2.5!",
+            "2.5 & 3",
+            "1 << 0.5",
+        ];
+        for input in undefined {
+            let expr = Expr::from_str(input).unwrap_or_else(|e| panic!("{input}: {e}"));
+            assert_eq!(expr.eval(&env), None, "{input} shouldn't evaluate");
+        }
+    }
+
+    /// Where in the expression an error is, if it's somewhere in particular
+    fn span(error: &CalcError) -> Option<Range<usize>> {
+        match error {
+            CalcError::Unexpected { span, .. }
+            | CalcError::InvalidNumber { span, .. }
+            | CalcError::UnknownFunction { span, .. } => Some(span.clone()),
+            CalcError::UnknownVariable { span, .. } | CalcError::WrongArity { span, .. } => {
+                span.clone()
+            }
+            CalcError::Incomplete
+            | CalcError::InvalidAssignment(_)
+            | CalcError::Domain(_)
+            | CalcError::IncompatibleUnits { .. } => None,
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let variables = HashMap::from([("x".to_string(), Value::Float(2.))]);
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &variables,
+        };
+        let errors = [
+            ("2 +", "Incomplete expression", None),
+            ("(1 + 2", "Incomplete expression", None),
+            ("1 $ 2", "Unexpected $ at column 3", Some(2..3)),
+            ("2 3", "Unexpected 3 at column 3", Some(2..3)),
+            ("y = 1 + )", "Unexpected ) at column 9", Some(8..9)),
+            ("0xfg + 1", "Invalid number 0xfg at column 1", Some(0..4)),
+            ("pi = 3", "Can't assign to pi", None),
+            ("2 * sni(x)", "Unknown function sni", Some(4..7)),
+            ("foo + 1", "Unknown variable foo", Some(0..3)),
+            ("sqrt(1, 2)", "sqrt takes 1 argument, not 2", Some(0..4)),
+            ("log()", "log takes 1 or 2 arguments, not 0", Some(0..3)),
+            (
+                "stdev(1)",
+                "stdev takes at least 2 arguments, not 1",
+                Some(0..5),
+            ),
+            ("1 + 0/0", "Division by zero", None),
+            ("1/0", "Division by zero", None),
+            ("2 * (1.5 / 0) + 1", "Division by zero", None),
+            ("sqrt(2)^5000", "The result is too big", None),
+            ("171!", "The result is too big", None),
+            ("ln(0)", "ln is undefined for these arguments", None),
+            ("gcd(1.5, 2)", "gcd is undefined for these arguments", None),
+            ("ncr(2, -1)", "ncr is undefined for these arguments", None),
+            (
+                "(-1)!",
+                "Factorials are only defined for whole numbers that aren't negative",
+                None,
+            ),
+            ("2.5 & 3", "Bitwise operators only work on integers", None),
+        ];
+        for (input, message, expected_span) in errors {
+            let error = calculate(input, DecimalSeparator::Point, &env)
+                .err()
+                .unwrap_or_else(|| panic!("{input} shouldn't calculate"));
+            assert_eq!(error.to_string(), message, "{input}");
+            assert_eq!(span(&error), expected_span, "{input}");
+        }
+
+        let suggestion = |input| match calculate(input, DecimalSeparator::Point, &env) {
+            Err(CalcError::UnknownFunction { suggestion, .. }) => suggestion,
+            result => panic!("{input}: {result:?}"),
+        };
+        assert_eq!(suggestion("sni(1)"), Some("sin"));
+        assert_eq!(suggestion("Sqrt(4)"), Some("sqrt"));
+        assert_eq!(suggestion("meidan(1, 2)"), Some("median"));
+        assert_eq!(suggestion("frobnicate(1)"), None);
+    }
+
+    #[test]
+    fn math_queries() {
+        let variables = HashMap::from([("x".to_string(), Value::Float(2.))]);
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &variables,
+        };
+        let math = [
+            "2 +", "sqrt(", "sni(4)", "3x + 1", "1 $ 2", "y = 2 *", "(1 + 2",
+        ];
+        let not_math = [
+            "firefox",
+            "2 apples",
+            "hello + world",
+            "42",
+            "rust 2024 book",
+            "",
+        ];
+        for query in math {
+            assert!(looks_like_math(query, &env), "{query} looks like math");
+        }
+        for query in not_math {
+            assert!(
+                !looks_like_math(query, &env),
+                "{query} doesn't look like math"
+            );
         }
     }
 }
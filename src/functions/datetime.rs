@@ -0,0 +1,938 @@
+//! Dates, times and time zones: `now + 3 days 4h`, `days until 2026-12-25`,
+//! `2026-03-01 - 2025-11-15`, `3pm PST in Berlin`, `time in Tokyo`, `week number`, Unix
+//! timestamps (`1735689600`, `2025-01-01 to unix`), and the times of the world clock
+//! (`10:00 everywhere`)
+//!
+//! Time zones are looked up in the tz database bundled with `chrono-tz`, so that they work
+//! offline. They can be written as IANA names (`Europe/Berlin`), cities (`Berlin`, `New York`),
+//! abbreviations (`PST`, `CET`), or offsets from UTC (`UTC+2`).
+use std::{borrow::Cow, sync::LazyLock};
+
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    SecondsFormat, TimeDelta, TimeZone, Timelike, Utc,
+};
+use chrono_tz::{TZ_VARIANTS, Tz};
+use regex::Regex;
+
+/// Time zone abbreviations and their offsets from UTC, in minutes. They're always these offsets,
+/// so `PST` is UTC-8 in the summer too.
+const ABBREVIATIONS: [(&str, i32); 28] = [
+    ("HST", -600),
+    ("AKST", -540),
+    ("AKDT", -480),
+    ("PST", -480),
+    ("PDT", -420),
+    ("MST", -420),
+    ("MDT", -360),
+    ("CST", -360),
+    ("CDT", -300),
+    ("EST", -300),
+    ("EDT", -240),
+    ("BRT", -180),
+    ("WET", 0),
+    ("WEST", 60),
+    ("BST", 60),
+    ("CET", 60),
+    ("CEST", 120),
+    ("EET", 120),
+    ("EEST", 180),
+    ("MSK", 180),
+    ("IST", 330),
+    ("SGT", 480),
+    ("HKT", 480),
+    ("JST", 540),
+    ("KST", 540),
+    ("AEST", 600),
+    ("AEDT", 660),
+    ("NZST", 720),
+];
+
+/// Places that aren't the city of a time zone's IANA name, and the time zones they're in
+const PLACES: [(&str, &str); 16] = [
+    ("san francisco", "America/Los_Angeles"),
+    ("seattle", "America/Los_Angeles"),
+    ("california", "America/Los_Angeles"),
+    ("washington", "America/New_York"),
+    ("boston", "America/New_York"),
+    ("miami", "America/New_York"),
+    ("texas", "America/Chicago"),
+    ("beijing", "Asia/Shanghai"),
+    ("china", "Asia/Shanghai"),
+    ("mumbai", "Asia/Kolkata"),
+    ("delhi", "Asia/Kolkata"),
+    ("bangalore", "Asia/Kolkata"),
+    ("india", "Asia/Kolkata"),
+    ("munich", "Europe/Berlin"),
+    ("frankfurt", "Europe/Berlin"),
+    ("osaka", "Asia/Tokyo"),
+];
+
+/// The units durations (`3 days 4h`) and counts (`days until`) can be in, by what they're called
+const UNITS: [(&[&str], Step); 7] = [
+    (&["s", "sec", "secs", "second", "seconds"], Step::Seconds(1)),
+    (
+        &["m", "min", "mins", "minute", "minutes"],
+        Step::Seconds(60),
+    ),
+    (&["h", "hr", "hrs", "hour", "hours"], Step::Seconds(3600)),
+    (&["d", "day", "days"], Step::Days(1)),
+    (&["w", "wk", "wks", "week", "weeks"], Step::Days(7)),
+    (&["mo", "month", "months"], Step::Months(1)),
+    (&["y", "yr", "yrs", "year", "years"], Step::Months(12)),
+];
+
+/// The last year a plain number is read as a Unix timestamp in. Every 10 or 13 digit timestamp is
+/// after 2001, so this keeps numbers like `9999999999` from being read as dates in the far future
+const LAST_TIMESTAMP_YEAR: i32 = 2100;
+
+/// A count followed by a unit, like `3 days` or `4h`
+static DURATION_PART: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(\d+)\s*([a-z]+)").unwrap());
+
+/// What a query is asked relative to: the current time, the local time zone, and the time zones
+/// of the world clock (as they're written in the config)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock<'a> {
+    pub now: DateTime<Utc>,
+    pub local: Zone,
+    pub world_clock: &'a [String],
+}
+
+impl Clock<'_> {
+    /// The current time in the system's time zone
+    pub fn system(world_clock: &[String]) -> Clock<'_> {
+        Clock {
+            now: Utc::now(),
+            local: Zone::Local,
+            world_clock,
+        }
+    }
+}
+
+/// A time zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    /// The system's time zone
+    Local,
+    /// A time zone of the tz database, with its daylight saving time
+    Named(Tz),
+    /// A fixed offset from UTC, with the abbreviation it was written as
+    Fixed(&'static str, FixedOffset),
+}
+
+impl Zone {
+    /// Parses a time zone's IANA name, city, abbreviation or offset from UTC (`UTC+5:30`)
+    pub fn parse(text: &str) -> Option<Zone> {
+        let text = text.trim();
+        let lowercase = text.to_lowercase();
+        let utc = FixedOffset::east_opt(0)?;
+        if matches!(lowercase.as_str(), "utc" | "gmt" | "z") {
+            return Some(Zone::Fixed("UTC", utc));
+        } else if matches!(lowercase.as_str(), "local" | "here") {
+            return Some(Zone::Local);
+        }
+        if let Some(offset) = lowercase
+            .strip_prefix("utc")
+            .or_else(|| lowercase.strip_prefix("gmt"))
+            .and_then(parse_offset)
+        {
+            return Some(Zone::Fixed("UTC", offset));
+        }
+        if let Some((abbreviation, minutes)) = ABBREVIATIONS
+            .iter()
+            .find(|(abbreviation, _)| abbreviation.eq_ignore_ascii_case(text))
+        {
+            return Some(Zone::Fixed(
+                abbreviation,
+                FixedOffset::east_opt(minutes * 60)?,
+            ));
+        }
+        if let Some((_, name)) = PLACES.iter().find(|(place, _)| *place == lowercase) {
+            return name.parse().ok().map(Zone::Named);
+        }
+        TZ_VARIANTS
+            .iter()
+            .find(|tz| {
+                tz.name().eq_ignore_ascii_case(text) || city(**tz).eq_ignore_ascii_case(&lowercase)
+            })
+            .copied()
+            .map(Zone::Named)
+    }
+
+    /// The zone's offset from UTC at a moment
+    fn offset(self, moment: DateTime<Utc>) -> FixedOffset {
+        let naive = moment.naive_utc();
+        match self {
+            Zone::Local => Local.offset_from_utc_datetime(&naive),
+            Zone::Named(tz) => tz.offset_from_utc_datetime(&naive).fix(),
+            Zone::Fixed(_, offset) => offset,
+        }
+    }
+
+    /// A moment as it is in this zone
+    fn at(self, moment: DateTime<Utc>) -> DateTime<FixedOffset> {
+        moment.with_timezone(&self.offset(moment))
+    }
+
+    /// The moment a local date and time in this zone is. Times that are skipped when the clocks
+    /// go forward are taken to be an hour later, and ones that happen twice the first time.
+    fn moment(self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let find = |local: NaiveDateTime| match self {
+            Zone::Local => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|t| t.to_utc()),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|t| t.to_utc()),
+            Zone::Fixed(_, offset) => offset
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|t| t.to_utc()),
+        };
+        find(local).or_else(|| find(local + TimeDelta::hours(1)))
+    }
+
+    /// What the zone is called in results: its city, abbreviation or offset, or nothing for the
+    /// local time zone
+    fn place(self) -> Option<String> {
+        match self {
+            Zone::Local => None,
+            Zone::Named(tz) => Some(city(tz)),
+            Zone::Fixed("UTC", offset) if offset.local_minus_utc() != 0 => {
+                Some(format!("UTC{offset}"))
+            }
+            Zone::Fixed(abbreviation, _) => Some(abbreviation.to_string()),
+        }
+    }
+}
+
+/// A row of the answer to a query: the text that's copied, and what it is
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRow {
+    pub text: String,
+    pub desc: String,
+}
+
+impl DateRow {
+    fn new(text: impl Into<String>, desc: impl Into<String>) -> DateRow {
+        DateRow {
+            text: text.into(),
+            desc: desc.into(),
+        }
+    }
+}
+
+/// A change to a moment: a number of seconds, of calendar days, or of calendar months
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Seconds(i64),
+    Days(i64),
+    Months(i64),
+}
+
+impl Step {
+    /// This step `count` times
+    fn times(self, count: i64) -> Option<Step> {
+        Some(match self {
+            Step::Seconds(n) => Step::Seconds(n.checked_mul(count)?),
+            Step::Days(n) => Step::Days(n.checked_mul(count)?),
+            Step::Months(n) => Step::Months(n.checked_mul(count)?),
+        })
+    }
+
+    /// Moves a moment by this step, keeping its local time in `zone` for days and months
+    fn apply(self, moment: DateTime<Utc>, zone: Zone) -> Option<DateTime<Utc>> {
+        let local = zone.at(moment).naive_local();
+        match self {
+            Step::Seconds(n) => moment.checked_add_signed(TimeDelta::try_seconds(n)?),
+            Step::Days(n) => zone.moment(local.checked_add_signed(TimeDelta::try_days(n)?)?),
+            Step::Months(n) if n < 0 => zone
+                .moment(local.checked_sub_months(Months::new(n.unsigned_abs().try_into().ok()?))?),
+            Step::Months(n) => {
+                zone.moment(local.checked_add_months(Months::new(n.try_into().ok()?))?)
+            }
+        }
+    }
+}
+
+/// The answer to a date or time query, in rows that can each be copied, or [`None`] if the query
+/// isn't about dates or times
+pub fn date_results(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    week_number(&query, clock)
+        .or_else(|| world_times(&query, clock))
+        .or_else(|| count_until(&query, clock))
+        .or_else(|| difference(&query, clock))
+        .or_else(|| moment(&query, clock))
+}
+
+/// `week number`, or `week number of <date>`
+fn week_number(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let rest = query
+        .strip_prefix("week number")
+        .or_else(|| query.strip_prefix("week no"))?
+        .trim();
+    let rest = rest.strip_prefix("of ").unwrap_or(rest).trim();
+    let (moment, zone) = if rest.is_empty() {
+        (clock.now, clock.local)
+    } else {
+        parse_expression(rest, clock)?
+    };
+
+    let date = zone.at(moment).date_naive();
+    let week = date.iso_week();
+    Some(vec![
+        DateRow::new(
+            format!("Week {}", week.week()),
+            format!("Week number of {}", date.format("%A, %-d %B %Y")),
+        ),
+        DateRow::new(
+            format!(
+                "{}-W{:02}-{}",
+                week.year(),
+                week.week(),
+                date.weekday().number_from_monday()
+            ),
+            "ISO week date",
+        ),
+    ])
+}
+
+/// `<moment> everywhere` or `<moment> in world clock`: the moment in each time zone of the world
+/// clock
+fn world_times(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let expression = query.strip_suffix(" everywhere").or_else(|| {
+        let (expression, target) = query.rsplit_once(" in ")?;
+        matches!(target, "world clock" | "clocks" | "my clocks").then_some(expression)
+    })?;
+    if clock.world_clock.is_empty() {
+        return None;
+    }
+    let (moment, _) = parse_expression(expression, clock)?;
+    Some(clock_rows(moment, clock))
+}
+
+/// A moment in each time zone of the world clock, with its date, UTC offset, whether it's day or
+/// night there, and how far ahead of or behind the local time zone it is
+pub fn clock_rows(moment: DateTime<Utc>, clock: &Clock) -> Vec<DateRow> {
+    let here = clock.local.offset(moment).local_minus_utc();
+    clock
+        .world_clock
+        .iter()
+        .map(|name| {
+            let Some(zone) = Zone::parse(name) else {
+                return DateRow::new(name.as_str(), "Unknown time zone");
+            };
+            let local = zone.at(moment);
+            let place = zone.place().unwrap_or_else(|| "Local time".to_string());
+            let day = if (6..18).contains(&local.hour()) {
+                "day"
+            } else {
+                "night"
+            };
+            let ahead = local.offset().local_minus_utc() - here;
+            let relative = match ahead {
+                0 => "same time as here".to_string(),
+                ahead if ahead > 0 => {
+                    format!("{} ahead", spell(TimeDelta::seconds(ahead.into()), 2))
+                }
+                ahead => format!("{} behind", spell(TimeDelta::seconds((-ahead).into()), 2)),
+            };
+            DateRow::new(
+                format!("{} in {place}", local.format("%H:%M")),
+                format!(
+                    "{}, UTC{}, {day}, {relative}",
+                    local.format("%A, %-d %B"),
+                    local.offset()
+                ),
+            )
+        })
+        .collect()
+}
+
+/// `days until <date>` or `hours since <date>`, in any unit
+fn count_until(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let (unit, rest) = query.split_once(' ')?;
+    let (since, rest) = if let Some(rest) = rest.strip_prefix("until ") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix("till ") {
+        (false, rest)
+    } else {
+        (true, rest.strip_prefix("since ")?)
+    };
+    let (names, step) = UNITS.iter().find(|(names, _)| names.contains(&unit))?;
+    let (moment, zone) = parse_expression(rest, clock)?;
+
+    let (from, to) = if since {
+        (moment, clock.now)
+    } else {
+        (clock.now, moment)
+    };
+    let local = |moment| zone.at(moment).naive_local();
+    let count = match step {
+        Step::Seconds(seconds) => (to - from).num_seconds() / seconds,
+        // Calendar days, so that tomorrow is a day away even if it's less than 24 hours away
+        Step::Days(days) => (local(to).date() - local(from).date()).num_days() / days,
+        Step::Months(months) => months_between(local(from), local(to)) / months,
+    };
+
+    let name = names.last()?;
+    let desc = format!(
+        "{}{} {} {}",
+        name[..1].to_uppercase(),
+        &name[1..],
+        if since { "since" } else { "until" },
+        zone.at(moment).format("%A, %-d %B %Y, %H:%M")
+    );
+    let mut rows = vec![DateRow::new(plural(count, &name[..name.len() - 1]), desc)];
+    if let Some(relative) = relative(moment, clock) {
+        rows.push(DateRow::new(relative, "Relative to now"));
+    }
+    Some(rows)
+}
+
+/// `<date> - <date>`, in days, weeks, months and hours
+fn difference(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let (lhs, rhs) = query.rsplit_once(" - ")?;
+    let (to, zone) = parse_expression(lhs, clock)?;
+    let (from, _) = parse_base(rhs, clock)?;
+
+    let sign = if to < from { "-" } else { "" };
+    let (from, to) = (from.min(to), from.max(to));
+    let difference = to - from;
+    let days = difference.num_days();
+    let mut rows = vec![DateRow::new(
+        if difference == TimeDelta::days(days) {
+            format!("{sign}{}", plural(days, "day"))
+        } else {
+            format!("{sign}{}", spell(difference, usize::MAX))
+        },
+        "Difference",
+    )];
+    if days >= 7 {
+        rows.push(DateRow::new(
+            format!("{sign}{}", join(&[(days / 7, "week"), (days % 7, "day")])),
+            "Difference in weeks",
+        ));
+    }
+    let (from_local, to_local) = (zone.at(from).naive_local(), zone.at(to).naive_local());
+    let months = months_between(from_local, to_local);
+    if months > 0 {
+        let rest =
+            to_local - from_local.checked_add_months(Months::new(months.try_into().ok()?))?;
+        rows.push(DateRow::new(
+            format!(
+                "{sign}{}",
+                join(&[
+                    (months / 12, "year"),
+                    (months % 12, "month"),
+                    (rest.num_days(), "day")
+                ])
+            ),
+            "Difference in months",
+        ));
+    }
+    rows.push(DateRow::new(
+        format!("{sign}{}", plural(difference.num_hours(), "hour")),
+        "Difference in hours",
+    ));
+    Some(rows)
+}
+
+/// A moment, optionally followed by `in <zone>`, `to unix`, or `to date` (for a timestamp)
+fn moment(query: &str, clock: &Clock) -> Option<Vec<DateRow>> {
+    let (expression, target, to_unix) = match query
+        .rsplit_once(" in ")
+        .or_else(|| query.rsplit_once(" to "))
+    {
+        Some((expression, "unix")) => (Cow::Borrowed(expression), None, true),
+        Some((expression, "date")) => (Cow::Owned(format!("@{}", expression.trim())), None, false),
+        Some((expression, zone)) => (Cow::Borrowed(expression), Some(Zone::parse(zone)?), false),
+        None => (Cow::Borrowed(query), None, false),
+    };
+    let (moment, _) = match expression.as_ref() {
+        "time" => (clock.now, clock.local),
+        expression => parse_expression(expression, clock)?,
+    };
+
+    if to_unix {
+        return Some(vec![
+            DateRow::new(moment.timestamp().to_string(), "Unix timestamp"),
+            DateRow::new(
+                moment.timestamp_millis().to_string(),
+                "Unix timestamp in milliseconds",
+            ),
+        ]);
+    }
+
+    let local = target.unwrap_or(clock.local).at(moment);
+    let readable = local.format("%A, %-d %B %Y, %H:%M").to_string();
+    let mut rows = match target.and_then(Zone::place) {
+        Some(place) => vec![DateRow::new(
+            format!("{readable} ({place})"),
+            format!("Time in {place}"),
+        )],
+        None => vec![DateRow::new(readable, "Date and time")],
+    };
+    rows.push(DateRow::new(
+        local.to_rfc3339_opts(SecondsFormat::Secs, true),
+        "ISO 8601",
+    ));
+    rows.push(DateRow::new(local.to_rfc2822(), "RFC 2822"));
+    if let Some(relative) = relative(moment, clock) {
+        rows.push(DateRow::new(relative, "Relative to now"));
+    }
+    Some(rows)
+}
+
+/// Parses a moment with durations added to or subtracted from it (`now + 3 days 4h - 30 min`),
+/// and returns it with the time zone it was written in
+fn parse_expression(text: &str, clock: &Clock) -> Option<(DateTime<Utc>, Zone)> {
+    let mut terms = text.split(" + ").flat_map(|term| {
+        let mut parts = term.split(" - ");
+        let first = parts.next().map(|part| (1, part));
+        first.into_iter().chain(parts.map(|part| (-1, part)))
+    });
+    let (_, base) = terms.next()?;
+    let (mut moment, zone) = parse_base(base, clock)?;
+    for (sign, term) in terms {
+        for step in parse_duration(term)? {
+            moment = step.times(sign)?.apply(moment, zone)?;
+        }
+    }
+    Some((moment, zone))
+}
+
+/// Parses a Unix timestamp, which is in milliseconds if it has 13 digits
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let number: i64 = text.parse().ok()?;
+    match text.len() {
+        ..13 => DateTime::from_timestamp(number, 0),
+        13 => DateTime::from_timestamp_millis(number),
+        _ => None,
+    }
+}
+
+/// Parses a duration like `3 days 4h` or `1 week, 2 days`
+fn parse_duration(text: &str) -> Option<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let captures = DURATION_PART.captures(rest)?;
+        let count: i64 = captures[1].parse().ok()?;
+        let (_, step) = UNITS
+            .iter()
+            .find(|(names, _)| names.contains(&&captures[2]))?;
+        steps.push(step.times(count)?);
+        rest = rest[captures.get(0)?.end()..].trim_start_matches([' ', ',']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+    }
+    (!steps.is_empty()).then_some(steps)
+}
+
+/// Parses a moment: `now`, `today`, `tomorrow` or `yesterday`, a Unix timestamp (`1735689600`,
+/// `@1735689600` or `unix 1735689600`), an RFC 3339 date and time, or a date and / or a time with
+/// an optional time zone (`2026-03-01 15:00 Berlin`, `3pm PST`), and returns it with the time zone
+/// it was written in
+///
+/// A number on its own is only a timestamp if it has 10 or 13 digits and isn't after
+/// [`LAST_TIMESTAMP_YEAR`], since it's just as likely to be a plain number.
+fn parse_base(text: &str, clock: &Clock) -> Option<(DateTime<Utc>, Zone)> {
+    let text = text.trim();
+    if text == "now" {
+        return Some((clock.now, clock.local));
+    }
+    if let Some(timestamp) = text
+        .strip_prefix('@')
+        .or_else(|| text.strip_prefix("unix "))
+    {
+        return Some((parse_timestamp(timestamp.trim())?, clock.local));
+    }
+    if matches!(text.len(), 10 | 13) && text.chars().all(|c| c.is_ascii_digit()) {
+        let moment = parse_timestamp(text).filter(|moment| moment.year() <= LAST_TIMESTAMP_YEAR)?;
+        return Some((moment, clock.local));
+    }
+    if let Ok(moment) = DateTime::parse_from_rfc3339(&text.to_uppercase()) {
+        let offset = *moment.offset();
+        return Some((moment.to_utc(), Zone::Fixed("UTC", offset)));
+    }
+
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut i = 0;
+    let date = tokens.first().and_then(|token| parse_date(token));
+    if date.is_some() {
+        i += 1;
+    }
+    let time = tokens
+        .get(i)
+        .and_then(|token| parse_time(token, tokens.get(i + 1).copied()));
+    if let Some((_, used)) = time {
+        i += used;
+    }
+    if date.is_none() && time.is_none() {
+        return None;
+    }
+    let zone = match &tokens[i..] {
+        [] => clock.local,
+        zone => Zone::parse(&zone.join(" "))?,
+    };
+
+    let today = zone.at(clock.now).date_naive();
+    let date = match date.unwrap_or(DateSpec::Relative(0)) {
+        DateSpec::Date(date) => date,
+        DateSpec::Relative(days) => today.checked_add_signed(TimeDelta::days(days))?,
+    };
+    let time = time.map_or(NaiveTime::MIN, |(time, _)| time);
+    Some((zone.moment(date.and_time(time))?, zone))
+}
+
+/// A date, or a number of days from today
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateSpec {
+    Date(NaiveDate),
+    Relative(i64),
+}
+
+/// Parses `today`, `tomorrow`, `yesterday`, `2026-03-01`, `2026/03/01` or `01.03.2026`
+fn parse_date(token: &str) -> Option<DateSpec> {
+    match token {
+        "today" => Some(DateSpec::Relative(0)),
+        "tomorrow" => Some(DateSpec::Relative(1)),
+        "yesterday" => Some(DateSpec::Relative(-1)),
+        _ => ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(token, format).ok())
+            .map(DateSpec::Date),
+    }
+}
+
+/// Parses a time (`15:00`, `15:00:30`, `3pm`, `3:30 pm`, `noon` or `midnight`), and returns it
+/// with how many tokens it took up
+fn parse_time(token: &str, next: Option<&str>) -> Option<(NaiveTime, usize)> {
+    match token {
+        "noon" => return Some((NaiveTime::from_hms_opt(12, 0, 0)?, 1)),
+        "midnight" => return Some((NaiveTime::MIN, 1)),
+        _ => {}
+    }
+
+    let (clock, meridiem, used) = match (token.strip_suffix("am"), token.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(0), 1),
+        (_, Some(clock)) => (clock, Some(12), 1),
+        _ => match next {
+            Some("am") => (token, Some(0), 2),
+            Some("pm") => (token, Some(12), 2),
+            _ => (token, None, 1),
+        },
+    };
+    let (hours, minutes) = clock.split_once(':').unwrap_or((clock, "00"));
+    let hours: u32 = hours.parse().ok()?;
+    let time = match meridiem {
+        Some(meridiem) if (1..=12).contains(&hours) => {
+            NaiveTime::parse_from_str(&format!("{}:{minutes}", hours % 12 + meridiem), "%H:%M")
+                .ok()?
+        }
+        Some(_) => return None,
+        None if clock.contains(':') => NaiveTime::parse_from_str(clock, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(clock, "%H:%M"))
+            .ok()?,
+        None => return None,
+    };
+    Some((time, used))
+}
+
+/// Parses an offset from UTC after `UTC` or `GMT`: `+2`, `-5`, `+5:30` or `+05:30`
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match text.trim().split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The city of a time zone's IANA name (`New York` for `America/New_York`)
+fn city(tz: Tz) -> String {
+    tz.name()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .replace('_', " ")
+}
+
+/// The whole calendar months from one local date and time to a later one
+fn months_between(from: NaiveDateTime, to: NaiveDateTime) -> i64 {
+    let months =
+        i64::from(to.year() - from.year()) * 12 + i64::from(to.month()) - i64::from(from.month());
+    let reached = |months: i64| {
+        u32::try_from(months)
+            .ok()
+            .and_then(|months| from.checked_add_months(Months::new(months)))
+            .is_some_and(|moment| moment <= to)
+    };
+    if months > 0 && !reached(months) {
+        months - 1
+    } else {
+        months.max(0)
+    }
+}
+
+/// How long from now a moment is (`in 3 days, 4 hours` or `2 hours ago`), unless it's within a
+/// minute of now
+fn relative(moment: DateTime<Utc>, clock: &Clock) -> Option<String> {
+    let difference = moment - clock.now;
+    if difference.num_minutes() == 0 {
+        None
+    } else if difference > TimeDelta::zero() {
+        Some(format!("in {}", spell(difference, 2)))
+    } else {
+        Some(format!("{} ago", spell(-difference, 2)))
+    }
+}
+
+/// A duration in years (of 365 days), days, hours and minutes, leaving out the ones that are 0 and
+/// keeping the `parts` largest ones
+fn spell(duration: TimeDelta, parts: usize) -> String {
+    let minutes = duration.num_minutes();
+    let counts = [
+        (minutes / (365 * 24 * 60), "year"),
+        (minutes / (24 * 60) % 365, "day"),
+        (minutes / 60 % 24, "hour"),
+        (minutes % 60, "minute"),
+    ];
+    let counts: Vec<(i64, &str)> = counts
+        .into_iter()
+        .filter(|(count, _)| *count != 0)
+        .take(parts)
+        .collect();
+    join(&counts)
+}
+
+/// Counts of units, leaving out the ones that are 0 (`1 week, 2 days`)
+fn join(counts: &[(i64, &str)]) -> String {
+    let parts: Vec<String> = counts
+        .iter()
+        .filter(|(count, _)| *count != 0)
+        .map(|(count, unit)| plural(*count, unit))
+        .collect();
+    if parts.is_empty() {
+        "0 minutes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// A count of a unit, with the unit in the plural unless there's one of it
+fn plural(count: i64, unit: &str) -> String {
+    if count.abs() == 1 {
+        format!("{count} {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sunday, 18 October 2026, 12:00 UTC, with UTC as the local time zone
+    fn clock() -> Clock<'static> {
+        Clock {
+            now: Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap(),
+            local: Zone::Named(Tz::UTC),
+            world_clock: &[],
+        }
+    }
+
+    fn answer(query: &str) -> Vec<String> {
+        date_results(query, &clock())
+            .unwrap_or_else(|| panic!("{query} has no answer"))
+            .into_iter()
+            .map(|row| row.text)
+            .collect()
+    }
+
+    #[test]
+    fn moments() {
+        assert_eq!(
+            answer("now + 3 days 4h"),
+            [
+                "Wednesday, 21 October 2026, 16:00",
+                "2026-10-21T16:00:00Z",
+                "Wed, 21 Oct 2026 16:00:00 +0000",
+                "in 3 days, 4 hours",
+            ]
+        );
+        assert_eq!(
+            answer("today - 1 week")[0],
+            "Sunday, 11 October 2026, 00:00"
+        );
+        assert_eq!(
+            answer("2026-01-31 + 1 month")[0],
+            "Saturday, 28 February 2026, 00:00"
+        );
+        assert_eq!(answer("1735689600")[1], "2025-01-01T00:00:00Z");
+        assert_eq!(answer("1735689600000")[1], "2025-01-01T00:00:00Z");
+        assert_eq!(answer("@1735689600")[1], "2025-01-01T00:00:00Z");
+        assert_eq!(answer("unix 1735689600000")[1], "2025-01-01T00:00:00Z");
+        assert_eq!(answer("1735689600 to date")[1], "2025-01-01T00:00:00Z");
+        assert_eq!(answer("@0 + 1 day")[1], "1970-01-02T00:00:00Z");
+        assert_eq!(
+            answer("2025-01-01 to unix"),
+            ["1735689600", "1735689600000"]
+        );
+        assert_eq!(answer("tomorrow noon")[3], "in 1 day");
+        assert_eq!(answer("2026-10-18 10:00")[3], "2 hours ago");
+    }
+
+    #[test]
+    fn time_zones() {
+        // 3pm PST is 23:00 UTC, which is 1am the next day in Berlin (still on summer time)
+        assert_eq!(
+            answer("3pm PST in Berlin")[..2],
+            [
+                "Monday, 19 October 2026, 01:00 (Berlin)",
+                "2026-10-19T01:00:00+02:00"
+            ]
+        );
+        assert_eq!(
+            answer("time in Tokyo")[0],
+            "Sunday, 18 October 2026, 21:00 (Tokyo)"
+        );
+        assert_eq!(
+            answer("2026-12-25 9:30 am new york to utc+5:30")[1],
+            "2026-12-25T20:00:00+05:30"
+        );
+        assert_eq!(
+            answer("15:00 Europe/London in UTC")[1],
+            "2026-10-18T14:00:00Z"
+        );
+        assert_eq!(answer("2026-03-29 02:30 berlin")[1], "2026-03-29T01:30:00Z");
+
+        for zone in [
+            "America/Los_Angeles",
+            "san francisco",
+            "pdt",
+            "GMT-7",
+            "Sao Paulo",
+        ] {
+            assert!(Zone::parse(zone).is_some(), "{zone}");
+        }
+        assert!(Zone::parse("atlantis").is_none());
+    }
+
+    #[test]
+    fn differences() {
+        assert_eq!(
+            answer("2026-03-01 - 2025-11-15"),
+            [
+                "106 days",
+                "15 weeks, 1 day",
+                "3 months, 14 days",
+                "2544 hours"
+            ]
+        );
+        assert_eq!(answer("2025-11-15 - 2026-03-01")[0], "-106 days");
+        assert_eq!(
+            answer("days until 2026-12-25"),
+            ["68 days", "in 67 days, 12 hours"]
+        );
+        assert_eq!(answer("weeks until 2026-12-25")[0], "9 weeks");
+        assert_eq!(answer("hours since 2026-10-17")[0], "36 hours");
+        assert_eq!(answer("months since 2025-12-25")[0], "9 months");
+    }
+
+    #[test]
+    fn week_numbers() {
+        assert_eq!(answer("week number"), ["Week 42", "2026-W42-7"]);
+        assert_eq!(
+            answer("week number of 2027-01-01"),
+            ["Week 53", "2026-W53-5"]
+        );
+    }
+
+    #[test]
+    fn world_clock() {
+        let zones = ["Tokyo", "America/New_York", "IST", "Atlantis"].map(String::from);
+        let clock = Clock {
+            world_clock: &zones,
+            local: Zone::parse("Berlin").unwrap(),
+            ..clock()
+        };
+        let rows = clock_rows(clock.now, &clock);
+        assert_eq!(
+            rows.iter().map(|row| row.text.as_str()).collect::<Vec<_>>(),
+            [
+                "21:00 in Tokyo",
+                "08:00 in New York",
+                "17:30 in IST",
+                "Atlantis"
+            ]
+        );
+        assert_eq!(
+            rows[0].desc,
+            "Sunday, 18 October, UTC+09:00, night, 7 hours ahead"
+        );
+        assert_eq!(
+            rows[1].desc,
+            "Sunday, 18 October, UTC-04:00, day, 6 hours behind"
+        );
+        assert!(
+            rows[2]
+                .desc
+                .ends_with("UTC+05:30, day, 3 hours, 30 minutes ahead")
+        );
+        assert_eq!(rows[3].desc, "Unknown time zone");
+
+        // 10:00 in Berlin, which is on summer time
+        let times: Vec<String> = date_results("10:00 everywhere", &clock)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.text)
+            .collect();
+        assert_eq!(
+            times[..3],
+            ["17:00 in Tokyo", "04:00 in New York", "13:30 in IST"]
+        );
+        assert_eq!(
+            date_results("tomorrow 9am in world clock", &clock).unwrap()[0].text,
+            "16:00 in Tokyo"
+        );
+        assert_eq!(
+            date_results("10:00 everywhere", &super::tests::clock()),
+            None
+        );
+    }
+
+    #[test]
+    fn not_dates() {
+        for query in [
+            "5 km to mi",
+            "2 + 3",
+            "12345",
+            "123456789",
+            "9999999999",
+            "17356896000000",
+            "3 m in ft",
+            "1 day in hours",
+            "week",
+            "now in atlantis",
+            "firefox",
+        ] {
+            assert_eq!(date_results(query, &clock()), None, "{query}");
+        }
+    }
+}
@@ -1,2 +1,10 @@
+pub mod calc_complex;
+pub mod calc_history;
+pub mod calc_quantity;
+pub mod calc_value;
 pub mod calculator;
 pub mod clipboard;
+pub mod datetime;
+pub mod plot;
+pub mod quicklink;
+pub mod solver;
@@ -0,0 +1,434 @@
+//! Plotting functions of one variable (`plot sin(x)/x from -10 to 10`), by sampling them
+use std::borrow::Cow;
+
+use arboard::ImageData;
+
+use crate::functions::{
+    calc_value::{DecimalSeparator, Format, Value},
+    calculator::{ANS, Env, Expr},
+};
+
+/// The colors of the plotted functions, in order
+pub const PLOT_COLORS: [[u8; 3]; 5] = [
+    [66, 133, 244],
+    [234, 67, 53],
+    [52, 168, 83],
+    [251, 188, 5],
+    [171, 71, 188],
+];
+
+/// The interval that's plotted if none is given
+const DEFAULT_INTERVAL: (f64, f64) = (-10., 10.);
+
+/// How many pieces the interval is split into
+const SAMPLES: u32 = 400;
+
+/// The size of the image that's copied
+const IMAGE_SIZE: (u32, u32) = (800, 400);
+
+/// A plot of some functions of the same variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub variable: String,
+    pub curves: Vec<Curve>,
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    decimal_separator: DecimalSeparator,
+}
+
+/// A plotted function, its values at the samples (NaN where it has none), and the samples it
+/// jumps after
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    pub expression: String,
+    pub points: Vec<(f64, f64)>,
+    jumps: Vec<usize>,
+}
+
+impl Plot {
+    /// Parses `plot f(x), g(x)`, optionally followed by `from a to b` (-10 to 10 by default), and
+    /// samples the functions. They're separated by commas (or semicolons, which also work with a
+    /// decimal comma), and have to use the same variable, which is the one that isn't set
+    pub fn parse(query: &str, decimal_separator: DecimalSeparator, env: &Env) -> Option<Self> {
+        let (plot, functions) = query.trim().split_once(char::is_whitespace)?;
+        if !plot.eq_ignore_ascii_case("plot") {
+            return None;
+        }
+
+        let (functions, interval) = match functions
+            .rsplit_once(" from ")
+            .and_then(|(functions, bounds)| Some((functions, bounds.split_once(" to ")?)))
+        {
+            Some((functions, (from, to))) => {
+                let from = Expr::parse(from, decimal_separator).ok()?.eval(env)?;
+                let to = Expr::parse(to, decimal_separator).ok()?.eval(env)?;
+                (functions, (from.min(to), from.max(to)))
+            }
+            None => (functions, DEFAULT_INTERVAL),
+        };
+        if !interval.0.is_finite() || !interval.1.is_finite() || interval.0 == interval.1 {
+            return None;
+        }
+
+        let separators: &[char] = match decimal_separator {
+            DecimalSeparator::Point => &[',', ';'],
+            DecimalSeparator::Comma => &[';'],
+        };
+        let expressions = split_top_level(functions, separators)
+            .into_iter()
+            .map(|function| {
+                Expr::parse(function, decimal_separator)
+                    .ok()
+                    .filter(|expr| !matches!(expr, Expr::Assign { .. }))
+                    .map(|expr| (function.trim().to_string(), expr))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if expressions.is_empty() || expressions.len() > PLOT_COLORS.len() {
+            return None;
+        }
+
+        let mut variables: Vec<&str> = Vec::new();
+        for (_, expr) in &expressions {
+            for name in expr.variables() {
+                if !variables.contains(&name) {
+                    variables.push(name);
+                }
+            }
+        }
+        let unknowns: Vec<_> = variables
+            .iter()
+            .filter(|name| !env.variables.contains_key(**name))
+            .collect();
+        let variable = match (unknowns.as_slice(), variables.as_slice()) {
+            ([unknown], _) => unknown.to_string(),
+            ([], [only]) if *only != ANS => only.to_string(),
+            ([], _) => "x".to_string(),
+            _ => return None,
+        };
+
+        let mut values = env.variables.clone();
+        let mut at = |expr: &Expr, x: f64| {
+            values.insert(variable.clone(), Value::Float(x));
+            expr.eval(&Env {
+                angle_unit: env.angle_unit,
+                variables: &values,
+            })
+            .filter(|y| y.is_finite())
+            .unwrap_or(f64::NAN)
+        };
+        let mut curves: Vec<Curve> = expressions
+            .iter()
+            .map(|(expression, expr)| Curve {
+                expression: expression.clone(),
+                points: (0..=SAMPLES)
+                    .map(|k| {
+                        let x = interval.0
+                            + (interval.1 - interval.0) * f64::from(k) / f64::from(SAMPLES);
+                        (x, at(expr, x))
+                    })
+                    .collect(),
+                jumps: Vec::new(),
+            })
+            .collect();
+
+        // A big step between two samples is a jump (like tan's at its poles) rather than a steep
+        // part of the curve if the value halfway isn't between them
+        let y_range = y_range(&curves)?;
+        let span = y_range.1 - y_range.0;
+        for (curve, (_, expr)) in curves.iter_mut().zip(&expressions) {
+            curve.jumps = (0..curve.points.len() - 1)
+                .filter(|k| {
+                    let ((xa, ya), (xb, yb)) = (curve.points[*k], curve.points[k + 1]);
+                    let middle = at(expr, (xa + xb) / 2.);
+                    (ya - yb).abs() > span / 10. && !(ya.min(yb)..=ya.max(yb)).contains(&middle)
+                })
+                .collect();
+        }
+
+        Some(Self {
+            variable,
+            curves,
+            x_range: interval,
+            y_range,
+            decimal_separator,
+        })
+    }
+
+    /// The value of a curve at x, interpolated between the samples around it
+    pub fn value_at(&self, curve: &Curve, x: f64) -> Option<f64> {
+        let (from, to) = self.x_range;
+        let position = (x - from) / (to - from) * f64::from(SAMPLES);
+        if !(0. ..=f64::from(SAMPLES)).contains(&position) {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let k = (position.floor() as usize).min(curve.points.len() - 2);
+        if curve.jumps.contains(&k) {
+            return None;
+        }
+        let ((x0, y0), (x1, y1)) = (curve.points[k], curve.points[k + 1]);
+        let y = y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+        y.is_finite().then_some(y)
+    }
+
+    /// The pieces of a curve that are drawn as lines: the runs of samples where it has values,
+    /// broken where it jumps
+    pub fn segments<'a>(&self, curve: &'a Curve) -> Vec<&'a [(f64, f64)]> {
+        let mut segments = Vec::new();
+        let mut start = None;
+        for (k, (_, y)) in curve.points.iter().enumerate() {
+            if y.is_nan() {
+                segments.extend(start.take().map(|start| &curve.points[start..k]));
+                continue;
+            }
+            let first = *start.get_or_insert(k);
+            if curve.jumps.contains(&k) {
+                segments.push(&curve.points[first..=k]);
+                start = None;
+            }
+        }
+        segments.extend(start.map(|start| &curve.points[start..]));
+        segments
+    }
+
+    /// Where a point of the plot is in an area of the given size, with y going down
+    pub fn to_screen(&self, (x, y): (f64, f64), width: f32, height: f32) -> (f32, f32) {
+        let (x0, x1) = self.x_range;
+        let (y0, y1) = self.y_range;
+        #[allow(clippy::cast_possible_truncation)]
+        (
+            ((x - x0) / (x1 - x0)) as f32 * width,
+            (1. - (y - y0) / (y1 - y0)) as f32 * height,
+        )
+    }
+
+    /// The x at a position in an area of the given width
+    pub fn x_at(&self, x: f32, width: f32) -> f64 {
+        let (x0, x1) = self.x_range;
+        x0 + f64::from(x / width) * (x1 - x0)
+    }
+
+    /// Writes a number short enough to label the plot with
+    pub fn format(&self, x: f64) -> String {
+        Value::Float(x).format(&Format {
+            precision: 4,
+            digit_grouping: false,
+            decimal_separator: self.decimal_separator,
+        })
+    }
+
+    /// A description of the plot, like `sin(x), cos(x) from -10 to 10`
+    pub fn describe(&self) -> String {
+        let functions: Vec<_> = self
+            .curves
+            .iter()
+            .map(|curve| curve.expression.as_str())
+            .collect();
+        format!(
+            "{} from {} to {}",
+            functions.join(", "),
+            self.format(self.x_range.0),
+            self.format(self.x_range.1)
+        )
+    }
+
+    /// Draws the plot (its axes and curves, on white) into an image for the clipboard
+    pub fn to_image(&self) -> ImageData<'static> {
+        let (width, height) = IMAGE_SIZE;
+        let mut canvas = Canvas {
+            width,
+            height,
+            pixels: [255].repeat(width as usize * height as usize * 4),
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let (w, h) = (width as f32, height as f32);
+
+        let (axis_x, axis_y) = self.axes(w, h);
+        let gray = [160, 160, 160];
+        canvas.line((axis_x, 0.), (axis_x, h), gray, 1.);
+        canvas.line((0., axis_y), (w, axis_y), gray, 1.);
+
+        for (curve, color) in self.curves.iter().zip(PLOT_COLORS) {
+            for segment in self.segments(curve) {
+                for pair in segment.windows(2) {
+                    let from = self.to_screen(pair[0], w, h);
+                    let to = self.to_screen(pair[1], w, h);
+                    canvas.line(from, to, color, 2.5);
+                }
+            }
+        }
+
+        ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: Cow::Owned(canvas.pixels),
+        }
+    }
+
+    /// Where the axes are drawn in an area of the given size: at 0 if it's in the plot, and on
+    /// its edge otherwise
+    pub fn axes(&self, width: f32, height: f32) -> (f32, f32) {
+        let x = 0f64.clamp(self.x_range.0, self.x_range.1);
+        let y = 0f64.clamp(self.y_range.0, self.y_range.1);
+        self.to_screen((x, y), width, height)
+    }
+}
+
+/// The range of values that's shown: all of them, unless a few are much further out than the
+/// rest (like near a pole), with some room around them
+fn y_range(curves: &[Curve]) -> Option<(f64, f64)> {
+    let mut ys: Vec<f64> = curves
+        .iter()
+        .flat_map(|curve| curve.points.iter().map(|(_, y)| *y))
+        .filter(|y| !y.is_nan())
+        .collect();
+    ys.sort_by(f64::total_cmp);
+    let (min, max) = (*ys.first()?, *ys.last()?);
+    let percentile = |p: usize| ys[(ys.len() - 1) * p / 100];
+    let (low, high) = (percentile(1), percentile(99));
+    let (low, high) = if max - min > 10. * (high - low) && high > low {
+        (low, high)
+    } else {
+        (min, max)
+    };
+    if high - low < f64::EPSILON * low.abs().max(1.) {
+        return Some((low - 1., high + 1.));
+    }
+    let padding = (high - low) * 0.05;
+    Some((low - padding, high + padding))
+}
+
+/// Splits a list at the separators that aren't inside parentheses
+fn split_top_level<'a>(s: &'a str, separators: &[char]) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && separators.contains(&c) => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// RGBA pixels that lines can be drawn on
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Draws a line of the given thickness, by stamping a square every half pixel along it
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), color: [u8; 3], thickness: f32) {
+        let length = (to.0 - from.0).hypot(to.1 - from.1);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = (length * 2.).ceil().min(10_000.) as u32;
+        for step in 0..=steps {
+            #[allow(clippy::cast_precision_loss)]
+            let t = if steps == 0 {
+                0.
+            } else {
+                step as f32 / steps as f32
+            };
+            let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            self.dot(x, y, color, thickness);
+        }
+    }
+
+    fn dot(&mut self, x: f32, y: f32, color: [u8; 3], thickness: f32) {
+        let radius = thickness / 2.;
+        #[allow(clippy::cast_possible_truncation)]
+        let (left, right) = ((x - radius).floor() as i64, (x + radius).ceil() as i64);
+        #[allow(clippy::cast_possible_truncation)]
+        let (top, bottom) = ((y - radius).floor() as i64, (y + radius).ceil() as i64);
+        for py in top.max(0)..bottom.min(i64::from(self.height)) {
+            for px in left.max(0)..right.min(i64::from(self.width)) {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let i = (py as usize * self.width as usize + px as usize) * 4;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::functions::calculator::AngleUnit;
+
+    fn plot(query: &str, variables: &HashMap<String, Value>) -> Option<Plot> {
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables,
+        };
+        Plot::parse(query, DecimalSeparator::Point, &env)
+    }
+
+    #[test]
+    fn parsing() {
+        let none = HashMap::new();
+        let sinc = plot("plot sin(x)/x from -10 to 10", &none).unwrap();
+        assert_eq!(sinc.variable, "x");
+        assert_eq!(sinc.x_range, (-10., 10.));
+        assert_eq!(sinc.curves.len(), 1);
+        assert_eq!(sinc.curves[0].points.len(), SAMPLES as usize + 1);
+        // sin(0)/0 has no value, but the curve is drawn around it
+        assert!(sinc.curves[0].points[200].1.is_nan());
+        assert_eq!(sinc.segments(&sinc.curves[0]).len(), 2);
+
+        let several = plot("plot sin(t), max(t, 0); t^2 from 2pi to 0", &none).unwrap();
+        let expressions: Vec<_> = several
+            .curves
+            .iter()
+            .map(|c| c.expression.as_str())
+            .collect();
+        assert_eq!(expressions, ["sin(t)", "max(t, 0)", "t^2"]);
+        assert_eq!(several.x_range, (0., 2. * std::f64::consts::PI));
+
+        let a = HashMap::from([("a".to_string(), Value::Float(2.))]);
+        assert_eq!(plot("plot a x", &a).unwrap().variable, "x");
+        assert_eq!(plot("plot 3", &none).unwrap().x_range, DEFAULT_INTERVAL);
+
+        let not_plots = [
+            "plot",
+            "plot x + y",
+            "plot x = 2",
+            "plot x from 1 to 1",
+            "plot foo(x)",
+            "plotx",
+            "plot sqrt(x) from -2 to -1",
+        ];
+        for query in not_plots {
+            assert_eq!(plot(query, &a), None, "{query}");
+        }
+    }
+
+    #[test]
+    fn values() {
+        let none = HashMap::new();
+        let square = plot("plot x^2 from 0 to 4", &none).unwrap();
+        let y0 = square.y_range.0;
+        assert!((-0.9..0.).contains(&y0), "{:?}", square.y_range);
+        assert!((square.value_at(&square.curves[0], 2.).unwrap() - 4.).abs() < 1e-3);
+        assert_eq!(square.value_at(&square.curves[0], 5.), None);
+        assert_eq!(square.to_screen((0., y0), 100., 50.), (0., 50.));
+
+        // The values near tan's poles don't squash the rest of the plot
+        let tan = plot("plot tan(x)", &none).unwrap();
+        assert!(tan.y_range.1 < 1000., "{:?}", tan.y_range);
+        assert_eq!(tan.segments(&tan.curves[0]).len(), 7);
+
+        let image = square.to_image();
+        assert_eq!(image.bytes.len(), image.width * image.height * 4);
+    }
+}
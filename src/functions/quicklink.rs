@@ -0,0 +1,269 @@
+//! This has the logic for quicklinks: URLs and paths with placeholders, opened by typing their
+//! keyword followed by the values of the placeholders
+use crate::{
+    app::apps::{AppCommand, SimpleApp},
+    commands::Function,
+    config::Quicklink,
+    platform::get_img_handle,
+    utils::percent_encode,
+};
+
+/// The placeholder that is replaced by everything typed after the keyword
+const QUERY_PLACEHOLDER: &str = "query";
+
+/// The names of the placeholders in the template, in the order they first appear
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[start + 1 + len..];
+    }
+    names
+}
+
+/// The arguments in the input, split where `is_separator` matches, with the byte offset each one
+/// starts at
+fn arguments(input: &str, is_separator: impl Fn(char) -> bool) -> Vec<(usize, &str)> {
+    let mut args = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices() {
+        match (is_separator(c), start) {
+            (true, Some(from)) => {
+                args.push((from, &input[from..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        args.push((from, &input[from..]));
+    }
+    args
+}
+
+/// Fills in the placeholders of a template with the arguments typed after the keyword
+///
+/// - `{query}` is replaced with everything after the keyword
+/// - `{1}`, `{2}`... are replaced with the first, second... argument
+/// - any other `{name}` takes the next argument, in the order the placeholders appear
+///
+/// Arguments are separated by spaces, or by slashes if there aren't enough of them otherwise, so
+/// `rust-lang/rust` fills both `{owner}` and `{repo}`. The placeholder that takes the last
+/// argument also gets the rest of the input after it, as it was typed.
+///
+/// The values are percent-encoded when `encode` is set, and the template is filled in a single
+/// pass, so values that look like placeholders are left alone. Returns [`None`] if there aren't
+/// enough arguments.
+pub fn expand(template: &str, input: &str, encode: bool) -> Option<String> {
+    let input = input.trim();
+    let names = placeholders(template);
+    if names.is_empty() {
+        return Some(template.to_string());
+    }
+
+    // The index of the argument each placeholder takes
+    let mut next = 0;
+    let indices: Vec<Option<usize>> = names
+        .iter()
+        .map(|name| {
+            if *name == QUERY_PLACEHOLDER {
+                None
+            } else if let Ok(n) = name.parse::<usize>() {
+                Some(n.saturating_sub(1))
+            } else {
+                next += 1;
+                Some(next - 1)
+            }
+        })
+        .collect();
+    let needed = indices.iter().flatten().map(|i| i + 1).max().unwrap_or(0);
+
+    if input.is_empty() {
+        return None;
+    }
+    let mut args = arguments(input, char::is_whitespace);
+    if args.len() < needed {
+        args = arguments(input, |c| c.is_whitespace() || c == '/');
+    }
+    if args.len() < needed {
+        return None;
+    }
+
+    let values: Vec<(&str, String)> = names
+        .iter()
+        .zip(indices)
+        .map(|(name, index)| {
+            let value = match index {
+                None => input,
+                Some(i) if i + 1 == needed => &input[args[i].0..],
+                Some(i) => args[i].1,
+            };
+            let value = if encode {
+                percent_encode(value)
+            } else {
+                value.to_string()
+            };
+            (*name, value)
+        })
+        .collect();
+
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        expanded.push_str(&rest[..start]);
+        match values.iter().find(|(placeholder, _)| *placeholder == name) {
+            Some((_, value)) => expanded.push_str(value),
+            None => expanded.push_str(&rest[start..start + 2 + len]),
+        }
+        rest = &rest[start + 2 + len..];
+    }
+    expanded.push_str(rest);
+    Some(expanded)
+}
+
+impl Quicklink {
+    /// Returns the result for the query, if it starts with the quicklink's keyword and has all the
+    /// arguments it needs
+    pub fn to_app(&self, query: &str) -> Option<SimpleApp> {
+        let query = query.trim_start();
+        let (keyword, input) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        if !keyword.eq_ignore_ascii_case(&self.keyword) {
+            return None;
+        }
+
+        let is_url = self.url.contains("://");
+        // Only the `~` a path starts with is the home directory, not one typed in an argument
+        let template = match self.url.strip_prefix('~') {
+            Some(rest) if !is_url => {
+                format!("{}{rest}", std::env::var("HOME").unwrap_or_default())
+            }
+            _ => self.url.clone(),
+        };
+        let target = expand(&template, input, is_url)?;
+
+        let function = if self.open_with.is_none()
+            && (target.starts_with("http://") || target.starts_with("https://"))
+        {
+            Function::OpenWebsite(target.clone())
+        } else {
+            Function::OpenWith(target.clone(), self.open_with.clone())
+        };
+
+        let icon = self.icon_path.as_ref().and_then(|path| {
+            let path = path.replace('~', &std::env::var("HOME").unwrap_or_default());
+            get_img_handle(path.as_ref())
+        });
+
+        Some(SimpleApp::new_builtin_with_icon(
+            self.name.as_deref().unwrap_or(&self.keyword),
+            "",
+            &target,
+            AppCommand::Function(function),
+            icon,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let cases = [
+            (
+                "https://jira.local/browse/{1}",
+                "PROJ-123",
+                "https://jira.local/browse/PROJ-123",
+            ),
+            ("https://x.com/{2}/{1}", "a b", "https://x.com/b/a"),
+            (
+                "https://github.com/{owner}/{repo}/pulls",
+                "rust-lang rust",
+                "https://github.com/rust-lang/rust/pulls",
+            ),
+            (
+                "https://github.com/{owner}/{repo}/pulls",
+                "rust-lang/rust",
+                "https://github.com/rust-lang/rust/pulls",
+            ),
+            (
+                "https://x.com/?q={query}&p={1}&s={2}",
+                "a b",
+                "https://x.com/?q=a%20b&p=a&s=b",
+            ),
+            ("https://x.com/{1}/{1}", "a", "https://x.com/a/a"),
+            ("https://x.com/{}", "a", "https://x.com/{}"),
+            ("https://x.com/", "a", "https://x.com/"),
+        ];
+        for (template, input, expected) in cases {
+            assert_eq!(
+                expand(template, input, true).as_deref(),
+                Some(expected),
+                "{template} with {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn leftover_arguments() {
+        assert_eq!(
+            expand("https://x.com/{1}", "a  b", true).as_deref(),
+            Some("https://x.com/a%20%20b")
+        );
+        assert_eq!(
+            expand("~/{project}/{path}", "app src/main.rs", false).as_deref(),
+            Some("~/app/src/main.rs")
+        );
+        assert_eq!(
+            expand("https://x.com/{owner}/{repo}", "a/b/c", true).as_deref(),
+            Some("https://x.com/a/b%2Fc")
+        );
+        assert_eq!(
+            expand("~/{1}/{2}", "été   ~/notes ~", false).as_deref(),
+            Some("~/été/~/notes ~")
+        );
+    }
+
+    #[test]
+    fn missing_arguments() {
+        assert_eq!(expand("https://x.com/{1}", "", true), None);
+        assert_eq!(expand("https://x.com/{query}", "  ", true), None);
+        assert_eq!(expand("https://x.com/{2}", "a", true), None);
+        assert_eq!(expand("https://x.com/{owner}/{repo}", "a", true), None);
+    }
+
+    #[test]
+    fn values_are_encoded_once() {
+        assert_eq!(
+            expand("https://x.com/?q={query}", "c++ & rust/ü", true).as_deref(),
+            Some("https://x.com/?q=c%2B%2B%20%26%20rust%2F%C3%BC")
+        );
+        assert_eq!(
+            expand("~/notes/{query}.md", "a b", false).as_deref(),
+            Some("~/notes/a b.md")
+        );
+
+        // Values that look like placeholders aren't filled in again
+        assert_eq!(
+            expand("https://x.com/{1}/{2}", "{2} b", false).as_deref(),
+            Some("https://x.com/{2}/b")
+        );
+        assert_eq!(
+            expand("https://x.com/{1}?q={query}", "{query}", false).as_deref(),
+            Some("https://x.com/{query}?q={query}")
+        );
+    }
+}
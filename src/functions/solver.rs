@@ -0,0 +1,533 @@
+//! Solving equations with one unknown, like `solve x^2 - 5x + 6 = 0` or `2x + 3 = 11`
+//!
+//! Linear and quadratic equations are solved exactly (with complex roots when a quadratic has no
+//! real ones). Other polynomials and everything else are solved numerically, by looking for the
+//! places where the difference between the two sides changes sign.
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::functions::{
+    calc_complex::Complex,
+    calc_value::{DecimalSeparator, Value},
+    calculator::{ANS, BinOp, Env, Expr, UnaryOp},
+};
+
+/// The highest degree a polynomial can be expanded to, so that something like `(x+1)^1000` is
+/// solved numerically instead
+const MAX_DEGREE: usize = 32;
+
+/// Where the roots of an equation that isn't a polynomial are looked for, if no interval is given
+const DEFAULT_INTERVAL: (f64, f64) = (-100., 100.);
+
+/// How many pieces the interval is split into to look for sign changes
+const SAMPLES: u32 = 2000;
+
+/// The most roots that are shown, which are the ones closest to 0
+const MAX_ROOTS: usize = 10;
+
+/// How close to 0 the difference between the two sides has to be (relative to its terms) for a
+/// place where it touches 0 without changing sign to count as a root
+const TOUCH_TOLERANCE: f64 = 1e-9;
+
+/// An equation with one unknown, and the interval its roots are looked for in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equation {
+    pub unknown: String,
+    lhs: Expr,
+    rhs: Expr,
+    interval: Option<(Expr, Expr)>,
+}
+
+impl Equation {
+    /// Parses `solve equation`, optionally followed by `from a to b` (the interval to look for
+    /// roots in), or an equation without `solve` that isn't an assignment (`2x + 3 = 11`).
+    ///
+    /// After `solve`, an expression without `=` is solved for 0. The unknown is the variable that
+    /// isn't set, or after `solve`, the only variable there is.
+    pub fn parse(query: &str, decimal_separator: DecimalSeparator, env: &Env) -> Option<Self> {
+        let query = query.trim();
+        let (explicit, equation) = match query.split_once(char::is_whitespace) {
+            Some((solve, equation)) if solve.eq_ignore_ascii_case("solve") => (true, equation),
+            // Something like `x = 2` is an assignment rather than an equation
+            _ if Expr::parse(query, decimal_separator).is_ok() => return None,
+            _ => (false, query),
+        };
+
+        let (equation, interval) = match equation
+            .rsplit_once(" from ")
+            .and_then(|(equation, bounds)| Some((equation, bounds.split_once(" to ")?)))
+        {
+            Some((equation, (from, to))) => (
+                equation,
+                Some((
+                    Expr::parse(from, decimal_separator).ok()?,
+                    Expr::parse(to, decimal_separator).ok()?,
+                )),
+            ),
+            None => (equation, None),
+        };
+
+        let (lhs, rhs) = match equation.split_once('=') {
+            Some(sides) => sides,
+            None if explicit => (equation, "0"),
+            None => return None,
+        };
+        let lhs = Expr::parse(lhs, decimal_separator).ok()?;
+        let rhs = Expr::parse(rhs, decimal_separator).ok()?;
+        if matches!(rhs, Expr::Assign { .. }) {
+            return None;
+        }
+
+        let mut variables = lhs.variables();
+        variables.extend(
+            rhs.variables()
+                .into_iter()
+                .filter(|name| !lhs.variables().contains(name)),
+        );
+        let unknowns: Vec<_> = variables
+            .iter()
+            .filter(|name| !env.variables.contains_key(**name))
+            .collect();
+        let unknown = match (unknowns.as_slice(), variables.as_slice()) {
+            ([unknown], _) => unknown.to_string(),
+            ([], [only]) if explicit && *only != ANS => only.to_string(),
+            _ => return None,
+        };
+
+        Some(Self {
+            unknown,
+            lhs,
+            rhs,
+            interval,
+        })
+    }
+
+    /// The roots of the equation, in increasing order (with complex roots last), or an empty list
+    /// if it has none (in its interval) or they couldn't be found
+    pub fn solve(&self, env: &Env) -> Vec<Value> {
+        let difference = Expr::Binary {
+            op: BinOp::Sub,
+            lhs: Box::new(self.lhs.clone()),
+            rhs: Box::new(self.rhs.clone()),
+        };
+        let interval = match &self.interval {
+            Some((from, to)) => match (from.eval(env), to.eval(env)) {
+                (Some(from), Some(to)) if from.is_finite() && to.is_finite() => {
+                    Some((from.min(to), from.max(to)))
+                }
+                _ => return Vec::new(),
+            },
+            None => None,
+        };
+
+        if let Some(mut coefficients) = polynomial(&difference, &self.unknown, env) {
+            while coefficients.last().is_some_and(Zero::is_zero) {
+                coefficients.pop();
+            }
+            let roots = polynomial_roots(&coefficients, interval);
+            return match interval {
+                Some((from, to)) => roots
+                    .into_iter()
+                    .filter(|root| {
+                        let x = root.to_f64();
+                        from <= x && x <= to
+                    })
+                    .collect(),
+                None => roots,
+            };
+        }
+
+        let mut variables = env.variables.clone();
+        let mut f = |x: f64| {
+            variables.insert(self.unknown.clone(), Value::Float(x));
+            difference.eval(&Env {
+                angle_unit: env.angle_unit,
+                variables: &variables,
+            })
+        };
+        numeric_roots(&mut f, interval.unwrap_or(DEFAULT_INTERVAL), |_| false)
+            .into_iter()
+            .map(Value::Float)
+            .collect()
+    }
+}
+
+/// The coefficients of the expression as a polynomial in the unknown, starting with the constant
+/// term, or [`None`] if it isn't one (or its degree is too high)
+fn polynomial(expr: &Expr, unknown: &str, env: &Env) -> Option<Vec<BigRational>> {
+    if !expr.variables().contains(&unknown) {
+        return match expr.evaluate(env)? {
+            Value::Exact(r) => Some(vec![r]),
+            Value::Float(x) => Some(vec![BigRational::from_float(x)?]),
+            Value::Complex(_) => None,
+        };
+    }
+
+    match expr {
+        Expr::Var(_) => Some(vec![BigRational::zero(), BigRational::one()]),
+        Expr::Unary { op, rhs } => {
+            let p = polynomial(rhs, unknown, env)?;
+            match op {
+                UnaryOp::Plus => Some(p),
+                UnaryOp::Minus => Some(scale(&p, &-BigRational::one())),
+                UnaryOp::Percent => Some(scale(&p, &BigRational::new(1.into(), 100.into()))),
+                UnaryOp::Not | UnaryOp::Factorial => None,
+            }
+        }
+        Expr::Binary { op, lhs, rhs } => {
+            let a = polynomial(lhs, unknown, env)?;
+            let b = polynomial(rhs, unknown, env)?;
+            match (op, b.as_slice()) {
+                (BinOp::Add, _) => Some(add(&a, &b)),
+                (BinOp::Sub, _) => Some(add(&a, &scale(&b, &-BigRational::one()))),
+                (BinOp::Mul, _) => multiply(&a, &b),
+                (BinOp::Div, [divisor]) if !divisor.is_zero() => Some(scale(&a, &divisor.recip())),
+                (BinOp::Pow, [exponent]) if exponent.is_integer() && !exponent.is_negative() => {
+                    let exponent = exponent
+                        .to_integer()
+                        .to_usize()
+                        .filter(|exponent| *exponent <= MAX_DEGREE)?;
+                    (0..exponent)
+                        .try_fold(vec![BigRational::one()], |power, _| multiply(&power, &a))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn add(a: &[BigRational], b: &[BigRational]) -> Vec<BigRational> {
+    (0..a.len().max(b.len()))
+        .map(|i| match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => x + y,
+            (Some(x), None) | (None, Some(x)) => x.clone(),
+            (None, None) => BigRational::zero(),
+        })
+        .collect()
+}
+
+fn scale(p: &[BigRational], factor: &BigRational) -> Vec<BigRational> {
+    p.iter().map(|c| c * factor).collect()
+}
+
+/// The product of two polynomials, or [`None`] if its degree is above [`MAX_DEGREE`]
+fn multiply(a: &[BigRational], b: &[BigRational]) -> Option<Vec<BigRational>> {
+    if a.len() + b.len() > MAX_DEGREE + 2 {
+        return None;
+    }
+    let mut product = vec![BigRational::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    Some(product)
+}
+
+/// The roots of a polynomial (without trailing zero coefficients): exact or with square roots up
+/// to degree 2, and numerically (and only real ones) above that
+fn polynomial_roots(coefficients: &[BigRational], interval: Option<(f64, f64)>) -> Vec<Value> {
+    match coefficients {
+        [c, b] => vec![Value::Exact(-c / b)],
+        [c, b, a] => quadratic_roots(a, b, c),
+        [.., leading] if coefficients.len() > 3 => {
+            let coefficients: Vec<f64> = coefficients
+                .iter()
+                .map(|c| c.to_f64().unwrap_or(f64::NAN))
+                .collect();
+            // Cauchy's bound: every root is at most this far from 0
+            let bound = 1.
+                + coefficients.iter().map(|c| c.abs()).fold(0., f64::max)
+                    / leading.to_f64().unwrap_or(f64::NAN).abs();
+            if !bound.is_finite() {
+                return Vec::new();
+            }
+            let derivative: Vec<f64> = coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(power, c)| c * f64::from(power_i32(power)))
+                .collect();
+            // Double roots don't change sign, so the places where the derivative does are checked too
+            let touches = |x: f64| {
+                let terms = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(power, c)| (c * x.powi(power_i32(power))).abs())
+                    .fold(0., f64::max);
+                horner(&coefficients, x).abs() <= TOUCH_TOLERANCE * terms
+                    && horner(&derivative, x).abs() <= TOUCH_TOLERANCE.sqrt() * terms.max(1.)
+            };
+            numeric_roots(
+                &mut |x| Some(horner(&coefficients, x)),
+                interval.unwrap_or((-bound, bound)),
+                touches,
+            )
+            .into_iter()
+            .map(Value::Float)
+            .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn power_i32(power: usize) -> i32 {
+    i32::try_from(power).unwrap_or(i32::MAX)
+}
+
+/// The value of a polynomial at x
+fn horner(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0., |sum, c| sum * x + c)
+}
+
+/// The roots of ax^2 + bx + c, which are exact if the discriminant is the square of a rational
+fn quadratic_roots(a: &BigRational, b: &BigRational, c: &BigRational) -> Vec<Value> {
+    let two_a = a * BigRational::from_integer(2.into());
+    let discriminant = b * b - a * c * BigRational::from_integer(4.into());
+    if discriminant.is_zero() {
+        return vec![Value::Exact(-b / &two_a)];
+    }
+    if let Some(root) = exact_sqrt(&discriminant) {
+        let mut roots = [(-b - &root) / &two_a, (-b + root) / &two_a];
+        roots.sort();
+        return roots.into_iter().map(Value::Exact).collect();
+    }
+
+    let (Some(two_a), Some(b), Some(discriminant)) =
+        (two_a.to_f64(), b.to_f64(), discriminant.to_f64())
+    else {
+        return Vec::new();
+    };
+    if discriminant > 0. {
+        let mut roots = [
+            (-b - discriminant.sqrt()) / two_a,
+            (-b + discriminant.sqrt()) / two_a,
+        ];
+        roots.sort_by(f64::total_cmp);
+        roots.into_iter().map(Value::Float).collect()
+    } else {
+        let (re, im) = (-b / two_a, ((-discriminant).sqrt() / two_a).abs());
+        vec![
+            Value::Complex(Complex::new(re, -im)),
+            Value::Complex(Complex::new(re, im)),
+        ]
+    }
+}
+
+/// The square root of a rational, if it's the square of another one
+fn exact_sqrt(r: &BigRational) -> Option<BigRational> {
+    if r.is_negative() {
+        return None;
+    }
+    let numer = r.numer().sqrt();
+    let denom = r.denom().sqrt();
+    (&numer * &numer == *r.numer() && &denom * &denom == *r.denom())
+        .then(|| BigRational::new(numer, denom))
+}
+
+/// Looks for the roots of f in the interval, where it changes sign or is exactly 0 (or
+/// `touches` says the place where f's derivative changes sign is a root), and narrows them down by
+/// bisection. Returns the [`MAX_ROOTS`] roots closest to 0, in increasing order
+fn numeric_roots(
+    f: &mut impl FnMut(f64) -> Option<f64>,
+    (from, to): (f64, f64),
+    touches: impl Fn(f64) -> bool,
+) -> Vec<f64> {
+    let step = (to - from) / f64::from(SAMPLES);
+    let samples: Vec<(f64, Option<f64>)> = (0..=SAMPLES)
+        .map(|k| {
+            let x = from + step * f64::from(k);
+            (x, f(x).filter(|y| y.is_finite()))
+        })
+        .collect();
+
+    let mut roots = Vec::new();
+    for (k, window) in samples.windows(2).enumerate() {
+        let [(a, fa), (b, fb)] = [window[0], window[1]];
+        let (Some(fa), Some(fb)) = (fa, fb) else {
+            continue;
+        };
+        if fa == 0. {
+            roots.push(a);
+        } else if fb != 0. && fa.signum() != fb.signum() {
+            let root = bisect(f, (a, fa), b);
+            // A sign change with a huge value in between is a pole (like tan's), not a root
+            if f(root).is_some_and(|y| y.abs() <= fa.abs().max(fb.abs()).max(1.)) {
+                roots.push(root);
+            }
+        } else if k > 0
+            && let Some(touch) = local_extremum(f, &samples[k - 1..=k + 1])
+            && touches(touch)
+        {
+            roots.push(touch);
+        }
+    }
+    if let Some((last, Some(y))) = samples.last()
+        && *y == 0.
+    {
+        roots.push(*last);
+    }
+
+    let mut roots: Vec<f64> = roots.into_iter().map(|x| snap(f, x)).collect();
+    roots.dedup_by(|a, b| (*a - *b).abs() <= step / 2.);
+    roots.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+    roots.truncate(MAX_ROOTS);
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Narrows down a sign change of f between a and b, where f(a) is `fa`
+fn bisect(f: &mut impl FnMut(f64) -> Option<f64>, (mut a, fa): (f64, f64), mut b: f64) -> f64 {
+    loop {
+        let middle = (a + b) / 2.;
+        if middle <= a.min(b) || middle >= a.max(b) {
+            return middle;
+        }
+        match f(middle) {
+            Some(y) if y != 0. && y.signum() == fa.signum() => a = middle,
+            Some(y) if y != 0. => b = middle,
+            _ => return middle,
+        }
+    }
+}
+
+/// The place where |f| is smallest between the first and last of three samples, if the middle
+/// one is a local minimum of |f|, found by golden-section search
+fn local_extremum(
+    f: &mut impl FnMut(f64) -> Option<f64>,
+    samples: &[(f64, Option<f64>)],
+) -> Option<f64> {
+    let [(mut a, Some(fa)), (_, Some(fm)), (mut b, Some(fb))] = *samples else {
+        return None;
+    };
+    if fm.abs() > fa.abs() || fm.abs() > fb.abs() {
+        return None;
+    }
+    let ratio = (5f64.sqrt() - 1.) / 2.;
+    for _ in 0..100 {
+        let c = b - ratio * (b - a);
+        let d = a + ratio * (b - a);
+        if f(c)?.abs() < f(d)?.abs() {
+            b = d;
+        } else {
+            a = c;
+        }
+    }
+    Some((a + b) / 2.)
+}
+
+/// Rounds a root to the nearest whole number if that's at least as good a root, so that rounding
+/// errors don't show up as 1.9999999999999998
+fn snap(f: &mut impl FnMut(f64) -> Option<f64>, x: f64) -> f64 {
+    let rounded = x.round();
+    match (f(x), f(rounded)) {
+        (Some(y), Some(rounded_y))
+            if (x - rounded).abs() <= 1e-9 * x.abs().max(1.) && rounded_y.abs() <= y.abs() =>
+        {
+            rounded
+        }
+        _ => x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, f64::consts::PI};
+
+    use super::*;
+
+    fn solve(query: &str, variables: &HashMap<String, Value>) -> Option<Vec<Value>> {
+        let env = Env {
+            angle_unit: crate::functions::calculator::AngleUnit::Radians,
+            variables,
+        };
+        Equation::parse(query, DecimalSeparator::Point, &env).map(|equation| equation.solve(&env))
+    }
+
+    fn exact(numer: i64, denom: i64) -> Value {
+        Value::Exact(BigRational::new(numer.into(), denom.into()))
+    }
+
+    #[test]
+    fn exact_roots() {
+        let none = HashMap::new();
+        let cases = [
+            ("solve x^2 - 5x + 6 = 0", vec![exact(2, 1), exact(3, 1)]),
+            ("2x + 3 = 11", vec![exact(4, 1)]),
+            ("solve 3y = 1", vec![exact(1, 3)]),
+            ("solve (x - 1)^2", vec![exact(1, 1)]),
+            ("x^2 = 1/4", vec![exact(-1, 2), exact(1, 2)]),
+            ("solve x^2 - 5x + 6 = 0 from 2.5 to 10", vec![exact(3, 1)]),
+            ("solve 2x = 2x + 1", vec![]),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(solve(query, &none), Some(expected), "{query}");
+        }
+    }
+
+    #[test]
+    fn irrational_and_complex_roots() {
+        let none = HashMap::new();
+        let roots = solve("solve x^2 = 2", &none).unwrap();
+        let [Value::Float(a), Value::Float(b)] = roots.as_slice() else {
+            panic!("{roots:?}");
+        };
+        assert!((a + 2f64.sqrt()).abs() < 1e-12 && (b - 2f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(
+            solve("solve x^2 + 1 = 0", &none),
+            Some(vec![
+                Value::Complex(Complex::new(0., -1.)),
+                Value::Complex(Complex::new(0., 1.)),
+            ])
+        );
+    }
+
+    #[test]
+    fn numeric_roots() {
+        let none = HashMap::new();
+        let cases: [(&str, &[f64]); 6] = [
+            ("solve x^3 - 6x^2 + 11x - 6 = 0", &[1., 2., 3.]),
+            ("solve (x - 1)^2 (x + 2) = 0", &[-2., 1.]),
+            ("solve cos(x) = x", &[0.739_085_133_215_160_6]),
+            ("solve sin(x) = 0 from -1 to 7", &[0., PI, 2. * PI]),
+            ("solve tan(x) = 0 from 1 to 2", &[]),
+            ("solve sqrt(x) = 3", &[9.]),
+        ];
+        for (query, expected) in cases {
+            let roots: Vec<f64> = solve(query, &none)
+                .unwrap_or_else(|| panic!("{query} didn't parse"))
+                .iter()
+                .map(Value::to_f64)
+                .collect();
+            assert_eq!(roots.len(), expected.len(), "{query}: {roots:?}");
+            for (root, expected) in roots.iter().zip(expected) {
+                assert!((root - expected).abs() < 1e-9, "{query}: {roots:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn unknowns() {
+        let variables = HashMap::from([
+            ("a".to_string(), Value::Float(2.)),
+            ("x".to_string(), Value::Float(5.)),
+        ]);
+        assert_eq!(solve("a y = 6", &variables), Some(vec![exact(3, 1)]));
+        assert_eq!(solve("solve x^2 = 9", &variables).map(|r| r.len()), Some(2));
+
+        let not_equations = [
+            "x = 2",
+            "1 + 2",
+            "y + z = 1",
+            "x + 1 = 3",
+            "solve 1 = 2 = 3",
+            "solve x = y = 1",
+            "2 = 3",
+        ];
+        for query in not_equations {
+            assert_eq!(solve(query, &variables), None, "{query}");
+        }
+    }
+}
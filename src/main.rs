@@ -1,15 +1,20 @@
 mod app;
 mod app_finding;
+mod bookmarks;
 mod commands;
 mod config;
+mod extensions;
 mod functions;
 mod hotkey;
 mod icon;
 mod logging;
+mod mcp;
+mod modes;
 mod platform;
 mod styles;
 mod unit_conversion;
 mod utils;
+mod wasm_extensions;
 
 use std::fs::create_dir_all;
 use std::io;
@@ -0,0 +1,267 @@
+//! MCP (Model Context Protocol) servers
+//!
+//! Servers are listed under `[[mcp_servers]]` in the config and spawned like extensions, talking
+//! JSON-RPC over their stdin / stdout. rustcast acts as a (very small) MCP client: each tool a
+//! server offers becomes a searchable command, its arguments are collected through a form built
+//! from the tool's input schema, and its result is shown on its own page.
+//!
+//! Only tools are supported, not prompts, resources or sampling.
+use std::time::Duration;
+
+use base64::Engine;
+use iced::widget::image::Handle;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    app::{
+        Message,
+        apps::{AppCommand, SimpleApp},
+    },
+    extensions::{
+        host::{Event, ProcessHost, Registration},
+        process::ExtensionProcess,
+    },
+};
+
+pub mod schema;
+
+/// The MCP version rustcast implements
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// How long a tool gets to run, which is a lot longer than the other requests
+const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A tool offered by a server, as returned by `tools/list`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub input_schema: Value,
+}
+
+impl Tool {
+    /// The name to show the user
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListToolsResult {
+    tools: Vec<Tool>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallToolResult {
+    #[serde(default)]
+    content: Vec<Content>,
+    #[serde(default)]
+    structured_content: Option<Value>,
+    #[serde(default)]
+    is_error: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Content {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+    },
+    Resource {
+        resource: EmbeddedResource,
+    },
+    ResourceLink {
+        uri: String,
+        #[serde(default)]
+        description: Option<String>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Deserialize)]
+struct EmbeddedResource {
+    uri: String,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// A single piece of a tool's result
+#[derive(Debug, Clone)]
+pub enum OutputBlock {
+    Text(String),
+    Image(Handle),
+    Resource { uri: String, text: Option<String> },
+}
+
+/// The result of running a tool
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub blocks: Vec<OutputBlock>,
+    /// Whether the tool itself reported that it failed
+    pub is_error: bool,
+}
+
+impl From<CallToolResult> for ToolOutput {
+    fn from(result: CallToolResult) -> Self {
+        let mut blocks: Vec<OutputBlock> = result
+            .content
+            .into_iter()
+            .filter_map(|content| match content {
+                Content::Text { text } => Some(OutputBlock::Text(text)),
+                Content::Image { data } => {
+                    match base64::engine::general_purpose::STANDARD.decode(data) {
+                        Ok(bytes) => Some(OutputBlock::Image(Handle::from_bytes(bytes))),
+                        Err(e) => Some(OutputBlock::Text(format!("Invalid image: {e}"))),
+                    }
+                }
+                Content::Resource { resource } => Some(OutputBlock::Resource {
+                    uri: resource.uri,
+                    text: resource.text,
+                }),
+                Content::ResourceLink { uri, description } => Some(OutputBlock::Resource {
+                    uri,
+                    text: description,
+                }),
+                Content::Unsupported => None,
+            })
+            .collect();
+
+        if blocks.is_empty()
+            && let Some(structured) = result.structured_content
+        {
+            let text = serde_json::to_string_pretty(&structured).unwrap_or_default();
+            blocks.push(OutputBlock::Text(text));
+        }
+
+        Self {
+            blocks,
+            is_error: result.is_error,
+        }
+    }
+}
+
+/// Keeps track of all the configured MCP servers and their processes
+pub type McpHost = ProcessHost<Vec<Tool>>;
+
+impl Registration for Vec<Tool> {
+    fn handshake(process: &ExtensionProcess) -> impl Future<Output = anyhow::Result<Self>> + Send {
+        initialize(process)
+    }
+
+    fn apps(&self, name: &str) -> Vec<SimpleApp> {
+        tool_apps(name, self)
+    }
+
+    fn log(name: &str, event: Event<'_, Self>) {
+        match event {
+            Event::Restarting => tracing::warn!(target: "mcp", "[{name}] Crashed, restarting"),
+            Event::Started(tools) => {
+                tracing::info!(target: "mcp", "[{name}] Started with {} tools", tools.len());
+            }
+            Event::Failed(e) => tracing::error!(target: "mcp", "[{name}] Failed to start: {e:#}"),
+        }
+    }
+}
+
+impl McpHost {
+    /// Looks up one of a server's tools
+    pub fn tool(&self, server: &str, tool: &str) -> Option<Tool> {
+        self.find_map(|s| {
+            (s.name() == server)
+                .then(|| s.registration().iter().find(|t| t.name == tool).cloned())?
+        })
+    }
+
+    /// Returns a future that runs a tool with the given arguments
+    pub fn call_tool(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: Map<String, Value>,
+    ) -> impl Future<Output = Result<ToolOutput, String>> + use<> {
+        let process = self.process(server);
+        let server = server.to_string();
+        let params = json!({ "name": tool, "arguments": arguments });
+
+        async move {
+            let Some(process) = process else {
+                return Err(format!("{server} isn't running"));
+            };
+
+            process
+                .request_with_timeout::<CallToolResult>("tools/call", params, TOOL_CALL_TIMEOUT)
+                .await
+                .map(ToolOutput::from)
+                .map_err(|e| {
+                    tracing::error!(target: "mcp", "[{server}] {e:#}");
+                    format!("{e:#}")
+                })
+        }
+    }
+}
+
+/// Does the initialization handshake, then lists every tool the server offers
+async fn initialize(process: &ExtensionProcess) -> anyhow::Result<Vec<Tool>> {
+    let params = json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": {
+            "name": "rustcast",
+            "version": option_env!("APP_VERSION").unwrap_or("Unknown Version"),
+        },
+    });
+    process.request::<Value>("initialize", params).await?;
+    process.notify("notifications/initialized", json!({}))?;
+
+    let mut tools = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let page: ListToolsResult = process.request("tools/list", params).await?;
+        tools.extend(page.tools);
+
+        match page.next_cursor {
+            Some(next) if cursor.as_ref() != Some(&next) => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(tools)
+}
+
+fn tool_apps(server: &str, tools: &[Tool]) -> Vec<SimpleApp> {
+    tools
+        .iter()
+        .map(|tool| {
+            let name = tool.display_name();
+            let desc = tool
+                .description
+                .as_deref()
+                .and_then(|description| description.lines().next())
+                .unwrap_or(server);
+            SimpleApp::new_builtin(
+                name,
+                &name.to_lowercase(),
+                desc,
+                AppCommand::Message(Message::OpenMcpTool(server.to_string(), tool.name.clone())),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,268 @@
+//! Turns a tool's JSON schema into form fields, and the filled in fields back into arguments
+use serde_json::{Map, Number, Value};
+
+/// What kind of input a field takes
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    /// One of a fixed set of strings
+    Enum(Vec<String>),
+    /// Objects, arrays, and anything the schema doesn't describe, typed in as raw JSON
+    Json,
+}
+
+/// A single argument of a tool, along with what the user typed in for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    pub name: String,
+    pub kind: FieldKind,
+    pub description: Option<String>,
+    pub required: bool,
+    pub value: String,
+}
+
+impl FormField {
+    /// The placeholder to show in the field's text input
+    pub fn placeholder(&self) -> &'static str {
+        match self.kind {
+            FieldKind::String | FieldKind::Enum(_) | FieldKind::Boolean => "",
+            FieldKind::Number => "Number",
+            FieldKind::Integer => "Whole number",
+            FieldKind::Json => "JSON",
+        }
+    }
+}
+
+/// Builds a field for each property of the schema, with the required ones first
+pub fn form_fields(schema: &Value) -> Vec<FormField> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<FormField> = properties
+        .iter()
+        .map(|(name, property)| {
+            let kind = field_kind(property);
+            let value = match property.get("default") {
+                Some(Value::String(default)) => default.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(default) => default.to_string(),
+            };
+
+            FormField {
+                name: name.clone(),
+                kind,
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string),
+                required: required.contains(&name.as_str()),
+                value,
+            }
+        })
+        .collect();
+
+    // Stable, so the properties keep their order otherwise
+    fields.sort_by_key(|field| !field.required);
+    fields
+}
+
+fn field_kind(property: &Value) -> FieldKind {
+    if let Some(options) = property.get("enum").and_then(Value::as_array) {
+        let options: Option<Vec<String>> = options
+            .iter()
+            .map(|option| option.as_str().map(ToString::to_string))
+            .collect();
+        return options.map_or(FieldKind::Json, FieldKind::Enum);
+    }
+
+    // `"type": ["string", "null"]` is how optional values are usually spelled
+    let kind = match property.get("type") {
+        Some(Value::String(kind)) => Some(kind.as_str()),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        _ => None,
+    };
+
+    match kind {
+        Some("string") => FieldKind::String,
+        Some("number") => FieldKind::Number,
+        Some("integer") => FieldKind::Integer,
+        Some("boolean") => FieldKind::Boolean,
+        _ => FieldKind::Json,
+    }
+}
+
+/// Converts the filled in fields to the arguments of a `tools/call` request, skipping the optional
+/// fields that were left empty
+pub fn arguments(fields: &[FormField]) -> Result<Map<String, Value>, String> {
+    let mut arguments = Map::new();
+
+    for field in fields {
+        let raw = field.value.trim();
+        if raw.is_empty() {
+            if field.required {
+                return Err(format!("{} is required", field.name));
+            }
+            continue;
+        }
+
+        let value = match &field.kind {
+            FieldKind::String => Value::String(field.value.clone()),
+            FieldKind::Enum(_) => Value::String(raw.to_string()),
+            FieldKind::Integer => raw
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("{} must be a whole number", field.name))?,
+            FieldKind::Number => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("{} must be a number", field.name))?,
+            FieldKind::Boolean => Value::Bool(raw == "true"),
+            FieldKind::Json => serde_json::from_str(raw)
+                .map_err(|e| format!("{} must be valid JSON: {e}", field.name))?,
+        };
+        arguments.insert(field.name.clone(), value);
+    }
+
+    Ok(arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer", "default": 3 },
+                "query": { "type": "string", "description": "What to search for" },
+                "ratio": { "type": ["number", "null"] },
+                "recursive": { "type": "boolean" },
+                "sort": { "enum": ["name", "date"], "default": "name" },
+                "filter": { "type": "object" },
+            },
+            "required": ["sort", "query"],
+        })
+    }
+
+    fn fill(fields: &mut [FormField], values: &[(&str, &str)]) {
+        for (name, value) in values {
+            let field = fields.iter_mut().find(|field| field.name == *name).unwrap();
+            field.value = (*value).to_string();
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let fields = form_fields(&schema());
+        let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["query", "sort", "count", "filter", "ratio", "recursive"]
+        );
+
+        let kinds: Vec<&FieldKind> = fields.iter().map(|field| &field.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &FieldKind::String,
+                &FieldKind::Enum(vec!["name".to_string(), "date".to_string()]),
+                &FieldKind::Integer,
+                &FieldKind::Json,
+                &FieldKind::Number,
+                &FieldKind::Boolean,
+            ]
+        );
+        assert!(fields[0].required && fields[1].required && !fields[2].required);
+        assert_eq!(fields[0].description.as_deref(), Some("What to search for"));
+        assert_eq!(fields[1].value, "name");
+        assert_eq!(fields[2].value, "3");
+
+        assert_eq!(form_fields(&json!({ "type": "object" })), []);
+        assert_eq!(
+            field_kind(&json!({ "enum": [1, 2] })),
+            FieldKind::Json,
+            "Only enums of strings are picked from"
+        );
+    }
+
+    #[test]
+    fn filled_in_arguments() {
+        let mut fields = form_fields(&schema());
+        fill(
+            &mut fields,
+            &[
+                ("query", " rust "),
+                ("ratio", "0.5"),
+                ("recursive", "true"),
+                ("filter", r#"{"kind": "file"}"#),
+            ],
+        );
+        assert_eq!(
+            arguments(&fields).map(Value::Object),
+            Ok(json!({
+                "query": " rust ",
+                "sort": "name",
+                "count": 3,
+                "ratio": 0.5,
+                "recursive": true,
+                "filter": { "kind": "file" },
+            }))
+        );
+
+        // Optional fields that are left empty are left out
+        fill(
+            &mut fields,
+            &[("count", ""), ("ratio", " "), ("filter", "")],
+        );
+        assert_eq!(
+            arguments(&fields).map(Value::Object),
+            Ok(json!({ "query": " rust ", "sort": "name", "recursive": true }))
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let mut fields = form_fields(&schema());
+        assert_eq!(arguments(&fields), Err("query is required".to_string()));
+
+        fill(&mut fields, &[("query", "rust")]);
+        let errors = [
+            ("count", "2.5", "count must be a whole number"),
+            ("ratio", "half", "ratio must be a number"),
+        ];
+        for (name, value, error) in errors {
+            let mut fields = fields.clone();
+            fill(&mut fields, &[(name, value)]);
+            assert_eq!(
+                arguments(&fields),
+                Err(error.to_string()),
+                "{name} = {value}"
+            );
+        }
+
+        fill(&mut fields, &[("filter", "{kind}")]);
+        assert!(
+            arguments(&fields)
+                .unwrap_err()
+                .starts_with("filter must be valid JSON")
+        );
+    }
+}
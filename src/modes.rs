@@ -0,0 +1,108 @@
+//! Modes, which are similar to macos focus modes
+//!
+//! Each mode in the `[modes]` table can run a script when it is switched to and / or away from.
+//! There is always a `default` mode (which does nothing unless it is overridden), and the active
+//! mode is kept in a file next to the config so that it survives restarts.
+use std::{fs, path::PathBuf, process::Command, thread};
+
+use crate::{
+    app::{
+        Message,
+        apps::{AppCommand, AppData, SimpleApp},
+    },
+    config::Config,
+    utils::get_config_file_path,
+};
+
+/// The mode that always exists
+pub const DEFAULT_MODE: &str = "default";
+
+/// Where the active mode is stored
+fn state_file() -> PathBuf {
+    get_config_file_path().with_file_name("mode")
+}
+
+/// All the modes in the config, starting with the default one
+pub fn mode_names(config: &Config) -> Vec<String> {
+    std::iter::once(DEFAULT_MODE.to_string())
+        .chain(
+            config
+                .modes
+                .keys()
+                .filter(|name| *name != DEFAULT_MODE)
+                .cloned(),
+        )
+        .collect()
+}
+
+/// Reads the mode that was active when rustcast last quit, falling back to the default mode if it
+/// was removed from the config since
+pub fn load_active(config: &Config) -> String {
+    fs::read_to_string(state_file())
+        .ok()
+        .map(|mode| mode.trim().to_string())
+        .filter(|mode| config.modes.contains_key(mode))
+        .unwrap_or_else(|| DEFAULT_MODE.to_string())
+}
+
+/// Remembers the active mode for the next time rustcast starts
+pub fn save_active(mode: &str) {
+    if let Err(e) = fs::write(state_file(), mode) {
+        tracing::error!(target: "modes", "Failed to save the active mode: {e}");
+    }
+}
+
+/// Runs the exit script of the old mode, and then the enter script of the new one
+pub fn run_scripts(config: &Config, from: &str, to: &str) {
+    let scripts: Vec<String> = [
+        config.modes.get(from).and_then(|mode| mode.exit_script()),
+        config.modes.get(to).and_then(|mode| mode.enter_script()),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|script| script.replace('~', &std::env::var("HOME").unwrap_or_default()))
+    .collect();
+
+    thread::spawn(move || {
+        for script in scripts {
+            match Command::new("sh").arg("-c").arg(&script).status() {
+                Ok(status) if !status.success() => {
+                    tracing::warn!(target: "modes", "{script} exited with {status}");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!(target: "modes", "Failed to run {script}: {e}"),
+            }
+        }
+    });
+}
+
+/// The "Switch mode" results, one for each mode
+pub fn mode_apps(config: &Config, active: &str) -> Vec<SimpleApp> {
+    mode_names(config)
+        .into_iter()
+        .map(|mode| {
+            let desc = if mode == active {
+                "Current mode"
+            } else {
+                "Mode"
+            };
+            SimpleApp::new_builtin(
+                &format!("Switch mode: {mode}"),
+                &format!("switch mode {}", mode.to_lowercase()),
+                desc,
+                AppCommand::Message(Message::SwitchMode(mode)),
+            )
+        })
+        .collect()
+}
+
+/// Whether the app is one of the "Switch mode" results
+pub fn is_mode_app(app: &SimpleApp) -> bool {
+    matches!(
+        app.data,
+        AppData::Builtin {
+            command: AppCommand::Message(Message::SwitchMode(_)),
+            ..
+        }
+    )
+}
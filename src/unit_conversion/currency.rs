@@ -0,0 +1,452 @@
+//! Currency conversion with the exchange rates of a file next to the config, so that it works
+//! offline. The file is read again whenever it changes, and can be the ECB's reference rates
+//! (`eurofxref-daily.xml`, saved as `rates.xml`), or a TOML (`rates.toml`) or JSON (`rates.json`)
+//! table of rates against a base currency:
+//!
+//! ```toml
+//! base = "EUR"
+//! date = "2025-01-31"
+//!
+//! [rates]
+//! USD = 1.0393
+//! GBP = 0.8370
+//! ```
+//!
+//! Currencies are units whose dimension is [`Dimension::CURRENCY`], and whose scale is what one
+//! of them is worth in the base currency.
+use std::{borrow::Cow, collections::HashMap, ffi::OsStr, fs, path::Path, time::Duration};
+
+use iced::{
+    futures::{SinkExt, Stream},
+    stream,
+};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    app::Message,
+    unit_conversion::unit::{Dimension, Unit},
+    utils::get_config_file_path,
+};
+
+/// The files the rates are read from, next to the config, in the order they're looked for
+const RATES_FILES: [&str; 3] = ["rates.xml", "rates.toml", "rates.json"];
+
+/// How often the rates file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The currencies amounts are converted to when no target is given, after the rates' base
+const COMMON_CURRENCIES: [&str; 8] = ["USD", "EUR", "GBP", "JPY", "CNY", "CHF", "CAD", "AUD"];
+
+/// Currency symbols and the currencies they stand for, with the longer ones first so that `US$`
+/// isn't read as `$`
+const SYMBOLS: [(&str, &str); 22] = [
+    ("US$", "USD"),
+    ("HK$", "HKD"),
+    ("NZ$", "NZD"),
+    ("A$", "AUD"),
+    ("C$", "CAD"),
+    ("S$", "SGD"),
+    ("R$", "BRL"),
+    ("zł", "PLN"),
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("₺", "TRY"),
+    ("₪", "ILS"),
+    ("฿", "THB"),
+    ("₱", "PHP"),
+    ("₴", "UAH"),
+    ("₫", "VND"),
+    ("₦", "NGN"),
+];
+
+/// Currency names (in the singular, or irregular plurals) and the currencies they stand for
+const NAMES: [(&str, &str); 27] = [
+    ("dollar", "USD"),
+    ("euro", "EUR"),
+    ("pound", "GBP"),
+    ("sterling", "GBP"),
+    ("yen", "JPY"),
+    ("yuan", "CNY"),
+    ("renminbi", "CNY"),
+    ("franc", "CHF"),
+    ("rupee", "INR"),
+    ("won", "KRW"),
+    ("ruble", "RUB"),
+    ("rouble", "RUB"),
+    ("real", "BRL"),
+    ("reais", "BRL"),
+    ("peso", "MXN"),
+    ("zloty", "PLN"),
+    ("forint", "HUF"),
+    ("lira", "TRY"),
+    ("rand", "ZAR"),
+    ("shekel", "ILS"),
+    ("baht", "THB"),
+    ("ringgit", "MYR"),
+    ("rupiah", "IDR"),
+    ("koruna", "CZK"),
+    ("krona", "SEK"),
+    ("kronor", "SEK"),
+    ("krone", "NOK"),
+];
+
+/// Exchange rates against a base currency, as of a date
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Rates {
+    pub base: String,
+    pub date: Option<String>,
+    /// How much of each currency one of the base currency is worth, by ISO code
+    rates: HashMap<String, f64>,
+}
+
+impl Rates {
+    /// Reads the rates in a file, in the format its extension says (TOML if it's neither `xml`
+    /// nor `json`)
+    pub fn read(path: &Path) -> Result<Rates, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let rates = match path.extension().and_then(OsStr::to_str) {
+            Some("xml") => Rates::parse_ecb(&content)?,
+            Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+            _ => toml::from_str(&content).map_err(|e| e.to_string())?,
+        };
+        Ok(rates.normalized())
+    }
+
+    /// Parses the ECB's reference rates, which are against the euro. Of a file with the rates of
+    /// several days (`eurofxref-hist.xml`), only the first (and latest) day's are read.
+    fn parse_ecb(xml: &str) -> Result<Rates, String> {
+        let time = Regex::new(r#"time\s*=\s*['"]([0-9-]+)['"]"#).map_err(|e| e.to_string())?;
+        let rate =
+            Regex::new(r#"currency\s*=\s*['"]([A-Za-z]{3})['"]\s+rate\s*=\s*['"]([0-9.]+)['"]"#)
+                .map_err(|e| e.to_string())?;
+
+        let mut days = time.captures_iter(xml);
+        let date = days.next().map(|day| day[1].to_string());
+        let end = days
+            .next()
+            .map_or(xml.len(), |day| day.get(0).map_or(0, |m| m.start()));
+        let rates: HashMap<String, f64> = rate
+            .captures_iter(&xml[..end])
+            .filter_map(|rate| Some((rate[1].to_string(), rate[2].parse().ok()?)))
+            .collect();
+        if rates.is_empty() {
+            return Err("No exchange rates found".to_string());
+        }
+
+        Ok(Rates {
+            base: "EUR".to_string(),
+            date,
+            rates,
+        })
+    }
+
+    /// The rates with upper case codes, the base currency, and without rates that aren't positive
+    fn normalized(self) -> Rates {
+        let base = self.base.trim().to_uppercase();
+        let mut rates: HashMap<String, f64> = self
+            .rates
+            .into_iter()
+            .filter(|(_, rate)| rate.is_finite() && *rate > 0.)
+            .map(|(code, rate)| (code.trim().to_uppercase(), rate))
+            .collect();
+        if !base.is_empty() {
+            rates.insert(base.clone(), 1.);
+        }
+        Rates {
+            base,
+            date: self.date,
+            rates,
+        }
+    }
+
+    /// What the results of conversions with these rates say they're based on
+    pub fn describe(&self) -> String {
+        match &self.date {
+            Some(date) => format!("Exchange rates of {date}"),
+            None => "Exchange rates".to_string(),
+        }
+    }
+
+    /// Finds a currency by its ISO code (`usd`), its symbol (`$`) or its name (`dollars`), if
+    /// there's a rate for it
+    pub fn currency(&self, text: &str) -> Option<Unit> {
+        let text = text.trim();
+        let lowercase = text.to_lowercase();
+        let singular = lowercase.strip_suffix('s').unwrap_or(&lowercase);
+        let code = SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == text)
+            .or_else(|| {
+                NAMES
+                    .iter()
+                    .find(|(name, _)| *name == lowercase || *name == singular)
+            })
+            .map_or_else(|| text.to_uppercase(), |(_, code)| code.to_string());
+
+        let rate = self.rates.get(&code)?;
+        Some(Unit {
+            name: code,
+            scale: 1. / rate,
+            offset: 0.,
+            dimension: Dimension::CURRENCY,
+        })
+    }
+
+    /// Parses a unit (see [`Unit::parse`]), or a currency if it isn't one
+    pub fn parse_unit(&self, text: &str) -> Option<Unit> {
+        Unit::parse(text).or_else(|| self.currency(text))
+    }
+
+    /// The currencies an amount is converted to when no target is given: the base currency and
+    /// then the common ones
+    pub fn default_targets(&self, source: &Unit, max_results: usize) -> Vec<Unit> {
+        let mut targets: Vec<Unit> = Vec::new();
+        for code in std::iter::once(self.base.as_str()).chain(COMMON_CURRENCIES) {
+            if let Some(unit) = self.currency(code)
+                && unit.name != source.name
+                && !targets.contains(&unit)
+            {
+                targets.push(unit);
+            }
+        }
+        targets.truncate(max_results);
+        targets
+    }
+}
+
+/// Writes the currency symbols in a query as ISO codes after their amounts, so that `$20 + €15`
+/// can be calculated as `20 USD + 15 EUR`
+pub fn normalize(query: &str) -> Cow<'_, str> {
+    if !SYMBOLS.iter().any(|(symbol, _)| query.contains(symbol)) {
+        return Cow::Borrowed(query);
+    }
+
+    let mut normalized = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some(c) = rest.chars().next() {
+        let Some((symbol, code)) = SYMBOLS.iter().find(|(symbol, _)| rest.starts_with(symbol))
+        else {
+            normalized.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        rest = rest[symbol.len()..].trim_start();
+        let amount_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '_')))
+            .unwrap_or(rest.len());
+        let (amount, after) = rest.split_at(amount_end);
+        if amount.is_empty() {
+            normalized.push_str(&format!(" {code} "));
+        } else {
+            normalized.push_str(&format!("{amount} {code}"));
+        }
+        rest = after;
+    }
+    Cow::Owned(normalized)
+}
+
+/// The subscription that loads the rates file, and loads it again whenever it changes
+pub fn watch() -> impl Stream<Item = Message> {
+    stream::channel(1, async |mut output| {
+        let mut last_modified = None;
+        loop {
+            let file = RATES_FILES
+                .iter()
+                .map(|name| get_config_file_path().with_file_name(name))
+                .find(|path| path.is_file());
+            let modified = file.as_ref().map(|path| {
+                let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+                (path.clone(), modified)
+            });
+
+            if last_modified.as_ref() != Some(&modified) {
+                last_modified = Some(modified);
+                let rates = match file {
+                    Some(path) => Rates::read(&path).unwrap_or_else(|e| {
+                        tracing::warn!(target: "currency", "Failed to read {}: {e}", path.display());
+                        Rates::default()
+                    }),
+                    None => Rates::default(),
+                };
+                tracing::debug!(target: "currency", "Loaded {} exchange rates", rates.rates.len());
+                output.send(Message::RatesLoaded(rates)).await.ok();
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        functions::{
+            calc_quantity::calculate_quantity,
+            calc_value::DecimalSeparator,
+            calculator::{AngleUnit, Env},
+        },
+        unit_conversion::{Preferences, UnitSystem, convert_query},
+    };
+
+    const ECB: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01">
+  <Cube>
+    <Cube time='2025-01-31'>
+      <Cube currency='USD' rate='1.0393'/>
+      <Cube currency='JPY' rate='160.75'/>
+      <Cube currency='GBP' rate='0.8370'/>
+    </Cube>
+    <Cube time='2025-01-30'>
+      <Cube currency='USD' rate='1.0426'/>
+      <Cube currency='CHF' rate='0.9448'/>
+    </Cube>
+  </Cube>
+</gesmes:Envelope>"#;
+
+    #[test]
+    fn rates_files() {
+        let rates = Rates::parse_ecb(ECB).unwrap().normalized();
+        assert_eq!(rates.base, "EUR");
+        assert_eq!(rates.date.as_deref(), Some("2025-01-31"));
+        assert_eq!(rates.rates.len(), 4);
+        assert_eq!(rates.rates["USD"], 1.0393);
+        assert!(rates.currency("chf").is_none());
+
+        let toml: Rates = toml::from_str(
+            "base = \"usd\"\ndate = \"2025-01-31\"\n[rates]\neur = 0.96\nJPY = 154.7\nXYZ = 0\n",
+        )
+        .unwrap();
+        let toml = toml.normalized();
+        assert_eq!(toml.rates.len(), 3);
+        assert_eq!(toml.currency("€").unwrap().scale, 1. / 0.96);
+
+        let json: Rates =
+            serde_json::from_str(r#"{"base": "EUR", "rates": {"USD": 1.04}}"#).unwrap();
+        assert_eq!(json.normalized().describe(), "Exchange rates");
+        assert!(Rates::parse_ecb("<Cube/>").is_err());
+    }
+
+    #[test]
+    fn currencies() {
+        let rates = Rates::parse_ecb(ECB).unwrap().normalized();
+        for (text, code) in [
+            ("usd", "USD"),
+            ("JPY", "JPY"),
+            ("$", "USD"),
+            ("£", "GBP"),
+            ("euros", "EUR"),
+            ("Dollars", "USD"),
+            ("yen", "JPY"),
+            ("sterling", "GBP"),
+        ] {
+            assert_eq!(
+                rates.currency(text).map(|unit| unit.name).as_deref(),
+                Some(code)
+            );
+        }
+        assert!(rates.currency("m").is_none());
+        assert_eq!(
+            rates.parse_unit("pounds").unwrap().dimension,
+            Dimension::MASS
+        );
+
+        let usd = rates.currency("usd").unwrap();
+        let names: Vec<String> = rates
+            .default_targets(&usd, 3)
+            .into_iter()
+            .map(|unit| unit.name)
+            .collect();
+        assert_eq!(names, ["EUR", "GBP", "JPY"]);
+    }
+
+    #[test]
+    fn conversions() {
+        let rates = Rates::parse_ecb(ECB).unwrap().normalized();
+        let no_targets = HashMap::new();
+        let preferences = Preferences {
+            system: UnitSystem::Metric,
+            targets: &no_targets,
+            max_results: 4,
+        };
+        let convert = |query: &str| {
+            convert_query(query, &preferences, &rates)
+                .unwrap_or_else(|| panic!("{query} didn't convert"))
+                .into_iter()
+                .map(|result| (result.target_unit.name, result.target_value))
+                .collect::<Vec<_>>()
+        };
+
+        let cases = [
+            ("100 usd to eur", "EUR", 100. / 1.0393),
+            ("$100 in €", "EUR", 100. / 1.0393),
+            ("100 pounds to yen", "JPY", 100. / 0.8370 * 160.75),
+            ("50 euros as gbp", "GBP", 50. * 0.8370),
+        ];
+        for (query, code, expected) in cases {
+            let (name, value) = convert(query).remove(0);
+            assert_eq!(name, code, "{query}");
+            assert!((value - expected).abs() < 1e-9, "{query}: {value}");
+        }
+
+        let names: Vec<String> = convert("£20").into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["EUR", "USD", "JPY"]);
+        assert_eq!(convert("5 pounds to kg")[0].0, "kg");
+        assert!(convert_query("100 usd to kg", &preferences, &rates).is_none());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let rates = Rates::parse_ecb(ECB).unwrap().normalized();
+        let variables = HashMap::new();
+        let env = Env {
+            angle_unit: AngleUnit::Radians,
+            variables: &variables,
+        };
+        let calculate = |query: &str| {
+            calculate_quantity(query, DecimalSeparator::Point, &env, &rates)
+                .unwrap_or_else(|| panic!("{query} isn't a calculation"))
+                .map(|(value, unit)| (value, unit.map(|unit| unit.name)))
+                .map_err(|error| error.to_string())
+        };
+
+        let cases = [
+            (
+                "$20 + €15 in gbp",
+                20. / 1.0393 * 0.8370 + 15. * 0.8370,
+                "GBP",
+            ),
+            ("100 usd * 3 to eur", 300. / 1.0393, "EUR"),
+            ("(10 euros + 5 eur) / 2", 7.5, "EUR"),
+            ("¥1000 / 4 in usd", 250. / 160.75 * 1.0393, "USD"),
+        ];
+        for (query, expected, code) in cases {
+            let (value, unit) = calculate(query).unwrap_or_else(|error| panic!("{query}: {error}"));
+            assert!((value - expected).abs() < 1e-9, "{query}: {value}");
+            assert_eq!(unit.as_deref(), Some(code), "{query}");
+        }
+        assert_eq!(
+            calculate("$20 + 3 m"),
+            Err("Can't add currency and length".to_string())
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        let cases = [
+            ("$100 to €", "100 USD to  EUR "),
+            ("€20 + £15.50 in US$", "20 EUR + 15.50 GBP in  USD "),
+            ("100 usd to eur", "100 usd to eur"),
+            ("-$ 5", "-5 USD"),
+        ];
+        for (query, normalized) in cases {
+            assert_eq!(normalize(query), normalized, "{query}");
+        }
+    }
+}
@@ -1,10 +1,20 @@
-use crate::unit_conversion::UnitCategory;
+use std::f64::consts::{PI, TAU};
+
+use crate::unit_conversion::{UnitCategory, UnitSystem};
+
+const ANY: &[UnitSystem] = &[];
+const METRIC: &[UnitSystem] = &[UnitSystem::Metric];
+const CUSTOMARY: &[UnitSystem] = &[UnitSystem::Imperial, UnitSystem::Us];
+const US: &[UnitSystem] = &[UnitSystem::Us];
+const IMPERIAL: &[UnitSystem] = &[UnitSystem::Imperial];
 
 #[derive(Debug, Clone, Copy)]
 pub struct UnitDef {
     pub name: &'static str,
     pub aliases: &'static [&'static str],
     pub category: UnitCategory,
+    /// The unit systems the unit belongs to, or none if it's used in all of them (like seconds)
+    pub systems: &'static [UnitSystem],
     pub scale: f64,
     pub offset: f64,
 }
@@ -21,6 +31,7 @@ pub const UNITS: &[UnitDef] = &[
             "millimetres",
         ],
         category: UnitCategory::Length,
+        systems: METRIC,
         scale: 0.001,
         offset: 0.0,
     },
@@ -34,6 +45,7 @@ pub const UNITS: &[UnitDef] = &[
             "centimetres",
         ],
         category: UnitCategory::Length,
+        systems: METRIC,
         scale: 0.01,
         offset: 0.0,
     },
@@ -41,6 +53,7 @@ pub const UNITS: &[UnitDef] = &[
         name: "m",
         aliases: &["m", "meter", "metre", "meters", "metres"],
         category: UnitCategory::Length,
+        systems: METRIC,
         scale: 1.0,
         offset: 0.0,
     },
@@ -48,20 +61,23 @@ pub const UNITS: &[UnitDef] = &[
         name: "km",
         aliases: &["km", "kilometer", "kilometre", "kilometers", "kilometres"],
         category: UnitCategory::Length,
+        systems: METRIC,
         scale: 1000.0,
         offset: 0.0,
     },
     UnitDef {
         name: "in",
-        aliases: &["in", "inch", "inches"],
+        aliases: &["in", "″", "inch", "inches"],
         category: UnitCategory::Length,
+        systems: CUSTOMARY,
         scale: 0.0254,
         offset: 0.0,
     },
     UnitDef {
         name: "ft",
-        aliases: &["ft", "foot", "feet"],
+        aliases: &["ft", "′", "foot", "feet"],
         category: UnitCategory::Length,
+        systems: CUSTOMARY,
         scale: 0.3048,
         offset: 0.0,
     },
@@ -69,6 +85,7 @@ pub const UNITS: &[UnitDef] = &[
         name: "yd",
         aliases: &["yd", "yard", "yards"],
         category: UnitCategory::Length,
+        systems: CUSTOMARY,
         scale: 0.9144,
         offset: 0.0,
     },
@@ -76,46 +93,52 @@ pub const UNITS: &[UnitDef] = &[
         name: "mi",
         aliases: &["mi", "mile", "miles"],
         category: UnitCategory::Length,
+        systems: CUSTOMARY,
         scale: 1609.344,
         offset: 0.0,
     },
-    // Mass (base: gram)
+    // Mass (base: kilogram)
     UnitDef {
         name: "mg",
         aliases: &["mg", "milligram", "milligrams"],
         category: UnitCategory::Mass,
-        scale: 0.001,
+        systems: METRIC,
+        scale: 1e-6,
         offset: 0.0,
     },
     UnitDef {
         name: "g",
         aliases: &["g", "gram", "grams"],
         category: UnitCategory::Mass,
-        scale: 1.0,
+        systems: METRIC,
+        scale: 0.001,
         offset: 0.0,
     },
     UnitDef {
         name: "kg",
         aliases: &["kg", "kilogram", "kilograms"],
         category: UnitCategory::Mass,
-        scale: 1000.0,
+        systems: METRIC,
+        scale: 1.0,
         offset: 0.0,
     },
     UnitDef {
         name: "oz",
         aliases: &["oz", "ounce", "ounces"],
         category: UnitCategory::Mass,
-        scale: 28.349_523_125,
+        systems: CUSTOMARY,
+        scale: 0.028_349_523_125,
         offset: 0.0,
     },
     UnitDef {
         name: "lb",
         aliases: &["lb", "lbs", "pound", "pounds"],
         category: UnitCategory::Mass,
-        scale: 453.592_37,
+        systems: CUSTOMARY,
+        scale: 0.453_592_37,
         offset: 0.0,
     },
-    // Volume (base: liter)
+    // Volume (base: cubic meter)
     UnitDef {
         name: "ml",
         aliases: &[
@@ -126,85 +149,838 @@ pub const UNITS: &[UnitDef] = &[
             "millilitres",
         ],
         category: UnitCategory::Volume,
-        scale: 0.001,
+        systems: METRIC,
+        scale: 1e-6,
         offset: 0.0,
     },
     UnitDef {
         name: "l",
         aliases: &["l", "liter", "litre", "liters", "litres"],
         category: UnitCategory::Volume,
-        scale: 1.0,
+        systems: METRIC,
+        scale: 0.001,
         offset: 0.0,
     },
     UnitDef {
         name: "tsp",
         aliases: &["tsp", "teaspoon", "teaspoons"],
         category: UnitCategory::Volume,
-        scale: 0.004_928_921_593_75,
+        systems: US,
+        scale: 4.928_921_593_75e-6,
         offset: 0.0,
     },
     UnitDef {
         name: "tbsp",
         aliases: &["tbsp", "tablespoon", "tablespoons"],
         category: UnitCategory::Volume,
-        scale: 0.014_786_764_781_25,
+        systems: US,
+        scale: 1.478_676_478_125e-5,
         offset: 0.0,
     },
     UnitDef {
         name: "floz",
         aliases: &["floz", "fl-oz", "fl_oz", "fluidounce", "fluidounces"],
         category: UnitCategory::Volume,
-        scale: 0.029_573_529_562_5,
+        systems: US,
+        scale: 2.957_352_956_25e-5,
         offset: 0.0,
     },
     UnitDef {
         name: "cup",
         aliases: &["cup", "cups"],
         category: UnitCategory::Volume,
-        scale: 0.236_588_236_5,
+        systems: US,
+        scale: 2.365_882_365e-4,
         offset: 0.0,
     },
     UnitDef {
         name: "pt",
         aliases: &["pt", "pint", "pints"],
         category: UnitCategory::Volume,
-        scale: 0.473_176_473,
+        systems: US,
+        scale: 4.731_764_73e-4,
         offset: 0.0,
     },
     UnitDef {
         name: "qt",
         aliases: &["qt", "quart", "quarts"],
         category: UnitCategory::Volume,
-        scale: 0.946_352_946,
+        systems: US,
+        scale: 9.463_529_46e-4,
         offset: 0.0,
     },
     UnitDef {
         name: "gal",
         aliases: &["gal", "gallon", "gallons"],
         category: UnitCategory::Volume,
-        scale: 3.785_411_784,
+        systems: US,
+        scale: 0.003_785_411_784,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "imp floz",
+        aliases: &["impfloz", "ukfloz"],
+        category: UnitCategory::Volume,
+        systems: IMPERIAL,
+        scale: 2.841_306_25e-5,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "imp pt",
+        aliases: &["imppt", "ukpt", "imperial-pint", "imperial-pints"],
+        category: UnitCategory::Volume,
+        systems: IMPERIAL,
+        scale: 5.682_612_5e-4,
         offset: 0.0,
     },
-    // Temperature (base: celsius)
+    UnitDef {
+        name: "imp gal",
+        aliases: &["impgal", "ukgal", "imperial-gallon", "imperial-gallons"],
+        category: UnitCategory::Volume,
+        systems: IMPERIAL,
+        scale: 0.004_546_09,
+        offset: 0.0,
+    },
+    // Temperature (base: kelvin)
     UnitDef {
         name: "c",
-        aliases: &["c", "celsius", "centigrade"],
+        aliases: &["c", "°c", "℃", "celsius", "centigrade"],
         category: UnitCategory::Temperature,
+        systems: METRIC,
         scale: 1.0,
-        offset: 0.0,
+        offset: 273.15,
     },
     UnitDef {
         name: "f",
-        aliases: &["f", "fahrenheit"],
+        aliases: &["f", "°f", "℉", "fahrenheit"],
         category: UnitCategory::Temperature,
+        systems: CUSTOMARY,
         scale: 5.0 / 9.0,
-        offset: -32.0,
+        offset: 459.67,
     },
     UnitDef {
         name: "k",
         aliases: &["k", "kelvin", "kelvins"],
         category: UnitCategory::Temperature,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    // Time (base: second)
+    UnitDef {
+        name: "ns",
+        aliases: &["ns", "nanosecond", "nanoseconds"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 1e-9,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "µs",
+        aliases: &["µs", "us", "microsecond", "microseconds"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 1e-6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "ms",
+        aliases: &["ms", "millisecond", "milliseconds"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 0.001,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "s",
+        aliases: &["s", "sec", "secs", "second", "seconds"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "min",
+        aliases: &["min", "mins", "minute", "minutes"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 60.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "h",
+        aliases: &["h", "hr", "hrs", "hour", "hours"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 3600.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "day",
+        aliases: &["day", "days", "d"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 86_400.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "week",
+        aliases: &["week", "weeks", "wk"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 604_800.0,
+        offset: 0.0,
+    },
+    // A twelfth of a (Gregorian) year
+    UnitDef {
+        name: "month",
+        aliases: &["month", "months"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 2_629_746.0,
+        offset: 0.0,
+    },
+    // A Gregorian year, of 365.2425 days
+    UnitDef {
+        name: "year",
+        aliases: &["year", "years", "yr", "yrs"],
+        category: UnitCategory::Time,
+        systems: ANY,
+        scale: 31_556_952.0,
+        offset: 0.0,
+    },
+    // Data (base: byte). Decimal prefixes are powers of 1000 (kb, mb), binary ones powers of
+    // 1024 (kib, mib). Bits are written out (kbit) since queries are lowercased
+    UnitDef {
+        name: "bit",
+        aliases: &["bit", "bits"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 0.125,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kbit",
+        aliases: &["kbit", "kbits", "kilobit", "kilobits"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 125.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "mbit",
+        aliases: &["mbit", "mbits", "megabit", "megabits"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 125_000.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "gbit",
+        aliases: &["gbit", "gbits", "gigabit", "gigabits"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 125_000_000.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "B",
+        aliases: &["b", "byte", "bytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kB",
+        aliases: &["kb", "kilobyte", "kilobytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MB",
+        aliases: &["mb", "megabyte", "megabytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "GB",
+        aliases: &["gb", "gigabyte", "gigabytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1e9,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "TB",
+        aliases: &["tb", "terabyte", "terabytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1e12,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "PB",
+        aliases: &["pb", "petabyte", "petabytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1e15,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "KiB",
+        aliases: &["kib", "kibibyte", "kibibytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1_024.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MiB",
+        aliases: &["mib", "mebibyte", "mebibytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1_048_576.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "GiB",
+        aliases: &["gib", "gibibyte", "gibibytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1_073_741_824.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "TiB",
+        aliases: &["tib", "tebibyte", "tebibytes"],
+        category: UnitCategory::Data,
+        systems: ANY,
+        scale: 1_099_511_627_776.0,
+        offset: 0.0,
+    },
+    // Speed (base: meter per second)
+    UnitDef {
+        name: "m/s",
+        aliases: &["m/s", "mps"],
+        category: UnitCategory::Speed,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "km/h",
+        aliases: &["km/h", "kph", "kmh", "kmph"],
+        category: UnitCategory::Speed,
+        systems: METRIC,
+        scale: 1.0 / 3.6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "ft/s",
+        aliases: &["ft/s", "fps"],
+        category: UnitCategory::Speed,
+        systems: CUSTOMARY,
+        scale: 0.3048,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "mph",
+        aliases: &["mph", "mi/h"],
+        category: UnitCategory::Speed,
+        systems: CUSTOMARY,
+        scale: 0.447_04,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kn",
+        aliases: &["kn", "kt", "knot", "knots"],
+        category: UnitCategory::Speed,
+        systems: ANY,
+        scale: 1852.0 / 3600.0,
+        offset: 0.0,
+    },
+    // Area (base: square meter)
+    UnitDef {
+        name: "mm²",
+        aliases: &["mm²", "mm2", "sqmm"],
+        category: UnitCategory::Area,
+        systems: METRIC,
+        scale: 1e-6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "cm²",
+        aliases: &["cm²", "cm2", "sqcm"],
+        category: UnitCategory::Area,
+        systems: METRIC,
+        scale: 1e-4,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "m²",
+        aliases: &["m²", "m2", "sqm"],
+        category: UnitCategory::Area,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "ha",
+        aliases: &["ha", "hectare", "hectares"],
+        category: UnitCategory::Area,
+        systems: METRIC,
+        scale: 10_000.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "km²",
+        aliases: &["km²", "km2", "sqkm"],
+        category: UnitCategory::Area,
+        systems: METRIC,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "in²",
+        aliases: &["in²", "in2", "sqin"],
+        category: UnitCategory::Area,
+        systems: CUSTOMARY,
+        scale: 0.000_645_16,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "ft²",
+        aliases: &["ft²", "ft2", "sqft"],
+        category: UnitCategory::Area,
+        systems: CUSTOMARY,
+        scale: 0.092_903_04,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "yd²",
+        aliases: &["yd²", "yd2", "sqyd"],
+        category: UnitCategory::Area,
+        systems: CUSTOMARY,
+        scale: 0.836_127_36,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "acre",
+        aliases: &["acre", "acres", "ac"],
+        category: UnitCategory::Area,
+        systems: CUSTOMARY,
+        scale: 4_046.856_422_4,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "mi²",
+        aliases: &["mi²", "mi2", "sqmi"],
+        category: UnitCategory::Area,
+        systems: CUSTOMARY,
+        scale: 2_589_988.110_336,
+        offset: 0.0,
+    },
+    // Energy (base: joule)
+    UnitDef {
+        name: "J",
+        aliases: &["j", "joule", "joules"],
+        category: UnitCategory::Energy,
+        systems: METRIC,
         scale: 1.0,
-        offset: -273.15,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kJ",
+        aliases: &["kj", "kilojoule", "kilojoules"],
+        category: UnitCategory::Energy,
+        systems: METRIC,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MJ",
+        aliases: &["mj", "megajoule", "megajoules"],
+        category: UnitCategory::Energy,
+        systems: METRIC,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "cal",
+        aliases: &["cal", "calorie", "calories"],
+        category: UnitCategory::Energy,
+        systems: ANY,
+        scale: 4.184,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kcal",
+        aliases: &["kcal", "kilocalorie", "kilocalories"],
+        category: UnitCategory::Energy,
+        systems: ANY,
+        scale: 4_184.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "Wh",
+        aliases: &["wh", "watt-hour", "watt-hours"],
+        category: UnitCategory::Energy,
+        systems: METRIC,
+        scale: 3_600.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kWh",
+        aliases: &["kwh", "kilowatt-hour", "kilowatt-hours"],
+        category: UnitCategory::Energy,
+        systems: METRIC,
+        scale: 3.6e6,
+        offset: 0.0,
     },
+    UnitDef {
+        name: "eV",
+        aliases: &["ev", "electronvolt", "electronvolts"],
+        category: UnitCategory::Energy,
+        systems: ANY,
+        scale: 1.602_176_634e-19,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "BTU",
+        aliases: &["btu", "btus"],
+        category: UnitCategory::Energy,
+        systems: CUSTOMARY,
+        scale: 1_055.055_852_62,
+        offset: 0.0,
+    },
+    // Pressure (base: pascal)
+    UnitDef {
+        name: "Pa",
+        aliases: &["pa", "pascal", "pascals"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "hPa",
+        aliases: &["hpa", "hectopascal", "hectopascals"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 100.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kPa",
+        aliases: &["kpa", "kilopascal", "kilopascals"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MPa",
+        aliases: &["mpa", "megapascal", "megapascals"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "mbar",
+        aliases: &["mbar", "millibar", "millibars"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 100.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "bar",
+        aliases: &["bar", "bars"],
+        category: UnitCategory::Pressure,
+        systems: METRIC,
+        scale: 1e5,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "psi",
+        aliases: &["psi"],
+        category: UnitCategory::Pressure,
+        systems: CUSTOMARY,
+        scale: 6_894.757_293_168,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "atm",
+        aliases: &["atm", "atmosphere", "atmospheres"],
+        category: UnitCategory::Pressure,
+        systems: ANY,
+        scale: 101_325.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "mmHg",
+        aliases: &["mmhg", "torr"],
+        category: UnitCategory::Pressure,
+        systems: ANY,
+        scale: 133.322_387_415,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "inHg",
+        aliases: &["inhg"],
+        category: UnitCategory::Pressure,
+        systems: CUSTOMARY,
+        scale: 3_386.388_666,
+        offset: 0.0,
+    },
+    // Angle (base: radian)
+    UnitDef {
+        name: "rad",
+        aliases: &["rad", "rads", "radian", "radians"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "deg",
+        aliases: &["deg", "°", "degree", "degrees"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: PI / 180.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "grad",
+        aliases: &["grad", "gon", "gradian", "gradians"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: PI / 200.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "arcmin",
+        aliases: &["arcmin", "arcminute", "arcminutes"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: PI / 10_800.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "arcsec",
+        aliases: &["arcsec", "arcsecond", "arcseconds"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: PI / 648_000.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "turn",
+        aliases: &["turn", "turns", "rev", "revolution", "revolutions"],
+        category: UnitCategory::Angle,
+        systems: ANY,
+        scale: TAU,
+        offset: 0.0,
+    },
+    // Power (base: watt)
+    UnitDef {
+        name: "W",
+        aliases: &["w", "watt", "watts"],
+        category: UnitCategory::Power,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kW",
+        aliases: &["kw", "kilowatt", "kilowatts"],
+        category: UnitCategory::Power,
+        systems: METRIC,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MW",
+        aliases: &["mw", "megawatt", "megawatts"],
+        category: UnitCategory::Power,
+        systems: METRIC,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "GW",
+        aliases: &["gw", "gigawatt", "gigawatts"],
+        category: UnitCategory::Power,
+        systems: METRIC,
+        scale: 1e9,
+        offset: 0.0,
+    },
+    // Mechanical horsepower
+    UnitDef {
+        name: "hp",
+        aliases: &["hp", "horsepower"],
+        category: UnitCategory::Power,
+        systems: CUSTOMARY,
+        scale: 745.699_871_582_270_2,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "BTU/h",
+        aliases: &["btu/h"],
+        category: UnitCategory::Power,
+        systems: CUSTOMARY,
+        scale: 0.293_071_070_172_222,
+        offset: 0.0,
+    },
+    // Force (base: newton)
+    UnitDef {
+        name: "N",
+        aliases: &["n", "newton", "newtons"],
+        category: UnitCategory::Force,
+        systems: METRIC,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kN",
+        aliases: &["kilonewton", "kilonewtons"],
+        category: UnitCategory::Force,
+        systems: METRIC,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "dyn",
+        aliases: &["dyn", "dyne", "dynes"],
+        category: UnitCategory::Force,
+        systems: METRIC,
+        scale: 1e-5,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kgf",
+        aliases: &["kgf", "kilogram-force"],
+        category: UnitCategory::Force,
+        systems: METRIC,
+        scale: 9.806_65,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "lbf",
+        aliases: &["lbf", "pound-force"],
+        category: UnitCategory::Force,
+        systems: CUSTOMARY,
+        scale: 4.448_221_615_260_5,
+        offset: 0.0,
+    },
+    // Frequency (base: hertz)
+    UnitDef {
+        name: "Hz",
+        aliases: &["hz", "hertz"],
+        category: UnitCategory::Frequency,
+        systems: ANY,
+        scale: 1.0,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "kHz",
+        aliases: &["khz", "kilohertz"],
+        category: UnitCategory::Frequency,
+        systems: ANY,
+        scale: 1e3,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "MHz",
+        aliases: &["mhz", "megahertz"],
+        category: UnitCategory::Frequency,
+        systems: ANY,
+        scale: 1e6,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "GHz",
+        aliases: &["ghz", "gigahertz"],
+        category: UnitCategory::Frequency,
+        systems: ANY,
+        scale: 1e9,
+        offset: 0.0,
+    },
+    UnitDef {
+        name: "rpm",
+        aliases: &["rpm", "rev/min"],
+        category: UnitCategory::Frequency,
+        systems: ANY,
+        scale: 1.0 / 60.0,
+        offset: 0.0,
+    },
+];
+
+/// SI prefixes and their factors. `u` is micro too, since `µ` is hard to type
+pub const SI_PREFIXES: &[(&str, f64)] = &[
+    ("Q", 1e30),
+    ("R", 1e27),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+    ("z", 1e-21),
+    ("y", 1e-24),
+    ("r", 1e-27),
+    ("q", 1e-30),
+];
+
+/// Binary prefixes and their factors, for units of data
+pub const BINARY_PREFIXES: &[(&str, f64)] = &[
+    ("Ki", 1_024.0),
+    ("Mi", 1_048_576.0),
+    ("Gi", 1_073_741_824.0),
+    ("Ti", 1_099_511_627_776.0),
+    ("Pi", 1_125_899_906_842_624.0),
+    ("Ei", 1_152_921_504_606_846_976.0),
+];
+
+/// The symbols that SI prefixes can be put in front of (case sensitive, so `mW` is a milliwatt and
+/// `MW` a megawatt), the name of their unit, and whether binary prefixes can be too
+pub const PREFIXABLE_UNITS: &[(&str, &str, bool)] = &[
+    ("m", "m", false),
+    ("g", "g", false),
+    ("s", "s", false),
+    ("l", "l", false),
+    ("L", "l", false),
+    ("K", "k", false),
+    ("B", "B", true),
+    ("bit", "bit", true),
+    ("Hz", "Hz", false),
+    ("N", "N", false),
+    ("Pa", "Pa", false),
+    ("bar", "bar", false),
+    ("J", "J", false),
+    ("eV", "eV", false),
+    ("Wh", "Wh", false),
+    ("W", "W", false),
+    ("cal", "cal", false),
+    ("rad", "rad", false),
 ];
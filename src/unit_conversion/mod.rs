@@ -1,57 +1,157 @@
 //! Unit conversion parsing and calculation.
 
-use crate::unit_conversion::defs::{UNITS, UnitDef};
+use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
+use crate::unit_conversion::{
+    currency::Rates,
+    defs::{UNITS, UnitDef},
+    unit::{Dimension, Unit},
+};
+
+pub mod currency;
 mod defs;
+pub mod unit;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum UnitCategory {
     Length,
     Mass,
     Volume,
     Temperature,
+    Time,
+    Data,
+    Speed,
+    Area,
+    Energy,
+    Pressure,
+    Angle,
+    Power,
+    Force,
+    Frequency,
+}
+
+impl UnitCategory {
+    pub const ALL: [UnitCategory; 14] = [
+        UnitCategory::Length,
+        UnitCategory::Mass,
+        UnitCategory::Volume,
+        UnitCategory::Temperature,
+        UnitCategory::Time,
+        UnitCategory::Data,
+        UnitCategory::Speed,
+        UnitCategory::Area,
+        UnitCategory::Energy,
+        UnitCategory::Pressure,
+        UnitCategory::Angle,
+        UnitCategory::Power,
+        UnitCategory::Force,
+        UnitCategory::Frequency,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitCategory::Length => "length",
+            UnitCategory::Mass => "mass",
+            UnitCategory::Volume => "volume",
+            UnitCategory::Temperature => "temperature",
+            UnitCategory::Time => "time",
+            UnitCategory::Data => "data",
+            UnitCategory::Speed => "speed",
+            UnitCategory::Area => "area",
+            UnitCategory::Energy => "energy",
+            UnitCategory::Pressure => "pressure",
+            UnitCategory::Angle => "angle",
+            UnitCategory::Power => "power",
+            UnitCategory::Force => "force",
+            UnitCategory::Frequency => "frequency",
+        }
+    }
+}
+
+/// The system of units that conversions without a target are shown in first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+    /// US customary units, whose volumes (like gallons) differ from imperial ones
+    Us,
+}
+
+/// Which units conversions without a target are shown in, and how many
+#[derive(Debug, Clone, Copy)]
+pub struct Preferences<'a> {
+    pub system: UnitSystem,
+    /// The unit each category is converted to first, like `mi` for lengths
+    pub targets: &'a HashMap<UnitCategory, String>,
+    pub max_results: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConversionResult {
     pub source_value: f64,
-    pub source_unit: &'static UnitDef,
+    pub source_unit: Unit,
     pub target_value: f64,
-    pub target_unit: &'static UnitDef,
+    pub target_unit: Unit,
 }
 
 #[derive(Debug, Clone)]
 struct ParsedQuery {
     value: f64,
-    source_unit: &'static UnitDef,
-    target_unit: Option<&'static UnitDef>,
+    source_unit: Unit,
+    target_unit: Option<Unit>,
 }
 
-pub fn convert_query(query: &str) -> Option<Vec<ConversionResult>> {
-    let parsed = parse_query(query)?;
-    let base_value = to_base(parsed.value, parsed.source_unit);
+/// The words between the source and target units of a conversion (`5 km to mi`)
+const TARGET_KEYWORDS: [&str; 3] = ["to", "in", "as"];
 
-    let mut results = Vec::new();
-    let targets: Vec<&UnitDef> = match parsed.target_unit {
-        Some(target) => vec![target],
-        None => UNITS
-            .iter()
-            .filter(|unit| unit.category == parsed.source_unit.category)
-            .collect(),
+/// Converts a value to the unit (or currency) the query asks for, or to the ones it's easiest to
+/// read in if it doesn't ask for one
+pub fn convert_query(
+    query: &str,
+    preferences: &Preferences,
+    rates: &Rates,
+) -> Option<Vec<ConversionResult>> {
+    let query = currency::normalize(query);
+    let same_dimension = |parsed: &ParsedQuery| {
+        parsed
+            .target_unit
+            .as_ref()
+            .is_none_or(|target| target.dimension == parsed.source_unit.dimension)
     };
+    // Names like `pounds` are units first, and currencies if they're converted to one
+    let parsed = parse_query(&query, |text| rates.parse_unit(text))
+        .filter(same_dimension)
+        .or_else(|| {
+            parse_query(&query, |text| {
+                rates.currency(text).or_else(|| Unit::parse(text))
+            })
+        })
+        .filter(same_dimension)?;
+    let base_value = parsed.source_unit.to_base(parsed.value);
 
-    for target_unit in targets {
-        if target_unit.name == parsed.source_unit.name {
-            continue;
+    let targets: Vec<Unit> = match parsed.target_unit {
+        Some(target) => vec![target],
+        None if parsed.source_unit.dimension == Dimension::CURRENCY => {
+            rates.default_targets(&parsed.source_unit, preferences.max_results)
         }
-        let target_value = from_base(base_value, target_unit);
-        results.push(ConversionResult {
+        None => default_targets(&parsed.source_unit, base_value, preferences),
+    };
+
+    let results: Vec<ConversionResult> = targets
+        .into_iter()
+        .filter(|target_unit| target_unit.name != parsed.source_unit.name)
+        .map(|target_unit| ConversionResult {
             source_value: parsed.value,
-            source_unit: parsed.source_unit,
-            target_value,
+            source_unit: parsed.source_unit.clone(),
+            target_value: target_unit.to_unit(base_value),
             target_unit,
-        });
-    }
+        })
+        .collect();
 
     if results.is_empty() {
         None
@@ -60,6 +160,70 @@ pub fn convert_query(query: &str) -> Option<Vec<ConversionResult>> {
     }
 }
 
+/// The units a value is converted to when no target is given: the one set for its category, and
+/// then the ones it's easiest to read in (between 1 and 1000, so 5 mi is 8.05 km rather than
+/// 8046720 mm). Among those, units of the preferred system come first, unless the value is in it
+/// already, in which case the other systems' units do.
+fn default_targets(source: &Unit, base_value: f64, preferences: &Preferences) -> Vec<Unit> {
+    let in_system =
+        |systems: &[UnitSystem]| systems.is_empty() || systems.contains(&preferences.system);
+    let source_in_system = UNITS
+        .iter()
+        .find(|def| def.name == source.name)
+        .is_none_or(|def| in_system(def.systems));
+
+    let category_target = UnitCategory::ALL
+        .iter()
+        .find(|category| category.dimension() == source.dimension)
+        .and_then(|category| preferences.targets.get(category))
+        .and_then(|name| Unit::parse(name))
+        .filter(|unit| unit.dimension == source.dimension && unit.name != source.name);
+
+    let mut candidates: Vec<(&UnitDef, f64)> = UNITS
+        .iter()
+        .filter(|def| def.category.dimension() == source.dimension && def.name != source.name)
+        .filter(|def| {
+            category_target
+                .as_ref()
+                .is_none_or(|unit| unit.name != def.name)
+        })
+        .map(|def| (def, Unit::new(def).to_unit(base_value)))
+        .collect();
+    // Values far from 1 to 1000 last, then the preferred (or other) systems, then the values
+    // closest to that range, and to 1
+    candidates.sort_by(|(a, a_value), (b, b_value)| {
+        let rank = |def: &UnitDef, value: f64| {
+            (
+                unreadability(value) > 1.,
+                in_system(def.systems) == source_in_system,
+            )
+        };
+        let magnitude = |value: f64| value.abs().log10().abs();
+        rank(a, *a_value)
+            .cmp(&rank(b, *b_value))
+            .then(unreadability(*a_value).total_cmp(&unreadability(*b_value)))
+            .then(magnitude(*a_value).total_cmp(&magnitude(*b_value)))
+    });
+
+    category_target
+        .into_iter()
+        .chain(candidates.into_iter().map(|(def, _)| Unit::new(def)))
+        .take(preferences.max_results)
+        .collect()
+}
+
+/// How many orders of magnitude a value is outside of 1 to 1000
+fn unreadability(value: f64) -> f64 {
+    let magnitude = value.abs().log10();
+    if value == 0. {
+        0.
+    } else if magnitude < 0. {
+        -magnitude
+    } else {
+        (magnitude - 3.).max(0.)
+    }
+}
+
 pub fn format_number(value: f64) -> String {
     let value = if value.abs() < 1e-9 { 0.0 } else { value };
     let mut s = format!("{value:.6}");
@@ -74,7 +238,9 @@ pub fn format_number(value: f64) -> String {
     s
 }
 
-fn parse_query(query: &str) -> Option<ParsedQuery> {
+/// Parses `<number> <unit>`, `<number> <unit> <unit>` and `<number> <unit> to <unit>` (or `in`
+/// / `as`), where units can be compound (`m/s^2`) and have spaces around their operators
+fn parse_query(query: &str, parse_unit: impl Fn(&str) -> Option<Unit>) -> Option<ParsedQuery> {
     let (value_str, rest) = parse_number_prefix(query)?;
     let value: f64 = value_str.parse().ok()?;
     let rest = rest.trim_start();
@@ -82,43 +248,27 @@ fn parse_query(query: &str) -> Option<ParsedQuery> {
         return None;
     }
 
-    let rest_lc = rest.to_lowercase();
-    let tokens: Vec<&str> = rest_lc.split_whitespace().collect();
-    if tokens.is_empty() {
-        return None;
-    }
-
-    let source_unit = find_unit(tokens[0])?;
-    match tokens.len() {
-        1 => Some(ParsedQuery {
-            value,
-            source_unit,
-            target_unit: None,
-        }),
-        2 => {
-            let target_unit = find_unit(tokens[1])?;
-            if target_unit.category != source_unit.category {
-                return None;
-            }
-            Some(ParsedQuery {
-                value,
-                source_unit,
-                target_unit: Some(target_unit),
-            })
-        }
-        3 if tokens[1] == "to" || tokens[1] == "in" => {
-            let target_unit = find_unit(tokens[2])?;
-            if target_unit.category != source_unit.category {
-                return None;
-            }
-            Some(ParsedQuery {
-                value,
-                source_unit,
-                target_unit: Some(target_unit),
-            })
+    let tokens: Vec<&str> = rest.split_whitespace().collect();
+    // The last keyword that isn't the first token, so that `5 in in cm` converts inches
+    let keyword = (1..tokens.len())
+        .rev()
+        .find(|i| TARGET_KEYWORDS.contains(&tokens[*i].to_lowercase().as_str()));
+    let (source, target) = match keyword {
+        Some(i) => (tokens[..i].join(" "), Some(tokens[i + 1..].join(" "))),
+        None if tokens.len() == 2 && parse_unit(rest).is_none() => {
+            (tokens[0].to_string(), Some(tokens[1].to_string()))
         }
-        _ => None,
-    }
+        None => (rest.to_string(), None),
+    };
+
+    Some(ParsedQuery {
+        value,
+        source_unit: parse_unit(&source)?,
+        target_unit: match target {
+            Some(target) => Some(parse_unit(&target)?),
+            None => None,
+        },
+    })
 }
 
 fn parse_number_prefix(s: &str) -> Option<(&str, &str)> {
@@ -157,21 +307,240 @@ fn parse_number_prefix(s: &str) -> Option<(&str, &str)> {
     Some((num, rest))
 }
 
-fn find_unit(token: &str) -> Option<&'static UnitDef> {
-    let token = token.trim();
-    if token.is_empty() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    /// Converts with the default preferences (metric, and up to 4 results)
+    fn convert(query: &str) -> Option<Vec<ConversionResult>> {
+        convert_with(query, UnitSystem::Metric, &HashMap::new())
     }
 
-    UNITS
-        .iter()
-        .find(|unit| unit.name == token || unit.aliases.contains(&token))
-}
+    fn convert_with(
+        query: &str,
+        system: UnitSystem,
+        targets: &HashMap<UnitCategory, String>,
+    ) -> Option<Vec<ConversionResult>> {
+        let preferences = Preferences {
+            system,
+            targets,
+            max_results: 4,
+        };
+        convert_query(query, &preferences, &Rates::default())
+    }
 
-fn to_base(value: f64, unit: &UnitDef) -> f64 {
-    (value + unit.offset) * unit.scale
-}
+    /// Checks that converting `value source` to each target gives the expected value (up to
+    /// rounding errors)
+    fn assert_converts(cases: &[(&str, f64)]) {
+        for (query, expected) in cases {
+            let results = convert(query).unwrap_or_else(|| panic!("{query} didn't convert"));
+            let result = results[0].target_value;
+            assert!(
+                (result - expected).abs() <= 1e-9 * expected.abs().max(1e-12),
+                "{query}: expected {expected}, got {result}"
+            );
+        }
+    }
+
+    #[test]
+    fn data() {
+        assert_converts(&[
+            ("1 kb to b", 1000.),
+            ("1 kib to b", 1024.),
+            ("1 mib to kb", 1048.576),
+            ("1 mib to kib", 1024.),
+            ("1 gb to mib", 953.674_316_406_25),
+            ("1 gib to bytes", 1_073_741_824.),
+            ("1 tib to gib", 1024.),
+            ("1 tb to gb", 1000.),
+            ("8 mbit to mb", 1.),
+            ("1 gbit to mib", 119.209_289_550_781_25),
+            ("1 byte to bits", 8.),
+        ]);
+    }
+
+    #[test]
+    fn time_and_frequency() {
+        assert_converts(&[
+            ("1 day to min", 1440.),
+            ("2 weeks in h", 336.),
+            ("1 year to days", 365.2425),
+            ("12 months to years", 1.),
+            ("1500 ms to s", 1.5),
+            ("1 µs to ns", 1000.),
+            ("60 rpm to hz", 1.),
+            ("2.4 ghz to mhz", 2400.),
+        ]);
+    }
+
+    #[test]
+    fn speed_and_area() {
+        assert_converts(&[
+            ("60 mph to km/h", 96.560_64),
+            ("36 km/h to m/s", 10.),
+            ("1 kn to km/h", 1.852),
+            ("10 ft/s to mph", 6.818_181_818_181_818),
+            ("1 acre to m2", 4_046.856_422_4),
+            ("1 ha to acres", 2.471_053_814_671_653),
+            ("1 mi2 to km²", 2.589_988_110_336),
+            ("1 sqft to cm2", 929.0304),
+        ]);
+    }
 
-fn from_base(value: f64, unit: &UnitDef) -> f64 {
-    value / unit.scale - unit.offset
+    #[test]
+    fn energy_pressure_and_power() {
+        assert_converts(&[
+            ("1 kwh to j", 3.6e6),
+            ("1 kcal to kj", 4.184),
+            ("1 wh to cal", 860.420_650_095_602_3),
+            ("1 btu to j", 1_055.055_852_62),
+            ("1 bar to psi", 14.503_773_773_375_085),
+            ("1 atm to kpa", 101.325),
+            ("1 mmhg to pa", 133.322_387_415),
+            ("1013.25 hpa to mbar", 1013.25),
+            ("1 hp to kw", 0.745_699_871_582_270_2),
+            ("1 mw to kw", 1000.),
+        ]);
+    }
+
+    #[test]
+    fn angles() {
+        assert_converts(&[
+            ("180 deg to rad", PI),
+            ("1 turn to degrees", 360.),
+            ("100 grad to deg", 90.),
+            ("1 deg to arcmin", 60.),
+            ("1 arcmin to arcsec", 60.),
+        ]);
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_converts(&[
+            ("1 µm to nm", 1000.),
+            ("1 μm to nm", 1000.),
+            ("5 um to mm", 0.005),
+            ("2.4 GHz to MHz", 2400.),
+            ("3 kN to N", 3000.),
+            ("1 mW to W", 0.001),
+            ("1 MW to W", 1e6),
+            ("1 hPa to Pa", 100.),
+            ("500 mg to g", 0.5),
+            ("1 dam to m", 10.),
+            ("1 Mibit to kbit", 1048.576),
+            ("1 GiB to MiB", 1024.),
+            ("1 EiB to PiB", 1024.),
+            ("1 mK to K", 0.001),
+        ]);
+        // Binary prefixes are only for data
+        assert!(convert("1 Kim to m").is_none());
+        assert!(convert("1 Mim to m").is_none());
+    }
+
+    #[test]
+    fn compound_units() {
+        assert_converts(&[
+            ("9.81 m/s^2 to ft/s^2", 32.185_039_370_078_74),
+            ("1 kg*m/s^2 to N", 1.),
+            ("1 kg·m²/s² to J", 1.),
+            ("1 N*m to J", 1.),
+            ("1 kW*h to MJ", 3.6),
+            ("1 m/s to km / h", 3.6),
+            ("1 m^3 to l", 1000.),
+            ("1 m3 to l", 1000.),
+            ("2 cm³ to ml", 2.),
+            ("1 s^-1 to Hz", 1.),
+            ("1 s⁻¹ to Hz", 1.),
+            ("1 g/cm3 to kg/m3", 1000.),
+            ("1 W/m2 to mW/cm2", 0.1),
+        ]);
+        assert!(convert("1 m/s to km").is_none());
+        assert!(convert("1 kg*m to N").is_none());
+        assert!(convert("1 m^ to km").is_none());
+    }
+
+    #[test]
+    fn unicode_and_temperatures() {
+        assert_converts(&[
+            ("25 °C to °F", 77.),
+            ("25 ℃ to K", 298.15),
+            ("-40 °F to °C", -40.),
+            ("0 k to c", -273.15),
+            ("12″ to cm", 30.48),
+            ("6′ to m", 1.8288),
+            ("3 m² to ft²", 32.291_731_250_129_17),
+            ("90° to rad", PI / 2.),
+        ]);
+    }
+
+    #[test]
+    fn categories_dont_mix() {
+        assert!(convert("1 kb to kw").is_none());
+        assert!(convert("1 h to hz").is_none());
+        let results = convert("1 mib").unwrap();
+        assert!(
+            results
+                .iter()
+                .all(|result| result.target_unit.dimension == UnitCategory::Data.dimension())
+        );
+    }
+
+    #[test]
+    fn default_targets() {
+        let names = |results: Vec<ConversionResult>| -> Vec<String> {
+            results
+                .into_iter()
+                .map(|result| result.target_unit.name)
+                .collect()
+        };
+        let no_targets = HashMap::new();
+
+        let miles = names(convert("5 miles").unwrap());
+        assert_eq!(miles[0], "km");
+        assert!(!miles.contains(&"mm".to_string()), "{miles:?}");
+        assert_eq!(miles.len(), 4);
+
+        let imperial = names(convert_with("10 km", UnitSystem::Imperial, &no_targets).unwrap());
+        assert_eq!(imperial[0], "mi");
+        // The value is in the preferred system already, so the other systems come first
+        assert_eq!(names(convert("20 c").unwrap())[0], "f");
+        assert_eq!(names(convert("3 l").unwrap())[0], "qt");
+        assert_eq!(
+            names(convert_with("3 l", UnitSystem::Imperial, &no_targets).unwrap())[0],
+            "imp pt"
+        );
+        assert_eq!(names(convert("90 min").unwrap())[0], "h");
+        assert_eq!(names(convert("86400 s").unwrap())[0], "day");
+
+        let targets = HashMap::from([
+            (UnitCategory::Length, "ft".to_string()),
+            (UnitCategory::Temperature, "k".to_string()),
+        ]);
+        let with_targets =
+            |query| names(convert_with(query, UnitSystem::Metric, &targets).unwrap());
+        assert_eq!(with_targets("5 km")[0], "ft");
+        assert_eq!(with_targets("20 c")[0], "k");
+        assert_eq!(with_targets("5 ft")[0], "m");
+        assert_eq!(
+            with_targets("5 km")
+                .iter()
+                .filter(|name| *name == "ft")
+                .count(),
+            1
+        );
+
+        let preferences = Preferences {
+            system: UnitSystem::Metric,
+            targets: &no_targets,
+            max_results: 1,
+        };
+        assert_eq!(
+            convert_query("1 day", &preferences, &Rates::default())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 }
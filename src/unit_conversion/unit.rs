@@ -0,0 +1,316 @@
+//! Units written in queries: single units (`km`), prefixed ones (`µm`, `GHz`, `MiB`) and products
+//! and quotients of them with exponents (`m/s^2`, `kg*m/s²`), with their dimensions.
+
+use std::fmt;
+
+use crate::unit_conversion::{
+    UnitCategory,
+    defs::{BINARY_PREFIXES, PREFIXABLE_UNITS, SI_PREFIXES, UNITS, UnitDef},
+};
+
+/// The names of the base dimensions, in the order of [`Dimension`]'s exponents, and the symbols
+/// of their base units
+const BASE_DIMENSIONS: [(&str, &str); 7] = [
+    ("length", "m"),
+    ("mass", "kg"),
+    ("time", "s"),
+    ("temperature", "K"),
+    ("data", "B"),
+    ("angle", "rad"),
+    ("currency", "¤"),
+];
+
+/// What a unit measures, as the exponents of the base dimensions (length, mass, time,
+/// temperature, data, angle and currency), so that m/s² is length¹ time⁻²
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimension([i8; 7]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 7]);
+    pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0, 0]);
+    pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0, 0]);
+    pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0, 0]);
+    pub const TEMPERATURE: Dimension = Dimension([0, 0, 0, 1, 0, 0, 0]);
+    pub const DATA: Dimension = Dimension([0, 0, 0, 0, 1, 0, 0]);
+    pub const ANGLE: Dimension = Dimension([0, 0, 0, 0, 0, 1, 0]);
+    pub const CURRENCY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 1]);
+
+    /// This dimension times another one to the power of `exponent`
+    pub fn times(self, other: Dimension, exponent: i8) -> Dimension {
+        let mut exponents = self.0;
+        for (a, b) in exponents.iter_mut().zip(other.0) {
+            *a = a.saturating_add(b.saturating_mul(exponent));
+        }
+        Dimension(exponents)
+    }
+}
+
+impl fmt::Display for Dimension {
+    /// Writes the dimension's name (`speed`), or its base dimensions if it has none
+    /// (`length time^-2`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Dimension::NONE {
+            return write!(f, "numbers without units");
+        } else if let Some(category) = UnitCategory::ALL
+            .iter()
+            .find(|category| category.dimension() == *self)
+        {
+            return write!(f, "{}", category.name());
+        }
+        let parts: Vec<String> = BASE_DIMENSIONS
+            .iter()
+            .zip(self.0)
+            .filter(|(_, exponent)| *exponent != 0)
+            .map(|((name, _), exponent)| match exponent {
+                1 => name.to_string(),
+                _ => format!("{name}^{exponent}"),
+            })
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// A unit, and how to convert values in it to the base units of its dimension:
+/// `(value + offset) * scale`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// The unit as it's shown, which is its symbol, or what was typed for compound units
+    pub name: String,
+    pub scale: f64,
+    /// Only temperatures have an offset, and only on their own (`°C`, not `J/°C`)
+    pub offset: f64,
+    pub dimension: Dimension,
+}
+
+impl Unit {
+    pub fn new(def: &UnitDef) -> Unit {
+        Unit {
+            name: def.name.to_string(),
+            scale: def.scale,
+            offset: def.offset,
+            dimension: def.category.dimension(),
+        }
+    }
+
+    /// Parses a unit: one from the table of units (`km/h`, `mph`, `°C`), a prefixed one (`µm`,
+    /// `GHz`, `KiB`), or products (`*`, `·`) and quotients (`/`) of those with exponents (`m^2`,
+    /// `m2`, `m²`, `s^-1`)
+    pub fn parse(text: &str) -> Option<Unit> {
+        let text = text.trim();
+        if let Some(unit) = find_symbol(text) {
+            return Some(unit);
+        }
+
+        let mut unit = Unit {
+            name: text.to_string(),
+            scale: 1.0,
+            offset: 0.0,
+            dimension: Dimension::NONE,
+        };
+        let mut factors = 0;
+        let mut rest = text;
+        let mut divide = false;
+        loop {
+            let end = rest
+                .find(|c: char| !is_symbol_char(c))
+                .unwrap_or(rest.len());
+            let (symbol, after) = rest.split_at(end);
+            let (exponent, after) = parse_exponent(after)?;
+            let exponent = if divide { -exponent } else { exponent };
+            let factor = find_symbol(symbol)?;
+
+            unit.scale *= factor.scale.powi(exponent.into());
+            unit.dimension = unit.dimension.times(factor.dimension, exponent);
+            if exponent == 1 {
+                unit.offset = factor.offset;
+            }
+            factors += 1;
+
+            rest = after.trim_start();
+            match rest.chars().next() {
+                None => break,
+                Some('*' | '·' | '×') => divide = false,
+                Some('/') => divide = true,
+                Some(_) => return None,
+            }
+            rest = rest[rest.chars().next()?.len_utf8()..].trim_start();
+        }
+        if factors > 1 {
+            unit.offset = 0.0;
+        }
+        Some(unit)
+    }
+
+    /// The coherent SI unit of a dimension: the named one if there is one (`N`, `m²`), or the
+    /// product of the base units (`kg*m/s^3`)
+    pub fn base(dimension: Dimension) -> Unit {
+        if let Some(def) = UNITS.iter().find(|def| {
+            def.category.dimension() == dimension && def.scale == 1.0 && def.offset == 0.0
+        }) {
+            return Unit::new(def);
+        }
+        let symbols = |positive: bool| {
+            BASE_DIMENSIONS
+                .iter()
+                .zip(dimension.0)
+                .filter(|(_, exponent)| *exponent != 0 && (*exponent > 0) == positive)
+                .map(|((_, symbol), exponent)| match exponent.abs() {
+                    1 => symbol.to_string(),
+                    exponent => format!("{symbol}^{exponent}"),
+                })
+                .collect::<Vec<_>>()
+                .join("*")
+        };
+        let (numerator, denominator) = (symbols(true), symbols(false));
+        let name = match (numerator.is_empty(), denominator.is_empty()) {
+            (_, true) => numerator,
+            (true, false) => format!("1/{denominator}"),
+            (false, false) => format!("{numerator}/{denominator}"),
+        };
+        Unit {
+            name,
+            scale: 1.0,
+            offset: 0.0,
+            dimension,
+        }
+    }
+
+    /// Converts a value in this unit to the base units of its dimension
+    pub fn to_base(&self, value: f64) -> f64 {
+        (value + self.offset) * self.scale
+    }
+
+    /// Converts a value in the base units of this unit's dimension to this unit
+    pub fn to_unit(&self, value: f64) -> f64 {
+        value / self.scale - self.offset
+    }
+}
+
+impl UnitCategory {
+    /// What the units of this category measure
+    pub fn dimension(self) -> Dimension {
+        let (length, mass, time) = (Dimension::LENGTH, Dimension::MASS, Dimension::TIME);
+        match self {
+            UnitCategory::Length => length,
+            UnitCategory::Mass => mass,
+            UnitCategory::Volume => Dimension::NONE.times(length, 3),
+            UnitCategory::Temperature => Dimension::TEMPERATURE,
+            UnitCategory::Time => time,
+            UnitCategory::Data => Dimension::DATA,
+            UnitCategory::Speed => length.times(time, -1),
+            UnitCategory::Area => Dimension::NONE.times(length, 2),
+            UnitCategory::Energy => mass.times(length, 2).times(time, -2),
+            UnitCategory::Pressure => mass.times(length, -1).times(time, -2),
+            UnitCategory::Angle => Dimension::ANGLE,
+            UnitCategory::Power => mass.times(length, 2).times(time, -3),
+            UnitCategory::Force => mass.times(length, 1).times(time, -2),
+            UnitCategory::Frequency => Dimension::NONE.times(time, -1),
+        }
+    }
+}
+
+/// Whether a character can be part of a unit's symbol
+fn is_symbol_char(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '°' | '′' | '″' | '℃' | '℉')
+}
+
+/// Superscript digits, for exponents like `m²`
+const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// The exponent at the start of `s` (`^2`, `^-1`, `2`, `²` or `⁻¹`), which is 1 if there is none,
+/// and the rest of `s`
+fn parse_exponent(s: &str) -> Option<(i8, &str)> {
+    let superscript = |c: char| {
+        SUPERSCRIPTS
+            .iter()
+            .position(|d| *d == c)
+            .and_then(|d| u32::try_from(d).ok())
+    };
+    let ascii = |c: char| c.to_digit(10);
+
+    let (negative, (exponent, rest)) = if let Some(rest) = s.strip_prefix('^') {
+        let rest = rest.trim_start();
+        match rest.strip_prefix('-') {
+            Some(rest) => (true, leading_number(rest, ascii)?),
+            None => (false, leading_number(rest, ascii)?),
+        }
+    } else if let Some(rest) = s.strip_prefix('⁻') {
+        (true, leading_number(rest, superscript)?)
+    } else if let Some(number) = leading_number(s, superscript).or_else(|| leading_number(s, ascii))
+    {
+        (false, number)
+    } else {
+        return Some((1, s));
+    };
+    Some((if negative { -exponent } else { exponent }, rest))
+}
+
+/// The number written with the digits at the start of `s`, if it starts with any, and the rest of
+/// `s`
+fn leading_number(s: &str, digit: impl Fn(char) -> Option<u32>) -> Option<(i8, &str)> {
+    let end = s.find(|c: char| digit(c).is_none()).unwrap_or(s.len());
+    let number = s[..end].chars().try_fold(0_i8, |n, c| {
+        n.checked_mul(10)?
+            .checked_add(i8::try_from(digit(c)?).ok()?)
+    })?;
+    (end > 0).then_some((number, &s[end..]))
+}
+
+/// Finds a unit in the table by its name or one of its aliases, case sensitively first
+fn find_unit(text: &str) -> Option<&'static UnitDef> {
+    let find = |text: &str| {
+        UNITS
+            .iter()
+            .find(|unit| unit.name == text || unit.aliases.contains(&text))
+    };
+    if text.is_empty() {
+        return None;
+    }
+    find(text).or_else(|| find(&text.to_lowercase()))
+}
+
+/// Finds the unit of a symbol in a compound unit: one from the table (exactly as it's written),
+/// a prefixed one, or one from the table in any case
+fn find_symbol(symbol: &str) -> Option<Unit> {
+    let exact = UNITS
+        .iter()
+        .find(|unit| unit.name == symbol || unit.aliases.contains(&symbol));
+    exact
+        .map(Unit::new)
+        .or_else(|| find_prefixed(symbol))
+        .or_else(|| find_unit(symbol).map(Unit::new))
+}
+
+/// Finds a prefixed unit, like `µm`, `GHz` or `MiB`
+fn find_prefixed(symbol: &str) -> Option<Unit> {
+    let prefixes = SI_PREFIXES
+        .iter()
+        .map(|(prefix, factor)| (prefix, factor, false))
+        .chain(
+            BINARY_PREFIXES
+                .iter()
+                .map(|(prefix, factor)| (prefix, factor, true)),
+        );
+    for (prefix, factor, binary) in prefixes {
+        let Some(rest) = symbol.strip_prefix(prefix) else {
+            continue;
+        };
+        let Some((_, name, takes_binary)) = PREFIXABLE_UNITS
+            .iter()
+            .find(|(unit_symbol, ..)| *unit_symbol == rest)
+        else {
+            continue;
+        };
+        if binary && !takes_binary {
+            continue;
+        }
+        let def = UNITS.iter().find(|unit| unit.name == *name)?;
+        return Some(Unit {
+            name: symbol.to_string(),
+            scale: factor * def.scale,
+            offset: 0.0,
+            dimension: def.category.dimension(),
+        });
+    }
+    None
+}
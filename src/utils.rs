@@ -49,6 +49,20 @@ pub fn read_config_file(file_path: &Path) -> anyhow::Result<Config> {
     }
 }
 
+/// Percent-encodes everything but the unreserved characters of RFC 3986, so the result can be
+/// put anywhere in a URL (path segment, query, fragment)
+pub fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
 // TODO: this should also work with args
 pub fn open_application(path: impl AsRef<Path>) {
     let path = path.as_ref();
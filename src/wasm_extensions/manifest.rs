@@ -0,0 +1,61 @@
+//! The `extension.toml` manifest that every WASM extension ships with
+use std::{collections::HashSet, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the manifest file in an extension's directory
+pub const MANIFEST_FILE: &str = "extension.toml";
+
+/// What a WASM extension is and what it's allowed to do
+///
+/// `component` is the path to the compiled component, relative to the manifest
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub component: String,
+    #[serde(default)]
+    pub permissions: HashSet<Permission>,
+}
+
+impl Manifest {
+    /// Reads the manifest in an extension's directory
+    pub fn read(dir: &Path) -> anyhow::Result<Self> {
+        Ok(toml::from_str(&std::fs::read_to_string(
+            dir.join(MANIFEST_FILE),
+        )?)?)
+    }
+
+    /// A human readable list of the permissions, e.g. for the install prompt
+    pub fn permissions_summary(&self) -> String {
+        if self.permissions.is_empty() {
+            return "no permissions".to_string();
+        }
+
+        let mut permissions: Vec<_> = self.permissions.iter().map(ToString::to_string).collect();
+        permissions.sort();
+        permissions.join(", ")
+    }
+}
+
+/// The capability-gated host functions an extension can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permission {
+    ClipboardWrite,
+    OpenUrl,
+    RunCommand,
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Permission::ClipboardWrite => "write to the clipboard",
+            Permission::OpenUrl => "open URLs",
+            Permission::RunCommand => "run commands",
+        })
+    }
+}
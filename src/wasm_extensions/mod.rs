@@ -0,0 +1,342 @@
+//! WASM extensions
+//!
+//! A WASM extension is a directory in `wasm-extensions/` (next to the config file) holding an
+//! [`extension.toml`](manifest::Manifest) and a WebAssembly component built against
+//! `wit/extension.wit`. Components run sandboxed in wasmtime, and can only reach the host
+//! functions their manifest asks for.
+//!
+//! Extensions aren't loaded until the user installs them, which shows the permissions they ask
+//! for and asks for confirmation. The granted permissions are stored in `granted.toml`, so an
+//! extension that later asks for more has to be installed again.
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use wasmtime::Engine;
+
+use crate::{
+    app::apps::{AppCommand, SimpleApp},
+    commands::Function,
+    utils::get_config_file_path,
+    wasm_extensions::{
+        manifest::{MANIFEST_FILE, Manifest, Permission},
+        runtime::{LoadedExtension, Provider},
+    },
+};
+
+mod manifest;
+mod runtime;
+
+/// The file the granted permissions are stored in
+const GRANTED_FILE: &str = "granted.toml";
+
+/// The directory WASM extensions are installed in
+pub fn extensions_dir() -> PathBuf {
+    get_config_file_path()
+        .parent()
+        .map(|dir| dir.join("wasm-extensions"))
+        .unwrap_or_default()
+}
+
+enum LoadState {
+    NotLoaded,
+    Loading,
+    Failed,
+    Loaded(Arc<Mutex<LoadedExtension>>),
+}
+
+struct WasmExtension {
+    manifest: Manifest,
+    dir: PathBuf,
+    installed: bool,
+    state: LoadState,
+    providers: Vec<Provider>,
+}
+
+/// Keeps track of the WASM extensions in the extensions directory
+#[derive(Clone, Default)]
+pub struct WasmHost {
+    engine: Option<Engine>,
+    extensions: Arc<Mutex<Vec<WasmExtension>>>,
+}
+
+impl WasmHost {
+    /// Reads the manifests in the extensions directory, without loading any components
+    pub fn discover() -> Self {
+        let dir = extensions_dir();
+        let granted = read_granted();
+
+        let extensions = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.join(MANIFEST_FILE).is_file())
+            .filter_map(|dir| match Manifest::read(&dir) {
+                Ok(manifest) => Some(WasmExtension {
+                    installed: granted
+                        .get(&manifest.name)
+                        .is_some_and(|granted| manifest.permissions.is_subset(granted)),
+                    manifest,
+                    dir,
+                    state: LoadState::NotLoaded,
+                    providers: Vec::new(),
+                }),
+                Err(e) => {
+                    tracing::error!(
+                        target: "wasm_extension",
+                        "Invalid manifest in {}: {e}", dir.display()
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let engine = if extensions.is_empty() {
+            None
+        } else {
+            runtime::engine()
+                .inspect_err(|e| tracing::error!(target: "wasm_extension", "{e:#}"))
+                .ok()
+        };
+
+        Self {
+            engine,
+            extensions: Arc::new(Mutex::new(extensions)),
+        }
+    }
+
+    /// The "Install extension" results for extensions that haven't been installed yet
+    pub fn install_apps(&self) -> Vec<SimpleApp> {
+        self.extensions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|extension| !extension.installed)
+            .map(|extension| {
+                let name = &extension.manifest.name;
+                SimpleApp::new_builtin(
+                    &format!("Install extension: {name}"),
+                    &format!("install {}", name.to_lowercase()),
+                    &format!("Wants to: {}", extension.manifest.permissions_summary()),
+                    AppCommand::Function(Function::InstallWasmExtension(name.clone())),
+                )
+            })
+            .collect()
+    }
+
+    /// The text shown when asking the user to confirm installing an extension
+    pub fn install_prompt(&self, name: &str) -> String {
+        let extensions = self.extensions.lock().unwrap();
+        let permissions = extensions
+            .iter()
+            .find(|extension| extension.manifest.name == name)
+            .map_or_else(String::new, |extension| {
+                extension.manifest.permissions_summary()
+            });
+
+        format!("Install {name}, allowing it to: {permissions}")
+    }
+
+    /// Grants an extension the permissions in its manifest, so it gets loaded from now on
+    pub fn install(&self, name: &str) -> anyhow::Result<()> {
+        let mut extensions = self.extensions.lock().unwrap();
+        let Some(extension) = extensions
+            .iter_mut()
+            .find(|extension| extension.manifest.name == name)
+        else {
+            anyhow::bail!("No WASM extension called {name}");
+        };
+
+        let mut granted = read_granted();
+        granted.insert(name.to_string(), extension.manifest.permissions.clone());
+        std::fs::create_dir_all(extensions_dir())?;
+        std::fs::write(
+            extensions_dir().join(GRANTED_FILE),
+            toml::to_string(&granted)?,
+        )?;
+
+        extension.installed = true;
+        tracing::info!(target: "wasm_extension", "[{name}] Installed");
+        Ok(())
+    }
+
+    /// Whether any installed extension hasn't been loaded yet
+    pub fn needs_load(&self) -> bool {
+        self.engine.is_some()
+            && self.extensions.lock().unwrap().iter().any(|extension| {
+                extension.installed && matches!(extension.state, LoadState::NotLoaded)
+            })
+    }
+
+    /// Compiles and instantiates every installed extension that isn't loaded yet
+    pub async fn load(self) {
+        let Some(engine) = self.engine.clone() else {
+            return;
+        };
+
+        let to_load: Vec<_> = self
+            .extensions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|extension| {
+                extension.installed && matches!(extension.state, LoadState::NotLoaded)
+            })
+            .map(|extension| {
+                extension.state = LoadState::Loading;
+                (
+                    extension.manifest.name.clone(),
+                    extension.dir.join(&extension.manifest.component),
+                    extension.manifest.permissions.clone(),
+                )
+            })
+            .collect();
+
+        for (name, component, permissions) in to_load {
+            let engine = engine.clone();
+            let loaded = tokio::task::spawn_blocking({
+                let name = name.clone();
+                move || LoadedExtension::load(&engine, &name, &component, permissions)
+            })
+            .await;
+
+            let (state, providers) = match loaded {
+                Ok(Ok(loaded)) => {
+                    tracing::info!(target: "wasm_extension", "[{name}] Loaded");
+                    let providers = loaded.providers().to_vec();
+                    (LoadState::Loaded(Arc::new(Mutex::new(loaded))), providers)
+                }
+                Ok(Err(e)) => {
+                    tracing::error!(target: "wasm_extension", "[{name}] Failed to load: {e:#}");
+                    (LoadState::Failed, Vec::new())
+                }
+                Err(_) => (LoadState::Failed, Vec::new()),
+            };
+
+            if let Some(extension) = self
+                .extensions
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .find(|extension| extension.manifest.name == name)
+            {
+                extension.state = state;
+                extension.providers = providers;
+            }
+        }
+    }
+
+    /// If the query starts with a keyword of a loaded extension's provider, returns a future that
+    /// asks that extension for results
+    pub fn keyword_search(
+        &self,
+        query: &str,
+    ) -> Option<impl Future<Output = Vec<SimpleApp>> + use<>> {
+        let (keyword, rest) = query.trim_start().split_once(char::is_whitespace)?;
+
+        let (name, loaded, provider) =
+            self.extensions
+                .lock()
+                .unwrap()
+                .iter()
+                .find_map(|extension| {
+                    let LoadState::Loaded(loaded) = &extension.state else {
+                        return None;
+                    };
+                    let provider = extension
+                        .providers
+                        .iter()
+                        .find(|provider| provider.keyword.eq_ignore_ascii_case(keyword))?
+                        .clone();
+                    Some((extension.manifest.name.clone(), loaded.clone(), provider))
+                })?;
+
+        let rest = rest.trim().to_string();
+        let extensions = self.extensions.clone();
+        Some(async move {
+            let items = tokio::task::spawn_blocking(move || {
+                loaded.lock().unwrap().search(&provider.keyword, &rest)
+            })
+            .await;
+
+            match items {
+                Ok(Ok(items)) => items
+                    .into_iter()
+                    .map(|item| {
+                        let desc = item.subtitle.unwrap_or_else(|| provider.title.clone());
+                        SimpleApp::new_builtin(
+                            &item.title,
+                            "",
+                            &desc,
+                            AppCommand::Function(Function::WasmExtensionAction(
+                                name.clone(),
+                                item.id,
+                            )),
+                        )
+                    })
+                    .collect(),
+                Ok(Err(e)) => {
+                    tracing::error!(target: "wasm_extension", "[{name}] Search failed: {e:#}");
+                    unload(&extensions, &name);
+                    Vec::new()
+                }
+                Err(_) => Vec::new(),
+            }
+        })
+    }
+
+    /// Returns a future that tells an extension one of its results was run
+    pub fn invoke(&self, name: &str, id: &str, query: &str) -> impl Future<Output = ()> + use<> {
+        let loaded = self
+            .extensions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|extension| extension.manifest.name == name)
+            .and_then(|extension| match &extension.state {
+                LoadState::Loaded(loaded) => Some(loaded.clone()),
+                _ => None,
+            });
+
+        let (name, id, query) = (name.to_string(), id.to_string(), query.to_string());
+        let extensions = self.extensions.clone();
+        async move {
+            let Some(loaded) = loaded else {
+                tracing::warn!(target: "wasm_extension", "[{name}] Not loaded, can't run {id}");
+                return;
+            };
+
+            let result =
+                tokio::task::spawn_blocking(move || loaded.lock().unwrap().invoke(&id, &query))
+                    .await;
+            if let Ok(Err(e)) = result {
+                tracing::error!(target: "wasm_extension", "[{name}] Invoke failed: {e:#}");
+                unload(&extensions, &name);
+            }
+        }
+    }
+}
+
+/// Drops an extension's instance after a trap (which leaves it unusable), so it gets loaded again
+/// the next time it's needed
+fn unload(extensions: &Mutex<Vec<WasmExtension>>, name: &str) {
+    if let Some(extension) = extensions
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .find(|extension| extension.manifest.name == name)
+    {
+        extension.state = LoadState::NotLoaded;
+        extension.providers.clear();
+    }
+}
+
+fn read_granted() -> HashMap<String, HashSet<Permission>> {
+    std::fs::read_to_string(extensions_dir().join(GRANTED_FILE))
+        .ok()
+        .and_then(|granted| toml::from_str(&granted).ok())
+        .unwrap_or_default()
+}
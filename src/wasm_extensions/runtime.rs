@@ -0,0 +1,154 @@
+//! The sandboxed wasmtime runtime that WASM extensions run in
+//!
+//! Extensions don't get WASI, so the only way they can touch the outside world is through the
+//! functions in `wit/extension.wit`, each of which checks the extension's permissions first.
+use std::{collections::HashSet, path::Path, process::Command};
+
+use arboard::Clipboard;
+use wasmtime::{
+    Config, Engine, Store, StoreLimits, StoreLimitsBuilder,
+    component::{Component, HasSelf, Linker},
+};
+
+use crate::wasm_extensions::manifest::Permission;
+
+wasmtime::component::bindgen!({
+    path: "wit/extension.wit",
+    world: "extension",
+});
+
+/// How much fuel (roughly, wasm instructions) a single call into an extension gets
+const FUEL_PER_CALL: u64 = 250_000_000;
+
+/// The most linear memory an extension can use
+const MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Creates the engine shared by every extension
+pub fn engine() -> anyhow::Result<Engine> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// The data each extension's store holds
+struct HostState {
+    name: String,
+    permissions: HashSet<Permission>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn check(&self, permission: Permission) -> Result<(), String> {
+        if self.permissions.contains(&permission) {
+            Ok(())
+        } else {
+            tracing::warn!(
+                target: "wasm_extension",
+                "[{}] Tried to {permission} without permission", self.name
+            );
+            Err(format!("Permission denied: {permission}"))
+        }
+    }
+}
+
+// The types interface has no functions, but still needs a (empty) host impl
+impl rustcast::extension::types::Host for HostState {}
+
+impl rustcast::extension::host::Host for HostState {
+    fn log(&mut self, message: String) {
+        tracing::info!(target: "wasm_extension", "[{}] {message}", self.name);
+    }
+
+    fn clipboard_write(&mut self, text: String) -> Result<(), String> {
+        self.check(Permission::ClipboardWrite)?;
+        Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| e.to_string())
+    }
+
+    fn open_url(&mut self, url: String) -> Result<(), String> {
+        self.check(Permission::OpenUrl)?;
+        let parsed = url::Url::parse(&url).map_err(|e| e.to_string())?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!(
+                "Only http(s) URLs can be opened, not {}",
+                parsed.scheme()
+            ));
+        }
+        open::that(parsed.as_str()).map_err(|e| e.to_string())
+    }
+
+    fn run_command(&mut self, command: String, args: Vec<String>) -> Result<(), String> {
+        self.check(Permission::RunCommand)?;
+        Command::new(command)
+            .args(args)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// An instantiated extension
+pub struct LoadedExtension {
+    store: Store<HostState>,
+    bindings: Extension,
+    providers: Vec<Provider>,
+}
+
+impl LoadedExtension {
+    /// Compiles and instantiates the component, then asks it for its providers
+    pub fn load(
+        engine: &Engine,
+        name: &str,
+        component: &Path,
+        permissions: HashSet<Permission>,
+    ) -> anyhow::Result<Self> {
+        let component = Component::from_file(engine, component)?;
+
+        let mut linker = Linker::new(engine);
+        Extension::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+
+        let mut store = Store::new(
+            engine,
+            HostState {
+                name: name.to_string(),
+                permissions,
+                limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+
+        let bindings = Extension::instantiate(&mut store, &component, &linker)?;
+
+        let mut extension = Self {
+            store,
+            bindings,
+            providers: Vec::new(),
+        };
+        extension.refuel()?;
+        extension.providers = extension.bindings.call_register(&mut extension.store)?;
+        Ok(extension)
+    }
+
+    /// The search providers the extension registered
+    pub fn providers(&self) -> &[Provider] {
+        &self.providers
+    }
+
+    /// Asks the extension for results
+    pub fn search(&mut self, keyword: &str, query: &str) -> anyhow::Result<Vec<Item>> {
+        self.refuel()?;
+        self.bindings.call_search(&mut self.store, keyword, query)
+    }
+
+    /// Tells the extension one of its results was run
+    pub fn invoke(&mut self, id: &str, query: &str) -> anyhow::Result<()> {
+        self.refuel()?;
+        self.bindings.call_invoke(&mut self.store, id, query)
+    }
+
+    fn refuel(&mut self) -> anyhow::Result<()> {
+        self.store.set_fuel(FUEL_PER_CALL)
+    }
+}